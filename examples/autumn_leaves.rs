@@ -1,4 +1,4 @@
-use musika_rs::{Bar, A, B, C, D, E, F, G};
+use musika_rs::{Bar, Chart, Key, Spelling, A, B, C, D, E, F, G};
 
 fn main() {
     let chord = E.dom7();
@@ -12,10 +12,7 @@ fn main() {
     let bar5 = Bar::new().with_chord(B.min7b5(), 1);
     let bar6 = Bar::new().with_chord(E.dom7(), 1);
     let bar7 = Bar::new().with_chord(A.min7(), 1);
-    let s = [bar0, bar1, bar2, bar3, bar4, bar5, bar6, bar7]
-        .into_iter()
-        .map(|bar| format!("{bar}"))
-        .collect::<Vec<_>>()
-        .join(" | ");
-    println!("LH: | {s} |");
+    let bars = [bar0, bar1, bar2, bar3, bar4, bar5, bar6, bar7];
+    let chart = Chart::new().with_spelling(Spelling::Key(Key::minor(E)));
+    println!("{}", chart.render(&bars));
 }
@@ -1,71 +1,64 @@
-use musika_rs::*;
+use musika_rs::{chords::Chords, musicxml::MusicXml, score::*, *};
+
+/// A measure of two chords of a half note each.
+fn two_beats(first: Chords, second: Chords) -> Measure {
+    Measure::chords(Bar::new().with_chord(first, 2).with_chord(second, 2))
+}
 
 fn key_c_major() {
     // Bar1
-    let bar1 = Bar::new().with_chord(D.min9(), 2).with_chord(D.min9(), 2);
-    let bar2 = Bar::new().with_chord(G.dom13(), 2).with_chord(G.dom13(), 2);
-
-    let line1 = [&bar1, &bar2, &bar1, &bar2];
-    let s = line1
-        .iter()
-        .map(|b| format!("{}", b))
-        .collect::<Vec<_>>()
-        .as_slice()
-        .join(" | ");
-    println!("| {s} |");
+    let line1 = Section::new("A")
+        .with_measure(two_beats(D.min9(), D.min9()))
+        .with_measure(two_beats(G.dom13(), G.dom13()))
+        .with_measure(two_beats(D.min9(), D.min9()))
+        .with_measure(two_beats(G.dom13(), G.dom13()));
 
-    let bar3 = Bar::new().with_chord(C.maj9(), 2).with_chord(C.maj9(), 2);
-    let bar4 = Bar::new().with_chord(F.maj13(), 2).with_chord(F.maj13(), 2);
-    let bar5 = Bar::new()
-        .with_chord(A.dom13b9b13(), 2)
-        .with_chord(A.dom13b9b13(), 2);
+    let line2 = Section::new("B")
+        .with_measure(two_beats(C.maj9(), C.maj9()))
+        .with_measure(two_beats(F.maj13(), F.maj13()))
+        .with_measure(two_beats(C.maj9(), C.maj9()))
+        .with_measure(two_beats(A.dom13b9b13(), A.dom13b9b13()));
 
-    let line2 = [&bar3, &bar4, &bar3, &bar5];
-    let s = line2
-        .iter()
-        .map(|b| format!("{}", b))
-        .collect::<Vec<_>>()
-        .as_slice()
-        .join(" | ");
-    println!("| {s} |");
+    let score = Score::new("Key: C major")
+        .with_section(line1)
+        .with_section(line2);
+    let chart = Chart::new().with_spelling(Spelling::Key(Key::major(C)));
+    println!("{}", score.title());
+    println!("{}", chart.render_score(&score, Staff::Chords));
 }
 
 fn key_f_major() {
     // Bar1
     // D -> G [D, F, G]
     // G -> C [G, A, B]
-
-    let bar1 = Bar::new().with_chord(G.min9(), 2).with_chord(G.min9(), 2);
-    let bar2 = Bar::new().with_chord(C.dom13(), 2).with_chord(G.dom13(), 2);
-
-    let line1 = [&bar1, &bar2, &bar1, &bar2];
-    let s = line1
-        .iter()
-        .map(|b| format!("{}", b))
-        .collect::<Vec<_>>()
-        .as_slice()
-        .join(" | ");
-    println!("| {s} |");
+    let line1 = Section::new("A")
+        .with_measure(two_beats(G.min9(), G.min9()))
+        .with_measure(two_beats(C.dom13(), G.dom13()))
+        .with_measure(two_beats(G.min9(), G.min9()))
+        .with_measure(two_beats(C.dom13(), G.dom13()));
 
     // C -> F [C, D, E, F]
     // F -> Bb
-    let bar3 = Bar::new().with_chord(C.maj9(), 2).with_chord(C.maj9(), 2);
-    let bar4 = Bar::new()
-        .with_chord(A_SHARP.maj13(), 2)
-        .with_chord(A_SHARP.maj13(), 2);
     // A -> D [A, B, C, D]
-    let bar5 = Bar::new()
-        .with_chord(D.dom13b9b13(), 2)
-        .with_chord(D.dom13b9b13(), 2);
+    let line2 = Section::new("B")
+        .with_measure(two_beats(C.maj9(), C.maj9()))
+        .with_measure(two_beats(A_SHARP.maj13(), A_SHARP.maj13()))
+        .with_measure(two_beats(C.maj9(), C.maj9()))
+        .with_measure(two_beats(D.dom13b9b13(), D.dom13b9b13()));
+
+    let score = Score::new("Key: F major")
+        .with_section(line1)
+        .with_section(line2);
+    let chart = Chart::new().with_spelling(Spelling::Key(Key::major(F)));
+    println!("{}", score.title());
+    println!("{}", chart.render_score(&score, Staff::Chords));
 
-    let line2 = [&bar3, &bar4, &bar3, &bar5];
-    let s = line2
-        .iter()
-        .map(|b| format!("{}", b))
-        .collect::<Vec<_>>()
-        .as_slice()
-        .join(" | ");
-    println!("| {s} |");
+    let path = std::env::temp_dir().join("exercise5.musicxml");
+    let xml = MusicXml::from_score(&score).with_key(Key::major(F));
+    match xml.write(&path) {
+        Ok(()) => println!("{}", path.display()),
+        Err(e) => eprintln!("cannot write {}: {e}", path.display()),
+    }
 }
 
 /// [Resource](https://www.youtube.com/watch?v=WrLFCznbNMw)
@@ -74,8 +67,6 @@ fn key_f_major() {
 fn main() {
     println!("Exercise 5:");
 
-    println!("Key: C major");
     key_c_major();
-    println!("Key: F major");
     key_f_major();
 }
@@ -0,0 +1,37 @@
+use musika_rs::{fretboard::Fretboard, Bar, A, C, D, E, F, G};
+
+fn main() {
+    let guitar = Fretboard::guitar();
+    println!("Tuning: {}", guitar.tuning());
+
+    for chord in [C.maj(), A.min7(), D.min7(), G.dom7(), C.maj7()] {
+        println!();
+        println!("{chord:X}");
+        for fingering in guitar.chord_shapes(&chord).iter().take(6) {
+            println!("  {fingering}");
+        }
+    }
+
+    for chord in [D.min7(), G.dom7(), C.maj7()] {
+        if let Some(chord_box) = guitar.chord_box(&chord) {
+            println!();
+            println!("{chord_box}");
+        }
+    }
+
+    let bars = [
+        Bar::new()
+            .with_note(C, 8)
+            .with_note(E, 8)
+            .with_note(F, 8)
+            .with_note(G, 8)
+            .with_note(A, 8)
+            .with_note(G, 8)
+            .with_note(F, 8)
+            .with_note(E, 8),
+        Bar::new().with_chord(D.min7(), 2).with_chord(G.dom7(), 2),
+        Bar::new().with_chord(C.maj7(), 1),
+    ];
+    println!();
+    println!("{}", guitar.tab(&bars));
+}
@@ -1,4 +1,10 @@
-use musika_rs::{Bar, C, D, G};
+use musika_rs::{
+    groove::{Groove, Swing},
+    midi::{Midi, Track},
+    timeline::TempoMap,
+    voicings::{self, Register},
+    Bar, Chart, Key, C, D, G,
+};
 
 fn main() {
     let dm7 = D.min7();
@@ -33,10 +39,26 @@ fn main() {
         .with_silence(4);
     // println!("{bar4}");
 
-    let s = [bar1, bar2, bar3, bar4]
-        .into_iter()
-        .map(|b| format!("{b}"))
-        .collect::<Vec<_>>()
-        .join(" | ");
-    println!("| {s} |");
+    let bars = [bar1, bar2, bar3, bar4];
+    println!("{}", Chart::new().render(&bars));
+
+    let piano = Track::new("Piano")
+        .with_groove(Groove::new().with_swing(Swing::triplet()))
+        .with_bars(bars);
+    let midi = Midi::new()
+        .with_tempo(TempoMap::new(120.0))
+        .with_key(Key::major(C))
+        .with_track(piano);
+    let path = std::env::temp_dir().join("jazz_251.mid");
+    match midi.write(&path) {
+        Ok(()) => println!("{}", path.display()),
+        Err(e) => eprintln!("cannot write {}: {e}", path.display()),
+    }
+
+    let progression = [D.min7(), G.dom7(), C.maj7()];
+    if let Some(voiced) = voicings::voice_lead(&progression, &Register::left_hand()) {
+        for chord in voiced {
+            println!("{chord:X}");
+        }
+    }
 }
@@ -1,46 +1,37 @@
-use musika_rs::{scales, Bar, A, B, C, D, E, F, G};
+use musika_rs::{
+    lilypond::LilyPond,
+    scales,
+    score::{Measure, Score, Section, Staff},
+    Bar, A, B, C, D, E, F, G,
+};
 
 fn exercise1() {
     println!();
-    println!("Exercise 1:");
 
-    let rh_bar = Bar::new()
-        .with_note(A, 8)
-        .with_note(C, 8)
-        .with_note(D, 8)
-        .with_note(E, 8)
-        .with_note(G, 8)
-        .with_note(A, 8)
-        .with_silence(8)
-        .with_silence(8);
-
-    println!();
-
-    // Bar 1
-    println!("RH: {rh_bar:X}");
-    let lh_bar1 = Bar::new().with_chord(D.min7(), 1);
-    println!("LH: {lh_bar1:X}");
-
-    println!();
-
-    // Bar 2
-    println!("RH: {rh_bar:X}");
-    let lh_bar2 = Bar::new().with_chord(G.dom7(), 1);
-    println!("LH: {lh_bar2:X}");
-
-    println!();
-
-    // Bar 3
-    println!("RH: {rh_bar:X}");
-    let lh_bar3 = Bar::new().with_chord(C.maj7(), 1);
-    println!("LH: {lh_bar3:X}");
-
-    println!();
-
-    // Bar 4
-    println!("RH: {rh_bar:X}");
-    let lh_bar4 = Bar::new().with_silence(1);
-    println!("LH: {lh_bar4:X}");
+    let rh_bar = || {
+        Bar::new()
+            .with_note(A, 8)
+            .with_note(C, 8)
+            .with_note(D, 8)
+            .with_note(E, 8)
+            .with_note(G, 8)
+            .with_note(A, 8)
+            .with_silence(8)
+            .with_silence(8)
+    };
+    let measure = |lh_bar: Bar| {
+        Measure::new()
+            .with_bar(Staff::RightHand, rh_bar())
+            .with_bar(Staff::LeftHand, lh_bar)
+    };
+
+    let section = Section::new("A")
+        .with_measure(measure(Bar::new().with_chord(D.min7(), 1)))
+        .with_measure(measure(Bar::new().with_chord(G.dom7(), 1)))
+        .with_measure(measure(Bar::new().with_chord(C.maj7(), 1)))
+        .with_measure(measure(Bar::new().with_silence(1)));
+    let score = Score::new("Exercise 1:").with_section(section);
+    println!("{score:X}");
 }
 
 fn exercise2() {
@@ -104,7 +95,8 @@ fn exercise3() {
         .with_note(F, 8)
         .with_note(G, 8)
         .with_note(A, 8)
-        .with_note(C, 8);
+        .with_note(C, 8)
+        .padded();
     println!("RH: {rh_bar1:X}");
     let lh_bar1 = Bar::new().with_chord(D.min7(), 1);
     println!("LH: {lh_bar1}");
@@ -117,7 +109,8 @@ fn exercise3() {
         .with_note(C, 8)
         .with_note(D, 8)
         .with_note(E, 8)
-        .with_note(G, 8);
+        .with_note(G, 8)
+        .padded();
     println!("RH: {rh_bar2:X}");
     let lh_bar2 = Bar::new().with_chord(G.dom7(), 1);
     println!("LH: {lh_bar2}");
@@ -130,7 +123,8 @@ fn exercise3() {
         .with_note(G, 8)
         .with_note(A, 8)
         .with_note(B, 8)
-        .with_note(D, 8);
+        .with_note(D, 8)
+        .padded();
     println!("RH: {rh_bar3:X}");
     let lh_bar3 = Bar::new().with_chord(C.maj7(), 1);
     println!("LH: {lh_bar3}");
@@ -143,12 +137,23 @@ fn exercise3() {
         .with_note(G, 8)
         .with_note(A, 8)
         .with_note(B, 8)
-        .with_note(D, 8);
+        .with_note(D, 8)
+        .padded();
     println!("RH: {rh_bar4:X}");
     let lh_bar4 = Bar::new().with_chord(C.maj7(), 1);
     println!("LH: {lh_bar4}");
 
     println!();
+
+    let path = std::env::temp_dir().join("exercise3.ly");
+    let ly = LilyPond::new("Exercise 3").with_piano(
+        [&rh_bar1, &rh_bar2, &rh_bar3, &rh_bar4],
+        [&lh_bar1, &lh_bar2, &lh_bar3, &lh_bar4],
+    );
+    match ly.write(&path) {
+        Ok(()) => println!("{}", path.display()),
+        Err(e) => eprintln!("cannot write {}: {e}", path.display()),
+    }
 }
 
 fn main() {
@@ -0,0 +1,48 @@
+use musika_rs::{
+    chords::Chord,
+    synth::{Instrument, Part, Synth},
+    timeline::TempoMap,
+    tuning::{Temperament, Tuning},
+    C,
+};
+
+fn main() {
+    let temperaments = [
+        Temperament::Equal,
+        Temperament::Just,
+        Temperament::Pythagorean,
+        Temperament::Meantone,
+        Temperament::Werckmeister,
+        Temperament::Vallotti,
+    ];
+
+    let triad = C.maj();
+    let notes = triad.notes().copied().collect::<Vec<_>>();
+    for temperament in temperaments {
+        let tuning = Tuning::new(temperament, C);
+        println!("{triad} in {temperament}:");
+        for note in &notes {
+            println!(
+                "  {note}: {:8.3} Hz ({:+6.2} cents)",
+                tuning.frequency(*note),
+                tuning.cents(*note)
+            );
+        }
+        println!(
+            "  third {:.2} cents, fifth {:.2} cents",
+            tuning.interval(notes[0], notes[1]),
+            tuning.interval(notes[0], notes[2])
+        );
+
+        let synth = Synth::new()
+            .with_tempo(TempoMap::new(60.0))
+            .with_tuning(tuning)
+            .with_part(Part::new(Instrument::piano()).with_chord(C.maj(), 1));
+        let name = format!("{temperament:?}").to_lowercase();
+        let path = std::env::temp_dir().join(format!("triad_{name}.wav"));
+        match synth.write(&path) {
+            Ok(()) => println!("  written to {}", path.display()),
+            Err(e) => eprintln!("cannot write {}: {e}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use musika_rs::{
+    voicings::{self, Register, UpperStructure, VoicingKind},
+    C, D, G,
+};
+
+fn main() {
+    let kinds = [
+        VoicingKind::Closed,
+        VoicingKind::Open,
+        VoicingKind::Shell,
+        VoicingKind::RootlessA,
+        VoicingKind::RootlessB,
+        VoicingKind::Drop2,
+        VoicingKind::Drop3,
+        VoicingKind::Drop24,
+        VoicingKind::Spread,
+        VoicingKind::Quartal,
+        VoicingKind::SoWhat,
+        VoicingKind::UpperStructure(UpperStructure::II),
+    ];
+
+    let register = Register::piano();
+    for chord in [D.min9(), G.dom13(), C.maj9()] {
+        println!();
+        for kind in kinds {
+            match voicings::voice(&chord, kind, &register) {
+                Some(voicing) => println!("{voicing:x}"),
+                None => println!("{chord} {kind}: not available"),
+            }
+        }
+    }
+}
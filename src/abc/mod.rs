@@ -0,0 +1,234 @@
+//! ABC notation, the plain text format of the folk and jazz tune collections.
+//!
+//! A [`Tune`] holds the header of an ABC tune (number, title, unit note length, key, tempo),
+//! the bars of its voices, the chord symbols written above the first voice, and the marks of
+//! its form (repeats, endings, segno, coda, D.C. and D.S.). A tune is written with
+//! [`Tune::to_abc`], and read back with [`Tune::from_abc`].
+//!
+//! # Example
+//! ```
+//! use musika_rs::{abc::*, score::Mark, *};
+//!
+//! let melody = vec![
+//!     Bar::new().with_note(E, 4).with_note(D, 4).with_note(C, 2),
+//!     Bar::new().with_note(G, 2).with_note(C + OCTAVE, 2),
+//! ];
+//! let chords = vec![Bar::new().with_chord(C.maj(), 1), Bar::new().with_chord(G.dom7(), 1)];
+//! let tune = Tune::new("Little Tune")
+//!     .with_voice("1", melody)
+//!     .with_chords(chords)
+//!     .with_mark(0, Mark::RepeatStart)
+//!     .with_mark(1, Mark::RepeatEnd(2));
+//!
+//! let abc = tune.to_abc();
+//! assert!(abc.ends_with("|: \"C\"E2 D2 C4 | \"G7\"G4 c4 :|\n"));
+//! assert_eq!(Tune::from_abc(&abc).unwrap().to_abc(), abc);
+//! ```
+
+mod read;
+mod write;
+
+use crate::{score::Mark, Bar, Duration, Key, EIGHTH};
+
+pub use read::AbcError;
+
+/// An ABC tune.
+pub struct Tune {
+    number: u32,
+    title: String,
+    unit: Duration,
+    key: Key,
+    tempo: Option<(Duration, u32)>,
+    voices: Vec<(String, Vec<Bar>)>,
+    chords: Vec<Bar>,
+    marks: Vec<Vec<Mark>>,
+    warnings: Vec<String>,
+}
+
+impl Tune {
+    /// A tune numbered 1, in C major, with the eighth note as unit note length.
+    pub fn new(title: &str) -> Self {
+        Self {
+            number: 1,
+            title: title.to_string(),
+            unit: EIGHTH,
+            key: Key::major(crate::C),
+            tempo: None,
+            voices: vec![],
+            chords: vec![],
+            marks: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Sets the reference number of the tune (the `X:` field).
+    pub fn with_number(self, number: u32) -> Self {
+        Self { number, ..self }
+    }
+
+    /// Sets the unit note length (the `L:` field): the durations are written as multiples of
+    /// it.
+    ///
+    /// # Panics
+    /// Panics if the unit is zero.
+    pub fn with_unit(self, unit: Duration) -> Self {
+        assert!(!unit.is_zero(), "Tune: the unit note length is zero");
+        Self { unit, ..self }
+    }
+
+    /// Sets the key (the `K:` field), which gives the key signature and the spelling of the
+    /// notes.
+    pub fn with_key(self, key: Key) -> Self {
+        Self { key, ..self }
+    }
+
+    /// Sets the tempo (the `Q:` field), in beats per minute of the given beat.
+    pub fn with_tempo(self, beat: Duration, bpm: u32) -> Self {
+        Self {
+            tempo: Some((beat, bpm)),
+            ..self
+        }
+    }
+
+    /// Adds a voice (the `V:` field), one bar per measure.
+    pub fn with_voice(self, id: &str, bars: impl IntoIterator<Item = Bar>) -> Self {
+        let mut voices = self.voices;
+        voices.push((id.to_string(), bars.into_iter().collect()));
+        Self { voices, ..self }
+    }
+
+    /// Writes the chords of the bars as chord symbols above the first voice.
+    pub fn with_chords(self, bars: impl IntoIterator<Item = Bar>) -> Self {
+        Self {
+            chords: bars.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Adds a mark of the form to a measure, from 0.
+    pub fn with_mark(self, measure: usize, mark: Mark) -> Self {
+        let mut marks = self.marks;
+        if marks.len() <= measure {
+            marks.resize(measure + 1, vec![]);
+        }
+        marks[measure].push(mark);
+        Self { marks, ..self }
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn unit(&self) -> Duration {
+        self.unit
+    }
+
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
+    /// The beat and the beats per minute, if the tune has a tempo.
+    pub fn tempo(&self) -> Option<(Duration, u32)> {
+        self.tempo
+    }
+
+    /// Returns the voices of the tune, with their id and a bar per measure.
+    pub fn voices(&self) -> impl Iterator<Item = (&str, &[Bar])> {
+        self.voices
+            .iter()
+            .map(|(id, bars)| (id.as_str(), bars.as_slice()))
+    }
+
+    /// Returns the chord symbols, with a bar per measure (a measure without chord symbol
+    /// being a silence), or nothing if the tune has no chord symbol.
+    pub fn chords(&self) -> &[Bar] {
+        &self.chords
+    }
+
+    /// Returns the marks of the form of a measure, from 0.
+    pub fn marks(&self, measure: usize) -> impl Iterator<Item = &Mark> {
+        self.marks.get(measure).into_iter().flatten()
+    }
+
+    /// Returns what was skipped when the tune was read, eg. "line 4: skipped the unknown
+    /// chord \"Csus4\"".
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// Reads the first tune of an ABC text.
+    ///
+    /// The notes, the rests, the chords (`[CEG]`), the tuplets, the broken rhythms and the
+    /// tied notes of a bar are read into the bars of the voices, the voices of a bar being
+    /// separated by `&`. The bar lines give the repeats and the endings, and the
+    /// decorations `!segno!`, `!coda!`, `!fine!`, `!dacoda!`, `!D.C.!` and `!D.S.!` give
+    /// the other marks. The other decorations, the slurs, the lyrics and the other fields
+    /// are skipped; the grace notes and the chord symbols which are not known are skipped
+    /// with a warning. The modes other than major and minor are read as the major key with
+    /// the same key signature.
+    pub fn from_abc(text: &str) -> Result<Self, AbcError> {
+        read::tunes(text)?
+            .into_iter()
+            .next()
+            .ok_or(AbcError::NoTune)
+    }
+
+    /// Reads all the tunes of an ABC text (a tunebook).
+    pub fn tunebook(text: &str) -> Result<Vec<Self>, AbcError> {
+        read::tunes(text)
+    }
+
+    /// Reads the first tune of an `.abc` file.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, AbcError> {
+        Self::from_abc(&std::fs::read_to_string(path)?)
+    }
+
+    /// The ABC text of the tune.
+    ///
+    /// The durations which cannot be written as a single note are written as tied notes,
+    /// and the triplets as `(3` tuplets. A repeat played more than twice is written as a
+    /// simple repeat.
+    pub fn to_abc(&self) -> String {
+        write::tune(self)
+    }
+
+    /// Writes the `.abc` file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_abc())
+    }
+}
+
+/// The pitch class of a note letter (C being 0).
+fn step(letter: char) -> i8 {
+    match letter.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        _ => 11,
+    }
+}
+
+/// The alteration of a note letter by the key signature.
+fn key_alter(key: Key, letter: char) -> i8 {
+    let signature = key.signature();
+    let letter = letter.to_ascii_uppercase();
+    match signature >= 0 {
+        true => "FCGDAEB"
+            .chars()
+            .take(signature as usize)
+            .any(|l| l == letter) as i8,
+        false => {
+            -("BEADGCF"
+                .chars()
+                .take(signature.unsigned_abs() as usize)
+                .any(|l| l == letter) as i8)
+        }
+    }
+}
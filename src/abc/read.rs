@@ -0,0 +1,1058 @@
+use std::{collections::HashMap, fmt::Display};
+
+use super::{key_alter, step, Tune};
+use crate::{
+    chords::Chords,
+    score::{Jump, Mark},
+    Bar, BarElement, Duration, Key, Note, TimeSignature, Voice, EIGHTH, SIXTEENTH,
+};
+
+/// The errors of reading an ABC text.
+#[derive(Debug)]
+pub enum AbcError {
+    Io(std::io::Error),
+    /// The text has no tune.
+    NoTune,
+    /// A field or a symbol of a line cannot be read.
+    Syntax(usize, String),
+}
+
+/// The longest note in whole notes, and the most measures of a multi-measure rest.
+const LONGEST: u32 = 64;
+const MOST_MEASURES: u32 = 1000;
+
+/// The state of a voice being read: its measures, and the measure being read.
+struct VoiceReader {
+    id: String,
+    time: TimeSignature,
+    unit: Duration,
+    key: Key,
+    bars: Vec<Bar>,
+    chords: Vec<Bar>,
+    marks: Vec<Vec<Mark>>,
+    has_chords: bool,
+    /// The voices of the measure being read, separated by `&`.
+    parts: Vec<Vec<BarElement>>,
+    symbols: Vec<(Duration, Option<Chords>)>,
+    measure_marks: Vec<Mark>,
+    accidentals: HashMap<(char, i8), i8>,
+    ending: Option<u8>,
+    /// The ratio of the durations of a tuplet, and the number of notes left in it.
+    tuplet: Option<(u32, u32, u32)>,
+    /// The ratio of the duration of the next note, after a broken rhythm.
+    broken: Option<(u32, u32)>,
+    tied: bool,
+}
+
+/// The state of a tune being read.
+struct TuneReader {
+    tune: Tune,
+    time: TimeSignature,
+    unit: Option<Duration>,
+    voices: Vec<VoiceReader>,
+    voice: usize,
+    in_body: bool,
+    line: usize,
+}
+
+/// A line of music being read.
+struct Line<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+/// Reads the tunes of an ABC text, each one starting with its `X:` field.
+pub(super) fn tunes(text: &str) -> Result<Vec<Tune>, AbcError> {
+    let mut tunes = vec![];
+    let mut reader: Option<TuneReader> = None;
+    let has_numbers = text.lines().any(|l| l.starts_with("X:"));
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        if line.starts_with("X:") || (!has_numbers && reader.is_none() && !line.trim().is_empty()) {
+            tunes.extend(reader.take().map(TuneReader::finish));
+            reader = Some(TuneReader::new());
+        }
+        let Some(tune) = reader.as_mut() else {
+            continue;
+        };
+        if line.trim().is_empty() {
+            // A blank line ends the tune, but not its header.
+            if tune.in_body {
+                tunes.extend(reader.take().map(TuneReader::finish));
+            }
+            continue;
+        }
+        tune.line = number;
+        tune.read_line(line)?;
+    }
+    tunes.extend(reader.map(TuneReader::finish));
+    Ok(tunes)
+}
+
+impl TuneReader {
+    fn new() -> Self {
+        Self {
+            tune: Tune::new(""),
+            time: TimeSignature::default(),
+            unit: None,
+            voices: vec![],
+            voice: 0,
+            in_body: false,
+            line: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> AbcError {
+        AbcError::Syntax(self.line, message)
+    }
+
+    fn warn(&mut self, warning: String) {
+        let warning = format!("line {}: {warning}", self.line);
+        if !self.tune.warnings.contains(&warning) {
+            self.tune.warnings.push(warning);
+        }
+    }
+
+    fn read_line(&mut self, line: &str) -> Result<(), AbcError> {
+        let line = line.split('%').next().unwrap_or_default();
+        let mut chars = line.chars();
+        let is_field = matches!(
+            (chars.next(), chars.next()),
+            (Some(c), Some(':')) if c.is_ascii_alphabetic()
+        );
+        match is_field {
+            true => self.field(&line[..1], line[2..].trim()),
+            false if self.in_body => self.music(line),
+            false => {
+                self.in_body = true;
+                self.music(line)
+            }
+        }
+    }
+
+    /// Reads a field, in the header or in the body of the tune.
+    fn field(&mut self, name: &str, value: &str) -> Result<(), AbcError> {
+        match name {
+            "X" => self.tune.number = value.parse().unwrap_or(1),
+            "T" if self.tune.title.is_empty() && !self.in_body => {
+                self.tune.title = value.to_string()
+            }
+            "M" => {
+                let time =
+                    meter(value).ok_or_else(|| self.error(format!("invalid meter {value:?}")))?;
+                match self.in_body {
+                    true => self.current().time = time,
+                    false => self.time = time,
+                }
+            }
+            "L" => {
+                let unit = fraction(value)
+                    .filter(|u| !u.is_zero())
+                    .ok_or_else(|| self.error(format!("invalid unit note length {value:?}")))?;
+                match self.in_body {
+                    true => self.current().unit = unit,
+                    false => self.unit = Some(unit),
+                }
+            }
+            "Q" if !self.in_body => self.tune.tempo = self.tempo(value)?,
+            "K" => {
+                let key = key(value).ok_or_else(|| self.error(format!("invalid key {value:?}")))?;
+                match self.in_body {
+                    true => self.current().key = key,
+                    false => {
+                        // The end of the header: its fields apply to the voices declared in it.
+                        self.tune.key = key;
+                        self.in_body = true;
+                        let unit = self.unit();
+                        for voice in &mut self.voices {
+                            voice.time = self.time.clone();
+                            voice.unit = unit;
+                            voice.key = key;
+                        }
+                    }
+                }
+            }
+            "V" => {
+                let id = value.split_whitespace().next().unwrap_or("1");
+                self.select(id);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The tempo, eg. "1/4=120", or a number of unit note lengths per minute.
+    fn tempo(&self, value: &str) -> Result<Option<(Duration, u32)>, AbcError> {
+        let error = || self.error(format!("invalid tempo {value:?}"));
+        let value = value.rsplit('"').next().unwrap_or_default().trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let (beat, bpm) = match value.split_once('=') {
+            Some((beat, bpm)) => {
+                let beats = beat.split_whitespace().map(fraction);
+                let beat = beats.sum::<Option<Duration>>().ok_or_else(error)?;
+                (beat, bpm)
+            }
+            None => (self.unit.unwrap_or(EIGHTH), value),
+        };
+        Ok(Some((beat, bpm.trim().parse().map_err(|_| error())?)))
+    }
+
+    /// The index of the voice, added if it is a new one.
+    fn select(&mut self, id: &str) {
+        self.voice = match self.voices.iter().position(|v| v.id == id) {
+            Some(index) => index,
+            None => {
+                let voice = VoiceReader::new(id, self.time.clone(), self.unit(), self.tune.key);
+                self.voices.push(voice);
+                self.voices.len() - 1
+            }
+        };
+    }
+
+    /// The unit note length, given by the meter if there is no `L:` field.
+    fn unit(&self) -> Duration {
+        self.unit
+            .unwrap_or(match self.time.duration().as_f64() < 0.75 {
+                true => SIXTEENTH,
+                false => EIGHTH,
+            })
+    }
+
+    fn current(&mut self) -> &mut VoiceReader {
+        if self.voices.is_empty() {
+            self.select("1");
+        }
+        &mut self.voices[self.voice]
+    }
+
+    /// Reads a line of music.
+    fn music(&mut self, text: &str) -> Result<(), AbcError> {
+        let mut line = Line {
+            chars: text.chars().peekable(),
+        };
+        while let Some(c) = line.chars.next() {
+            match c {
+                ' ' | '\t' | '`' | '\\' | '$' | 'y' | ')' | '.' | '~' | 'H' | 'L' | 'M' | 'P'
+                | 'T' | 'u' | 'v' => {}
+                '"' => {
+                    let text = line
+                        .until('"')
+                        .ok_or_else(|| self.error("unclosed chord symbol".to_string()))?;
+                    self.symbol(&text);
+                }
+                '!' | '+' => {
+                    let name = line
+                        .until(c)
+                        .ok_or_else(|| self.error(format!("unclosed decoration {c}")))?;
+                    if let Some(mark) = mark(&name) {
+                        self.current().measure_marks.push(mark);
+                    }
+                }
+                'S' => self.current().measure_marks.push(Mark::Segno),
+                'O' => self.current().measure_marks.push(Mark::Coda),
+                '{' => {
+                    line.until('}')
+                        .ok_or_else(|| self.error("unclosed grace notes".to_string()))?;
+                    self.warn("skipped grace notes".to_string());
+                }
+                '(' => {
+                    // A tuplet: p notes in the time of q, for the next r notes.
+                    if let Some(p) = line.number() {
+                        let q = line.skip(':').and_then(|_| line.number());
+                        let r = line.skip(':').and_then(|_| line.number());
+                        let q = q.unwrap_or(match p {
+                            2 | 4 | 8 => 3,
+                            _ => 2,
+                        });
+                        self.current().tuplet = Some((q, p, r.unwrap_or(p)));
+                    }
+                }
+                '-' => self.current().tied = true,
+                '>' | '<' => {
+                    let mut count = 1;
+                    while line.chars.next_if_eq(&c).is_some() {
+                        count += 1;
+                    }
+                    if count > 3 {
+                        return Err(self.error(format!("invalid broken rhythm {c:?}x{count}")));
+                    }
+                    self.current()
+                        .broken_rhythm(c == '>', count)
+                        .ok_or_else(|| self.error("length out of range".to_string()))?;
+                }
+                '&' => {
+                    let voice = self.current();
+                    voice.parts.push(vec![]);
+                    voice.accidentals.clear();
+                }
+                '[' => match line.chars.peek().copied() {
+                    Some(c) if c.is_ascii_digit() => {
+                        let n = line.number().unwrap_or(1);
+                        self.current().ending = Some(n as u8);
+                    }
+                    Some('|') => {
+                        line.chars.next();
+                        self.barline("[|", &mut line);
+                    }
+                    Some(c) if c.is_ascii_alphabetic() && line.second() == Some(':') => {
+                        let field = line
+                            .until(']')
+                            .ok_or_else(|| self.error("unclosed inline field".to_string()))?;
+                        self.field(&field[..1], field[2..].trim())?;
+                    }
+                    _ => {
+                        let mut notes = vec![];
+                        let mut duration = None;
+                        loop {
+                            match line.chars.peek() {
+                                Some(']') => {
+                                    line.chars.next();
+                                    break;
+                                }
+                                None => return Err(self.error("unclosed chord".to_string())),
+                                Some(' ') => {
+                                    line.chars.next();
+                                }
+                                Some('-') => {
+                                    line.chars.next();
+                                    self.current().tied = true;
+                                }
+                                Some(_) => {
+                                    let first = line.chars.next().unwrap_or_default();
+                                    let (note, length) = self.note(first, &mut line)?;
+                                    notes.push(note);
+                                    duration.get_or_insert(length);
+                                }
+                            }
+                        }
+                        let (n, d) = line.length();
+                        let (n0, d0) = duration.unwrap_or((1, 1));
+                        let length = (n as u64 * n0 as u64, d as u64 * d0 as u64);
+                        self.add(Some(notes), length)?;
+                    }
+                },
+                '|' | ':' => {
+                    let mut barline = c.to_string();
+                    while let Some(c) = line.chars.next_if(|c| matches!(c, '|' | ':' | ']')) {
+                        barline.push(c);
+                    }
+                    self.barline(&barline, &mut line);
+                }
+                'z' | 'x' => {
+                    let (n, d) = line.length();
+                    self.add(None, (n as u64, d as u64))?;
+                }
+                'Z' | 'X' => {
+                    let mut count = line.number().unwrap_or(1);
+                    if count > MOST_MEASURES {
+                        self.warn(format!("shortened a rest of {count} measures"));
+                        count = MOST_MEASURES;
+                    }
+                    let voice = self.current();
+                    for index in 0..count {
+                        if index > 0 {
+                            voice.finish_measure();
+                        }
+                        let bar = voice.time.duration();
+                        voice.push(None, bar);
+                    }
+                }
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' => {
+                    let (note, (n, d)) = self.note(c, &mut line)?;
+                    self.add(Some(vec![note]), (n as u64, d as u64))?;
+                }
+                c => self.warn(format!("skipped {c:?}")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds notes (or a rest without any note) to the current voice, their length being in
+    /// unit note lengths.
+    fn add(&mut self, notes: Option<Vec<Note>>, length: (u64, u64)) -> Result<(), AbcError> {
+        let mut duration = self
+            .current()
+            .duration(length)
+            .ok_or_else(|| self.error("length out of range".to_string()))?;
+        if duration > Duration::new(LONGEST, 1) {
+            self.warn(format!(
+                "shortened a note longer than {LONGEST} whole notes"
+            ));
+            duration = Duration::new(LONGEST, 1);
+        }
+        self.current().push(notes, duration);
+        Ok(())
+    }
+
+    /// Reads a note, from its first character, with its length.
+    fn note(&mut self, first: char, line: &mut Line) -> Result<(Note, (u32, u32)), AbcError> {
+        let mut alter = None;
+        let mut c = first;
+        loop {
+            match c {
+                '^' => *alter.get_or_insert(0) += 1,
+                '_' => *alter.get_or_insert(0) -= 1,
+                '=' => {
+                    alter.get_or_insert(0);
+                }
+                _ => break,
+            }
+            if alter.is_some_and(|alter: i8| alter.abs() > 2) {
+                return Err(self.error("more than two accidentals".to_string()));
+            }
+            c = line
+                .chars
+                .next()
+                .ok_or_else(|| self.error("missing note after an accidental".to_string()))?;
+        }
+        if !c.is_ascii_alphabetic() || !matches!(c.to_ascii_uppercase(), 'A'..='G') {
+            return Err(self.error(format!("invalid note {c:?}")));
+        }
+
+        let mut octave: i8 = match c.is_ascii_lowercase() {
+            true => 5,
+            false => 4,
+        };
+        while let Some(mark) = line.chars.next_if(|c| matches!(c, '\'' | ',')) {
+            octave += if mark == '\'' { 1 } else { -1 };
+            // From C-1 to B9.
+            if !(-1..=9).contains(&octave) {
+                return Err(self.error("note out of range".to_string()));
+            }
+        }
+
+        let voice = self.current();
+        let letter = c.to_ascii_uppercase();
+        let alter = match alter {
+            Some(alter) => {
+                voice.accidentals.insert((letter, octave), alter);
+                alter
+            }
+            None => voice
+                .accidentals
+                .get(&(letter, octave))
+                .copied()
+                .unwrap_or_else(|| key_alter(voice.key, letter)),
+        };
+        let note = (octave - 4) * 12 + step(letter) + alter;
+        let note = Some(note)
+            .filter(|note| (-60..=67).contains(note))
+            .ok_or_else(|| self.error("note out of range".to_string()))?;
+        Ok((Note::from(note), line.length()))
+    }
+
+    /// Reads a chord symbol, the annotations (starting with ^, _, <, > or @) being skipped.
+    fn symbol(&mut self, text: &str) {
+        if text.starts_with(['^', '_', '<', '>', '@']) {
+            return;
+        }
+        let chord = match text {
+            "N.C." | "NC" => None,
+            text => match text.parse::<Chords>() {
+                Ok(chord) => Some(chord),
+                Err(_) => {
+                    self.warn(format!("skipped the unknown chord {text:?}"));
+                    return;
+                }
+            },
+        };
+        let voice = self.current();
+        if voice.parts.len() == 1 {
+            let position = voice.parts[0].iter().map(BarElement::duration).sum();
+            voice.symbols.push((position, chord));
+            voice.has_chords = true;
+        }
+    }
+
+    /// Ends the measure at a bar line, with the repeats and the endings of the bar line.
+    fn barline(&mut self, barline: &str, line: &mut Line) {
+        let voice = self.current();
+        let repeat_end = barline.starts_with(':') && barline != ":";
+        let repeat_start = barline.ends_with(':') && barline != ":";
+        if repeat_end {
+            voice.measure_marks.push(Mark::RepeatEnd(2));
+        }
+        voice.finish_measure();
+
+        let closes = repeat_end
+            || barline.contains("||")
+            || barline.contains(']')
+            || barline.starts_with('[');
+        if closes {
+            voice.ending = None;
+        }
+        if repeat_start {
+            voice.measure_marks.push(Mark::RepeatStart);
+        }
+        if let Some(n) = line.number() {
+            voice.ending = Some(n as u8);
+        }
+    }
+
+    fn finish(mut self) -> Tune {
+        for voice in &mut self.voices {
+            voice.finish_measure();
+        }
+        let unit = self.unit();
+        let mut tune = self.tune;
+        tune.unit = unit;
+        if let Some(first) = self.voices.first_mut() {
+            tune.marks = std::mem::take(&mut first.marks);
+        }
+        if let Some(voice) = self.voices.iter_mut().find(|v| v.has_chords) {
+            tune.chords = std::mem::take(&mut voice.chords);
+        }
+        tune.voices = self
+            .voices
+            .into_iter()
+            .map(|voice| (voice.id, voice.bars))
+            .collect();
+        tune
+    }
+}
+
+impl VoiceReader {
+    fn new(id: &str, time: TimeSignature, unit: Duration, key: Key) -> Self {
+        Self {
+            id: id.to_string(),
+            time,
+            unit,
+            key,
+            bars: vec![],
+            chords: vec![],
+            marks: vec![],
+            has_chords: false,
+            parts: vec![vec![]],
+            symbols: vec![],
+            measure_marks: vec![],
+            accidentals: HashMap::new(),
+            ending: None,
+            tuplet: None,
+            broken: None,
+            tied: false,
+        }
+    }
+
+    /// The duration of a length in unit note lengths, after a broken rhythm or in a tuplet, if
+    /// it is not too long or too short to be a duration.
+    fn duration(&mut self, (numerator, denominator): (u64, u64)) -> Option<Duration> {
+        let scale = |ratio: (u128, u128), (n, d): (u32, u32)| {
+            Some((
+                ratio.0.checked_mul(n as u128)?,
+                ratio.1.checked_mul(d as u128)?,
+            ))
+        };
+        let mut ratio = (numerator as u128, denominator as u128);
+        if let Some(broken) = self.broken.take() {
+            ratio = scale(ratio, broken)?;
+        }
+        if let Some((q, p, left)) = self.tuplet.take() {
+            ratio = scale(ratio, (q, p))?;
+            if left > 1 {
+                self.tuplet = Some((q, p, left - 1));
+            }
+        }
+        let unit = (self.unit.numerator(), self.unit.denominator());
+        reduced(scale(ratio, unit)?)
+    }
+
+    /// Adds notes (or a rest without any note).
+    fn push(&mut self, notes: Option<Vec<Note>>, duration: Duration) {
+        let part = self.parts.last_mut().unwrap();
+        let tied = std::mem::take(&mut self.tied);
+        let mut notes = notes.unwrap_or_default();
+        notes.sort();
+        notes.dedup();
+        if let Some(last) = part.last_mut().filter(|last| tied && last.notes() == notes) {
+            let tied = last.duration() + duration;
+            with_duration(last, tied);
+            return;
+        }
+        part.push(match &notes[..] {
+            [] => BarElement::Silence(duration),
+            [note] => BarElement::Note(*note, duration),
+            notes => BarElement::Cluster(notes.to_vec(), duration),
+        });
+    }
+
+    /// Lengthens the last element and shortens the next one (`>`), or the reverse (`<`), or
+    /// returns `None` if the last element would be too long.
+    fn broken_rhythm(&mut self, dotted_first: bool, count: u32) -> Option<()> {
+        let short = (1, 1 << count);
+        let long = ((2 << count) - 1, 1 << count);
+        let (last, next) = match dotted_first {
+            true => (long, short),
+            false => (short, long),
+        };
+        if let Some(element) = self.parts.last_mut().and_then(|p| p.last_mut()) {
+            let duration = element.duration();
+            let duration = reduced((
+                duration.numerator() as u128 * last.0 as u128,
+                duration.denominator() as u128 * last.1 as u128,
+            ))?;
+            with_duration(element, duration);
+        }
+        self.broken = Some(next);
+        Some(())
+    }
+
+    /// Adds the measure being read, if it has any note or rest.
+    fn finish_measure(&mut self) {
+        self.accidentals.clear();
+        self.tuplet = None;
+        self.tied = false;
+        if self.parts.iter().all(Vec::is_empty) {
+            return;
+        }
+
+        let mut parts = std::mem::replace(&mut self.parts, vec![vec![]]).into_iter();
+        let bar = Bar::new().with_time_signature(self.time.clone());
+        let bar = parts
+            .next()
+            .unwrap()
+            .into_iter()
+            .fold(bar, Bar::with_element);
+        let bar = parts.fold(bar, |bar, part| {
+            bar.with_voice(part.into_iter().fold(Voice::new(), Voice::with_element))
+        });
+
+        self.chords.push(chord_bar(
+            std::mem::take(&mut self.symbols),
+            &self.time,
+            bar.duration(),
+        ));
+        let mut marks = std::mem::take(&mut self.measure_marks);
+        if let Some(n) = self.ending {
+            marks.push(Mark::Ending(n));
+        }
+        self.marks.push(marks);
+        self.bars.push(bar);
+    }
+}
+
+impl Line<'_> {
+    /// The text up to the delimiter, which is skipped.
+    fn until(&mut self, delimiter: char) -> Option<String> {
+        let mut text = String::new();
+        for c in self.chars.by_ref() {
+            if c == delimiter {
+                return Some(text);
+            }
+            text.push(c);
+        }
+        None
+    }
+
+    fn second(&self) -> Option<char> {
+        self.chars.clone().nth(1)
+    }
+
+    fn skip(&mut self, c: char) -> Option<()> {
+        self.chars.next_if_eq(&c).map(|_| ())
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let mut number = None;
+        while let Some(digit) = self.chars.next_if(char::is_ascii_digit) {
+            let value = number.unwrap_or(0u32);
+            number = Some(
+                value
+                    .saturating_mul(10)
+                    .saturating_add(digit as u32 - '0' as u32),
+            );
+        }
+        number
+    }
+
+    /// A length, in unit note lengths: "2", "3/2", "/", "//", "/4"...
+    fn length(&mut self) -> (u32, u32) {
+        let numerator = self.number().unwrap_or(1);
+        let mut denominator = 1;
+        while self.skip('/').is_some() {
+            match self.number() {
+                Some(d) => {
+                    denominator *= d.max(1);
+                    break;
+                }
+                None => denominator *= 2,
+            }
+        }
+        (numerator, denominator)
+    }
+}
+
+/// The duration numerator / denominator of a whole note, if both fit once reduced.
+fn reduced((numerator, denominator): (u128, u128)) -> Option<Duration> {
+    let (mut a, mut b) = (numerator, denominator);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let gcd = a.max(1);
+    let numerator = u32::try_from(numerator / gcd).ok()?;
+    let denominator = u32::try_from(denominator / gcd).ok().filter(|d| *d > 0)?;
+    Some(Duration::new(numerator, denominator))
+}
+
+/// Changes the duration of an element.
+fn with_duration(element: &mut BarElement, duration: Duration) {
+    *element = match std::mem::replace(element, BarElement::Silence(duration)) {
+        BarElement::Silence(_) => BarElement::Silence(duration),
+        BarElement::Note(note, _) => BarElement::Note(note, duration),
+        BarElement::Cluster(notes, _) => BarElement::Cluster(notes, duration),
+        BarElement::Chord(chord, _) => BarElement::Chord(chord, duration),
+    };
+}
+
+/// A bar of the chord symbols, each chord lasting until the next one.
+fn chord_bar(
+    symbols: Vec<(Duration, Option<Chords>)>,
+    time: &TimeSignature,
+    length: Duration,
+) -> Bar {
+    let ends = symbols
+        .iter()
+        .skip(1)
+        .map(|(start, _)| *start)
+        .chain(std::iter::once(length))
+        .collect::<Vec<_>>();
+
+    let first = symbols.first().map_or(length, |(start, _)| *start);
+    let bar = silences(first).fold(
+        Bar::new().with_time_signature(time.clone()),
+        Bar::with_element,
+    );
+    symbols
+        .into_iter()
+        .zip(ends)
+        .fold(bar, |bar, ((start, chord), end)| {
+            let Some(duration) = end.checked_sub(start).filter(|d| !d.is_zero()) else {
+                return bar;
+            };
+            match chord {
+                Some(chord) => bar.with_chord(chord, duration),
+                None => silences(duration).fold(bar, Bar::with_element),
+            }
+        })
+}
+
+fn silences(duration: Duration) -> impl Iterator<Item = BarElement> {
+    let parts = match duration.is_zero() {
+        true => vec![],
+        false => duration.split(),
+    };
+    parts.into_iter().map(BarElement::Silence)
+}
+
+/// The mark of a decoration.
+fn mark(decoration: &str) -> Option<Mark> {
+    match decoration {
+        "segno" => Some(Mark::Segno),
+        "coda" => Some(Mark::Coda),
+        "dacoda" => Some(Mark::ToCoda),
+        "fine" => Some(Mark::Fine),
+        "D.C." | "dacapo" => Some(Mark::DaCapo(Jump::End)),
+        "D.C.alfine" => Some(Mark::DaCapo(Jump::Fine)),
+        "D.C.alcoda" => Some(Mark::DaCapo(Jump::Coda)),
+        "D.S." | "dalsegno" => Some(Mark::DalSegno(Jump::End)),
+        "D.S.alfine" => Some(Mark::DalSegno(Jump::Fine)),
+        "D.S.alcoda" => Some(Mark::DalSegno(Jump::Coda)),
+        _ => None,
+    }
+}
+
+/// A meter: "3/4", "2+2+3/8", "C" (common time) or "C|" (cut time).
+fn meter(value: &str) -> Option<TimeSignature> {
+    match value {
+        "C" | "none" | "" => return Some(TimeSignature::common()),
+        "C|" => return Some(TimeSignature::cut()),
+        _ => {}
+    }
+    let (beats, denominator) = value.split_once('/')?;
+    let denominator = denominator.trim().parse::<u8>().ok()?;
+    let groups = beats
+        .split('+')
+        .map(|g| g.trim().parse::<u8>().ok().filter(|g| *g > 0))
+        .collect::<Option<Vec<_>>>()?;
+    if !denominator.is_power_of_two() || groups.iter().map(|g| *g as u32).sum::<u32>() > 255 {
+        return None;
+    }
+    match &groups[..] {
+        [numerator] => Some(TimeSignature::new(*numerator, denominator)),
+        groups => Some(TimeSignature::additive(groups, denominator)),
+    }
+}
+
+/// A fraction, eg. "1/8".
+fn fraction(value: &str) -> Option<Duration> {
+    let (numerator, denominator) = value.trim().split_once('/')?;
+    let denominator = denominator.trim().parse::<u32>().ok().filter(|d| *d > 0)?;
+    Some(Duration::new(numerator.trim().parse().ok()?, denominator))
+}
+
+/// A key: a tonic, and a mode ("m", "min", "dor"...). The other modes are read as the
+/// major key with the same key signature.
+fn key(value: &str) -> Option<Key> {
+    let value = value.split_whitespace().next().unwrap_or("C");
+    if value == "none" {
+        return Some(Key::major(crate::C));
+    }
+    let split = value
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| !matches!(c, '#' | 'b'))
+        .map_or(value.len(), |(index, _)| index);
+    let (tonic, mode) = value.split_at(split);
+    let tonic = tonic.parse::<Note>().ok()?;
+
+    // The fifths from the major key of the tonic to the key signature of the mode.
+    let mode = mode.to_ascii_lowercase();
+    let fifths = match mode.get(..3).unwrap_or(&mode) {
+        "" | "maj" | "ion" => return Some(Key::major(tonic)),
+        "m" | "min" | "aeo" => return Some(Key::minor(tonic)),
+        "mix" => -1,
+        "dor" => -2,
+        "phr" => -4,
+        "lyd" => 1,
+        "loc" => -5,
+        _ => return None,
+    };
+    let signature = Key::major(tonic).signature() + fifths;
+    Some(Key::major(Note::from((signature * 7).rem_euclid(12))))
+}
+
+impl Display for AbcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbcError::Io(e) => write!(f, "{e}"),
+            AbcError::NoTune => write!(f, "the ABC text has no tune"),
+            AbcError::Syntax(line, message) => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for AbcError {}
+
+impl From<std::io::Error> for AbcError {
+    fn from(e: std::io::Error) -> Self {
+        AbcError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chart, Mode, A, A_SHARP, B, C, D, E, F_SHARP, G, HALF, OCTAVE, QUARTER, WHOLE};
+
+    fn bars(tune: &Tune) -> Vec<String> {
+        let (_, bars) = tune.voices().next().unwrap();
+        bars.iter().map(|b| format!("{b:X}")).collect()
+    }
+
+    #[test]
+    fn header() {
+        let tune = Tune::from_abc(
+            "%abc-2.1\nX:7\nT:The Kesh\nT:The Kesh Jig\nR:jig\nM:6/8\nQ:\"Lively\" 3/8=116\nK:Gmaj\nGAG GAB|\n",
+        )
+        .unwrap();
+        assert_eq!(tune.number(), 7);
+        assert_eq!(tune.title(), "The Kesh");
+        assert_eq!(tune.unit(), EIGHTH);
+        assert_eq!(tune.key(), Key::major(G));
+        assert_eq!(tune.tempo(), Some((QUARTER.dotted(), 116)));
+        assert_eq!(bars(&tune), ["G A G G A B"]);
+
+        // The unit note length of a short meter is the sixteenth.
+        let tune = Tune::from_abc("X:1\nM:2/4\nK:Edor\nF2 c2|").unwrap();
+        assert_eq!(tune.unit(), SIXTEENTH);
+        assert_eq!(tune.key(), Key::major(D));
+        assert_eq!(bars(&tune)[0], "F# C#");
+        assert_eq!(Key::minor(A).mode(), Mode::Minor);
+        assert_eq!(
+            Tune::from_abc("X:1\nK:Bbm\n").unwrap().key(),
+            Key::minor(A_SHARP)
+        );
+    }
+
+    #[test]
+    fn notes() {
+        let tune = Tune::from_abc(
+            "X:1\nL:1/4\nK:F\n^F F =B B | B, c' [CEG] [C2E2G2]/ z/ | A>B C<D (3CDE F | A2- A A- [A,C] | Z2 |\n",
+        )
+        .unwrap();
+        let bars = tune.voices().next().unwrap().1;
+        assert_eq!(bars.len(), 6);
+        let notes = |index: usize| {
+            let bar: &Bar = &bars[index];
+            bar.elements()
+                .map(|e| (e.notes(), e.duration()))
+                .collect::<Vec<_>>()
+        };
+        // The accidentals last until the bar line, the key signature then applies again.
+        assert_eq!(
+            notes(0),
+            [
+                (vec![F_SHARP], QUARTER),
+                (vec![F_SHARP], QUARTER),
+                (vec![B + OCTAVE], QUARTER),
+                (vec![B + OCTAVE], QUARTER)
+            ]
+        );
+        assert_eq!(
+            notes(1),
+            [
+                (vec![A_SHARP], QUARTER),
+                (vec![C + OCTAVE + OCTAVE], QUARTER),
+                (vec![C, E, G], QUARTER),
+                (vec![C, E, G], QUARTER),
+                (vec![], EIGHTH)
+            ]
+        );
+        let durations = notes(2).into_iter().map(|(_, d)| d).collect::<Vec<_>>();
+        let triplet = QUARTER.triplet();
+        assert_eq!(
+            durations,
+            [
+                QUARTER.dotted(),
+                EIGHTH,
+                EIGHTH,
+                QUARTER.dotted(),
+                triplet,
+                triplet,
+                triplet,
+                QUARTER
+            ]
+        );
+        // The tied notes are one note.
+        assert_eq!(
+            notes(3),
+            [
+                (vec![A + OCTAVE], HALF.dotted()),
+                (vec![A + OCTAVE], QUARTER),
+                (vec![A, C], QUARTER)
+            ]
+        );
+        assert_eq!(format!("{:X}", bars[4]), "_");
+        assert_eq!(bars[5].duration(), crate::WHOLE);
+    }
+
+    #[test]
+    fn form() {
+        let tune = Tune::from_abc(
+            "X:1\nL:1/4\nK:C\n|: C4 |1 D4 :|2 E4 || !segno! F4 | G4 !D.S.alcoda! |] O A4 |]\n",
+        )
+        .unwrap();
+        let marks = (0..6)
+            .map(|i| tune.marks(i).copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            marks,
+            [
+                vec![Mark::RepeatStart],
+                vec![Mark::RepeatEnd(2), Mark::Ending(1)],
+                vec![Mark::Ending(2)],
+                vec![Mark::Segno],
+                vec![Mark::DalSegno(Jump::Coda)],
+                vec![Mark::Coda],
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_symbols() {
+        let tune = Tune::from_abc(
+            "X:1\nL:1/4\nK:D\n\"D\"A2 \"Bm7\"B2 | \"Em7/D\"E \"N.C.\"E \"Gsus4\"G \"^rit.\"G | A4 |\n",
+        )
+        .unwrap();
+        assert_eq!(Chart::new().render(tune.chords()), "| D Bm7 | Em7 _ | _ |");
+        assert_eq!(
+            tune.warnings().collect::<Vec<_>>(),
+            [r#"line 4: skipped the unknown chord "Gsus4""#]
+        );
+    }
+
+    #[test]
+    fn voices() {
+        let text = "X:1\nL:1/2\nV:RH\nV:LH clef=bass\nK:C\nV:RH\nc e | [M:3/4] g3/ & e3/ |\nV:LH\n% The left hand\nC, G, | C,3/ |\n";
+        let tune = Tune::from_abc(text).unwrap();
+        let voices = tune
+            .voices()
+            .map(|(id, bars)| {
+                let bars = bars.iter().map(|b| format!("{b:X}")).collect::<Vec<_>>();
+                (id.to_string(), bars.join(" | "))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            voices,
+            [
+                ("RH".to_string(), "C E | G / E".to_string()),
+                ("LH".to_string(), "C G | C".to_string())
+            ]
+        );
+
+        let (_, bars) = tune.voices().next().unwrap();
+        assert_eq!(bars[1].time_signature(), &TimeSignature::new(3, 4));
+        assert_eq!(bars[1].voices().count(), 2);
+        assert_eq!(
+            Tune::from_abc(&tune.to_abc()).unwrap().to_abc(),
+            tune.to_abc()
+        );
+    }
+
+    #[test]
+    fn tunebook() {
+        let text =
+            "Tunes of the session\n\nX:1\nT:First\nK:G\nGABc|\n\nX:2\nT:Second\nK:D\nDEFA|\n";
+        let tunes = Tune::tunebook(text).unwrap();
+        let titles = tunes.iter().map(Tune::title).collect::<Vec<_>>();
+        assert_eq!(titles, ["First", "Second"]);
+        assert_eq!(Tune::from_abc(text).unwrap().title(), "First");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| Tune::from_abc(text).err().unwrap().to_string();
+        assert_eq!(error(""), "the ABC text has no tune");
+        assert_eq!(error("X:1\nM:3/5\nK:C\n"), r#"line 2: invalid meter "3/5""#);
+        assert_eq!(error("X:1\nK:Hm\n"), r#"line 2: invalid key "Hm""#);
+        assert_eq!(
+            error("X:1\nK:C\nCD \"Am|\n"),
+            "line 3: unclosed chord symbol"
+        );
+        assert_eq!(error("X:1\nK:C\n[CEG|\n"), "line 3: invalid note '|'");
+        let sharps = format!("X:1\nK:C\n{}C|\n", "^".repeat(130));
+        assert_eq!(error(&sharps), "line 3: more than two accidentals");
+        let high = format!("X:1\nK:C\nc{}|\n", "'".repeat(22));
+        assert_eq!(error(&high), "line 3: note out of range");
+        assert_eq!(error("X:1\nK:C\n^^B'''''|\n"), "line 3: note out of range");
+        assert_eq!(
+            error("X:1\nL:1/4294967295\nK:C\n(3CDE|\n"),
+            "line 4: length out of range"
+        );
+        assert_eq!(
+            error("X:1\nK:C\nC>>>>D|\n"),
+            "line 3: invalid broken rhythm '>'x4"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        // The longest notes and rests.
+        let tune = Tune::from_abc("X:1\nK:C\n[CE]4294967295|Z99999999|\n").unwrap();
+        let (_, bars) = tune.voices().next().unwrap();
+        assert_eq!(bars[0].duration(), WHOLE * 64);
+        assert_eq!(bars.len(), 1001);
+        assert_eq!(
+            tune.warnings().collect::<Vec<_>>(),
+            [
+                "line 3: shortened a note longer than 64 whole notes",
+                "line 3: shortened a rest of 99999999 measures"
+            ]
+        );
+
+        // A multi-measure rest of a long meter with a tiny unit note length.
+        let tune = Tune::from_abc("X:1\nM:255/1\nL:1/4294967295\nK:C\nZ|\n").unwrap();
+        assert_eq!(tune.voices().next().unwrap().1[0].duration(), WHOLE * 255);
+    }
+}
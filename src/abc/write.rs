@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use super::{key_alter, step, Tune};
+use crate::{
+    chords::Chords,
+    score::{Jump, Mark},
+    Bar, BarElement, Duration, Mode, Note, Spelling, TimeSignature,
+};
+
+/// The number of measures written on a line.
+const MEASURES_PER_LINE: usize = 4;
+
+/// The accidentals written in a bar, by letter and octave: they last until the bar line.
+type Accidentals = HashMap<(char, i8), i8>;
+
+/// The ABC text of a tune.
+pub(super) fn tune(tune: &Tune) -> String {
+    let first_bar = tune.voices.first().and_then(|(_, bars)| bars.first());
+    let time = first_bar.map_or(TimeSignature::default(), |b| b.time_signature().clone());
+
+    let mut lines = vec![
+        format!("X:{}", tune.number),
+        format!("T:{}", tune.title),
+        format!("M:{}", meter(&time)),
+        format!(
+            "L:{}",
+            fraction(tune.unit.numerator(), tune.unit.denominator())
+        ),
+    ];
+    if let Some((beat, bpm)) = tune.tempo {
+        let beat = fraction(beat.numerator(), beat.denominator());
+        lines.push(format!("Q:{beat}={bpm}"));
+    }
+    let voices = tune.voices.len() > 1;
+    if voices {
+        lines.extend(tune.voices.iter().map(|(id, _)| format!("V:{id}")));
+    }
+    let mode = match tune.key.mode() {
+        Mode::Major => "",
+        Mode::Minor => "m",
+    };
+    lines.push(format!("K:{}{mode}", tune.key.spell(tune.key.tonic())));
+
+    for (index, (id, bars)) in tune.voices.iter().enumerate() {
+        if voices {
+            lines.push(format!("V:{id}"));
+        }
+        lines.extend(body(tune, bars, index == 0, &time));
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// The lines of the bars of a voice, with the bar lines and the marks of the form.
+fn body(tune: &Tune, bars: &[Bar], first_voice: bool, time: &TimeSignature) -> Vec<String> {
+    let has = |index: usize, mark: &dyn Fn(&Mark) -> bool| tune.marks(index).any(mark);
+    let ending = |index: usize| {
+        tune.marks(index).find_map(|m| match m {
+            Mark::Ending(n) => Some(*n),
+            _ => None,
+        })
+    };
+
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut time = time.clone();
+    for (index, bar) in bars.iter().enumerate() {
+        if index == 0 {
+            if has(0, &|m| *m == Mark::RepeatStart) {
+                line.push_str("|: ");
+            }
+            if let Some(n) = ending(0) {
+                line.push_str(&format!("[{n} "));
+            }
+        }
+        if *bar.time_signature() != time {
+            time = bar.time_signature().clone();
+            line.push_str(&format!("[M:{}] ", meter(&time)));
+        }
+        if first_voice {
+            for mark in tune.marks(index).filter(|m| m.is_before()) {
+                if let Some(decoration) = decoration(mark) {
+                    line.push_str(&format!("{decoration} "));
+                }
+            }
+        }
+
+        let chords = tune.chords.get(index).filter(|_| first_voice);
+        line.push_str(&measure(tune, bar, chords));
+
+        if first_voice {
+            for mark in tune.marks(index).filter(|m| m.is_after()) {
+                if let Some(decoration) = decoration(mark) {
+                    line.push_str(&format!(" {decoration}"));
+                }
+            }
+        }
+
+        // The bar line, with the repeats and the ending which follows it.
+        let last = index + 1 == bars.len();
+        let repeat_end = has(index, &|m| matches!(m, Mark::RepeatEnd(_)));
+        let repeat_start = !last && has(index + 1, &|m| *m == Mark::RepeatStart);
+        let next_ending = ending(index + 1).filter(|n| !last && ending(index) != Some(*n));
+        let barline = match (repeat_end, repeat_start) {
+            (true, true) => "::",
+            (true, false) => ":|",
+            (false, true) => "|:",
+            _ if last => "|]",
+            _ if ending(index).is_some() && ending(index + 1).is_none() => "||",
+            _ => "|",
+        };
+        line.push_str(&format!(" {barline}"));
+        if let Some(n) = next_ending {
+            line.push_str(&n.to_string());
+        }
+
+        if last || (index + 1) % MEASURES_PER_LINE == 0 {
+            lines.push(std::mem::take(&mut line));
+        } else {
+            line.push(' ');
+        }
+    }
+    lines
+}
+
+/// The decoration of a mark, the repeats and the endings being written with the bar lines.
+fn decoration(mark: &Mark) -> Option<&'static str> {
+    match mark {
+        Mark::Segno => Some("!segno!"),
+        Mark::Coda => Some("!coda!"),
+        Mark::ToCoda => Some("!dacoda!"),
+        Mark::Fine => Some("!fine!"),
+        Mark::DaCapo(Jump::End) => Some("!D.C.!"),
+        Mark::DaCapo(Jump::Fine) => Some("!D.C.alfine!"),
+        Mark::DaCapo(Jump::Coda) => Some("!D.C.alcoda!"),
+        Mark::DalSegno(Jump::End) => Some("!D.S.!"),
+        Mark::DalSegno(Jump::Fine) => Some("!D.S.alfine!"),
+        Mark::DalSegno(Jump::Coda) => Some("!D.S.alcoda!"),
+        _ => None,
+    }
+}
+
+/// The voices of a bar separated by `&`, with the chord symbols before the notes of the
+/// first voice.
+fn measure(tune: &Tune, bar: &Bar, chords: Option<&Bar>) -> String {
+    // The chord symbols, a silence after a chord being written "N.C.".
+    let mut symbols = vec![];
+    let mut onset = Duration::new(0, 1);
+    for element in chords.into_iter().flat_map(|b| b.elements()) {
+        match element {
+            BarElement::Chord(chord, _) => symbols.push((onset, Some(chord))),
+            _ if !symbols.is_empty() => symbols.push((onset, None)),
+            _ => {}
+        }
+        onset += element.duration();
+    }
+
+    bar.voices()
+        .enumerate()
+        .map(|(index, voice)| {
+            let elements = voice.elements().collect::<Vec<_>>();
+            let symbols = match index {
+                0 => &symbols[..],
+                _ => &[],
+            };
+            elements_text(tune, &elements, symbols)
+        })
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+fn elements_text(
+    tune: &Tune,
+    elements: &[&BarElement],
+    symbols: &[(Duration, Option<&Chords>)],
+) -> String {
+    let spelling = Spelling::Key(tune.key);
+    let mut accidentals = Accidentals::new();
+    let mut symbols = symbols.iter().peekable();
+    let mut tokens = vec![];
+    let mut onset = Duration::new(0, 1);
+    let mut triplet = 0;
+
+    for (index, element) in elements.iter().enumerate() {
+        let mut token = String::new();
+        while let Some((_, chord)) = symbols.next_if(|(start, _)| *start <= onset) {
+            let symbol = chord.map_or("N.C.".to_string(), |c| spelling.chord(c));
+            token.push_str(&format!("\"{symbol}\""));
+        }
+
+        // Three triplet durations in a row make a (3 tuplet.
+        let triplets = elements[index..].iter().take(3);
+        if triplet == 0
+            && triplets
+                .filter(|e| as_triplet(e.duration()).is_some())
+                .count()
+                == 3
+        {
+            triplet = 3;
+            token.insert_str(0, "(3");
+        }
+        let duration = match triplet {
+            0 => element.duration(),
+            _ => {
+                triplet -= 1;
+                as_triplet(element.duration()).unwrap()
+            }
+        };
+
+        let pieces = match duration.denominator().is_power_of_two() && !duration.is_simple() {
+            true => duration.split(),
+            false => vec![duration],
+        };
+        let notes = |notes: &[Note], accidentals: &mut Accidentals| {
+            let pitches = notes
+                .iter()
+                .map(|n| pitch(tune, &spelling, *n, accidentals))
+                .collect::<Vec<_>>();
+            match &pitches[..] {
+                [pitch] => pitch.clone(),
+                pitches => format!("[{}]", pitches.concat()),
+            }
+        };
+        let written = match element {
+            BarElement::Silence(_) => pieces
+                .iter()
+                .map(|d| format!("z{}", length(tune, *d)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            element => {
+                let notes = notes(&element.notes(), &mut accidentals);
+                pieces
+                    .iter()
+                    .map(|d| format!("{notes}{}", length(tune, *d)))
+                    .collect::<Vec<_>>()
+                    .join("-")
+            }
+        };
+        token.push_str(&written);
+        tokens.push(token);
+        onset += element.duration();
+    }
+    tokens.join(" ")
+}
+
+/// The duration written in a triplet, if the duration is a triplet one.
+fn as_triplet(duration: Duration) -> Option<Duration> {
+    let written = duration * 3 / 2;
+    (!duration.denominator().is_power_of_two() && written.denominator().is_power_of_two())
+        .then_some(written)
+}
+
+/// A note, with its accidental if the key signature or a previous accidental of the bar
+/// does not give it, and its octave.
+fn pitch(tune: &Tune, spelling: &Spelling, note: Note, accidentals: &mut Accidentals) -> String {
+    let name = spelling.note(note);
+    let letter = name.chars().next().unwrap_or('C');
+    let alter = name.matches('#').count() as i8 - name.matches('b').count() as i8;
+    let octave = (i8::from(note) - alter - step(letter)).div_euclid(12) + 4;
+
+    let current = accidentals
+        .get(&(letter, octave))
+        .copied()
+        .unwrap_or_else(|| key_alter(tune.key, letter));
+    let accidental = match alter == current {
+        true => "",
+        false => {
+            accidentals.insert((letter, octave), alter);
+            match alter {
+                2 => "^^",
+                1 => "^",
+                0 => "=",
+                -1 => "_",
+                _ => "__",
+            }
+        }
+    };
+    let pitch = match octave {
+        o if o >= 5 => format!(
+            "{}{}",
+            letter.to_ascii_lowercase(),
+            "'".repeat(o as usize - 5)
+        ),
+        o => format!("{letter}{}", ",".repeat((4 - o) as usize)),
+    };
+    format!("{accidental}{pitch}")
+}
+
+/// The length of a duration, in unit note lengths: nothing for 1, "2", "/" for 1/2, "/4",
+/// "3/2"...
+fn length(tune: &Tune, duration: Duration) -> String {
+    let numerator = duration.numerator() * tune.unit.denominator();
+    let denominator = duration.denominator() * tune.unit.numerator();
+    let ratio = Duration::new(numerator, denominator);
+    match (ratio.numerator(), ratio.denominator()) {
+        (1, 1) => String::new(),
+        (n, 1) => n.to_string(),
+        (1, 2) => "/".to_string(),
+        (1, d) => format!("/{d}"),
+        (n, d) => format!("{n}/{d}"),
+    }
+}
+
+fn fraction(numerator: u32, denominator: u32) -> String {
+    format!("{numerator}/{denominator}")
+}
+
+fn meter(time: &TimeSignature) -> String {
+    let beats = match time.is_additive() {
+        true => time
+            .groups()
+            .map(|g| g.to_string())
+            .collect::<Vec<_>>()
+            .join("+"),
+        false => time.numerator().to_string(),
+    };
+    format!("{beats}/{}", time.denominator())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        abc::Tune, score::Mark, Bar, Key, A, A_SHARP, B, C, D, EIGHTH, F, G, OCTAVE, QUARTER,
+        SIXTEENTH,
+    };
+
+    fn body(tune: Tune) -> String {
+        let abc = tune.to_abc();
+        let (_, body) = abc.split_once("\nK:").unwrap();
+        body.split_once('\n').unwrap().1.trim_end().to_string()
+    }
+
+    #[test]
+    fn header() {
+        let tune = Tune::new("Reel")
+            .with_number(12)
+            .with_unit(SIXTEENTH)
+            .with_key(Key::minor(D))
+            .with_tempo(QUARTER, 100)
+            .with_voice(
+                "1",
+                [Bar::new().with_time_signature(crate::TimeSignature::new(6, 8))],
+            );
+        assert_eq!(
+            tune.to_abc(),
+            "X:12\nT:Reel\nM:6/8\nL:1/16\nQ:1/4=100\nK:Dm\n |]\n"
+        );
+    }
+
+    #[test]
+    fn pitches() {
+        // In F major, the B is flat unless a natural is written, for the rest of the bar.
+        // The constants A, A# and B are in the octave 3.
+        let bar = Bar::new()
+            .with_note(A_SHARP + OCTAVE, EIGHTH)
+            .with_note(B + OCTAVE, EIGHTH)
+            .with_note(B + OCTAVE, EIGHTH)
+            .with_note(B + OCTAVE + OCTAVE, EIGHTH)
+            .with_note(C + OCTAVE, EIGHTH)
+            .with_note(C + OCTAVE + OCTAVE, EIGHTH)
+            .with_note(F - OCTAVE, EIGHTH)
+            .with_note(A, EIGHTH);
+        let tune = Tune::new("")
+            .with_key(Key::major(F))
+            .with_voice("1", [bar, Bar::new().with_note(B + OCTAVE, 1)]);
+        assert_eq!(body(tune), "B =B B =b c c' F, A, | =B8 |]");
+    }
+
+    #[test]
+    fn lengths() {
+        let bar = Bar::new()
+            .with_note(C, QUARTER.dotted())
+            .with_note(D, EIGHTH * 5)
+            .with_cluster(&[A, C, F], QUARTER.triplet())
+            .with_silence(QUARTER.triplet())
+            .with_note(G, QUARTER.triplet())
+            .with_silence(SIXTEENTH);
+        let tune = Tune::new("").with_voice("1", [bar]);
+        assert_eq!(body(tune), "C3 D4-D (3[A,CF]2 z2 G2 z/ |]");
+    }
+
+    #[test]
+    fn form() {
+        let bars = (0..5).map(|i| Bar::new().with_note(crate::Note::from(i), 1));
+        let tune = Tune::new("")
+            .with_voice("1", bars)
+            .with_mark(0, Mark::Segno)
+            .with_mark(1, Mark::RepeatStart)
+            .with_mark(2, Mark::Ending(1))
+            .with_mark(2, Mark::RepeatEnd(2))
+            .with_mark(3, Mark::Ending(2))
+            .with_mark(4, Mark::DalSegno(crate::score::Jump::End));
+        assert_eq!(
+            body(tune),
+            "!segno! C8 |: ^C8 |1 D8 :|2 _E8 ||\nE8 !D.S.! |]"
+        );
+    }
+}
@@ -1,10 +1,46 @@
-use crate::{chords::Chords, Note};
-use std::fmt::{Display, LowerHex, UpperHex};
+use crate::{
+    chords::{Chord, Chords},
+    Duration, Note, TimeSignature,
+};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, LowerHex, UpperHex},
+};
 
 pub enum BarElement {
-    Silence(u8),
-    Chord(Chords, u8),
-    Note(Note, u8),
+    Silence(Duration),
+    Chord(Chords, Duration),
+    Note(Note, Duration),
+    /// Explicit notes sounding together (a dyad, a cluster...), as a single event.
+    Cluster(Vec<Note>, Duration),
+}
+
+impl BarElement {
+    /// Returns how long the element lasts.
+    pub fn duration(&self) -> Duration {
+        match self {
+            BarElement::Silence(duration) => *duration,
+            BarElement::Chord(_, duration) => *duration,
+            BarElement::Note(_, duration) => *duration,
+            BarElement::Cluster(_, duration) => *duration,
+        }
+    }
+
+    /// Returns the notes sounding during the element, none for a silence.
+    pub fn notes(&self) -> Vec<Note> {
+        match self {
+            BarElement::Silence(_) => vec![],
+            BarElement::Chord(chord, _) => chord.notes().copied().collect(),
+            BarElement::Note(note, _) => vec![*note],
+            BarElement::Cluster(notes, _) => notes.clone(),
+        }
+    }
+
+    /// Writes the notes of a cluster, eg. `<C E G>`.
+    fn cluster(notes: &[Note], format: impl Fn(&Note) -> String) -> String {
+        let notes = notes.iter().map(format).collect::<Vec<_>>().join(" ");
+        format!("<{notes}>")
+    }
 }
 
 impl Display for BarElement {
@@ -13,6 +49,9 @@ impl Display for BarElement {
             BarElement::Silence(_) => write!(f, "_"),
             BarElement::Chord(chord, _) => write!(f, "{chord}"),
             BarElement::Note(note, _) => write!(f, "{note}"),
+            BarElement::Cluster(notes, _) => {
+                write!(f, "{}", Self::cluster(notes, |n| format!("{n}")))
+            }
         }
     }
 }
@@ -23,6 +62,9 @@ impl UpperHex for BarElement {
             BarElement::Silence(_) => write!(f, "_"),
             BarElement::Chord(chord, _) => write!(f, "{chord:X}"),
             BarElement::Note(note, _) => write!(f, "{note:X}"),
+            BarElement::Cluster(notes, _) => {
+                write!(f, "{}", Self::cluster(notes, |n| format!("{n:X}")))
+            }
         }
     }
 }
@@ -33,35 +75,418 @@ impl LowerHex for BarElement {
             BarElement::Silence(_) => write!(f, "_"),
             BarElement::Chord(chord, _) => write!(f, "{chord:x}"),
             BarElement::Note(note, _) => write!(f, "{note:x}"),
+            BarElement::Cluster(notes, _) => {
+                write!(f, "{}", Self::cluster(notes, |n| format!("{n:x}")))
+            }
         }
     }
 }
 
-pub struct Bar(Vec<BarElement>);
+/// A voice of a bar: notes, chords, clusters and silences played one after the other.
+#[derive(Default)]
+pub struct Voice(Vec<BarElement>);
 
-impl Bar {
+/// A bar in a time signature (4/4 by default), made of one or more voices played together.
+///
+/// The main voice is built with the `with_*` methods of the bar, the other voices are added
+/// with [`Bar::with_voice`]. The durations are given as [`Duration`]s, or as a `u8` shorthand
+/// for the fraction of the whole note (eg. 4 for a quarter, 0 panicking as it is not a note value).
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let bar = Bar::new()
+///     .with_note(C, QUARTER.dotted())
+///     .with_note(D, 8)
+///     .with_chord(G.dom7(), HALF);
+/// assert_eq!(bar.duration(), WHOLE);
+/// assert_eq!(bar.fill(), Fill::Complete);
+///
+/// let bass = Voice::new().with_note(C - OCTAVE, 2).with_note(G - OCTAVE, 2);
+/// let bar = bar.with_voice(bass);
+/// assert_eq!(bar.onsets().len(), 3);
+/// ```
+pub struct Bar {
+    voices: Vec<Voice>,
+    time_signature: TimeSignature,
+}
+
+/// How much a voice is filled by its elements, compared to the time signature of its bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fill {
+    Complete,
+    /// The voice misses the given duration.
+    Under(Duration),
+    /// The voice exceeds the time signature by the given duration.
+    Over(Duration),
+}
+
+/// The position of an element in a bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeatPosition {
+    start: Duration,
+    beat: usize,
+    offset: Duration,
+}
+
+/// An element of a voice, with the time it starts in the bar.
+#[derive(Clone, Copy)]
+pub struct Event<'a> {
+    onset: Duration,
+    voice: usize,
+    element: &'a BarElement,
+}
+
+impl Voice {
     const SEPARATOR: &'static str = " ";
 
     pub fn new() -> Self {
-        Self(vec![])
+        Self::default()
+    }
+
+    pub fn with_chord(self, chord: Chords, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Chord(chord, duration.into()))
+    }
+
+    pub fn with_note(self, note: Note, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Note(note, duration.into()))
+    }
+
+    /// Adds notes sounding together, eg. a dyad or a cluster.
+    pub fn with_cluster(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Cluster(notes.to_vec(), duration.into()))
+    }
+
+    pub fn with_silence(self, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Silence(duration.into()))
+    }
+
+    pub fn with_element(self, element: BarElement) -> Self {
+        let mut elements = self.0;
+        elements.push(element);
+        Self(elements)
+    }
+
+    /// Returns the elements of the voice, in the order they are played.
+    pub fn elements(&self) -> impl Iterator<Item = &BarElement> {
+        self.0.iter()
+    }
+
+    /// Returns the total duration of the elements of the voice.
+    pub fn duration(&self) -> Duration {
+        self.0.iter().map(|e| e.duration()).sum()
+    }
+
+    /// Returns the time each element starts, from the beginning of the bar.
+    fn onsets(&self) -> impl Iterator<Item = (Duration, &BarElement)> {
+        self.0.iter().scan(Duration::new(0, 1), |start, element| {
+            let onset = *start;
+            *start += element.duration();
+            Some((onset, element))
+        })
+    }
+
+    /// Compares the duration of the elements with the time signature.
+    fn fill(&self, time_signature: &TimeSignature) -> Fill {
+        let duration = self.duration();
+        let expected = time_signature.duration();
+        match duration.cmp(&expected) {
+            Ordering::Equal => Fill::Complete,
+            Ordering::Less => Fill::Under(expected - duration),
+            Ordering::Greater => Fill::Over(duration - expected),
+        }
+    }
+
+    /// Completes the voice with silences: first up to the next beat, then one silence per
+    /// beat.
+    fn padded(self, time_signature: &TimeSignature) -> Self {
+        let mut position = self.duration();
+        let mut silences = vec![];
+        for (start, beat) in beat_starts(time_signature) {
+            let end = start + beat;
+            if let Some(rest) = end.checked_sub(position).filter(|r| !r.is_zero()) {
+                silences.extend(rest.split().into_iter().rev());
+                position = end;
+            }
+        }
+
+        silences
+            .into_iter()
+            .fold(self, |voice, silence| voice.with_silence(silence))
+    }
+
+    fn format(&self, format: impl Fn(&BarElement) -> String) -> String {
+        self.0
+            .iter()
+            .map(format)
+            .collect::<Vec<String>>()
+            .join(Self::SEPARATOR)
+    }
+}
+
+impl Bar {
+    const VOICES_SEPARATOR: &'static str = " / ";
+
+    pub fn new() -> Self {
+        Self {
+            voices: vec![Voice::new()],
+            time_signature: TimeSignature::default(),
+        }
+    }
+
+    pub fn with_time_signature(self, time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            ..self
+        }
+    }
+
+    pub fn with_chord(self, chord: Chords, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Chord(chord, duration.into()))
+    }
+
+    pub fn with_note(self, note: Note, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Note(note, duration.into()))
+    }
+
+    /// Adds notes sounding together to the main voice, eg. a dyad or a cluster.
+    pub fn with_cluster(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Cluster(notes.to_vec(), duration.into()))
+    }
+
+    pub fn with_silence(self, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Silence(duration.into()))
+    }
+
+    /// Adds an element to the main voice.
+    pub fn with_element(self, element: BarElement) -> Self {
+        let mut voices = self.voices;
+        let main = std::mem::take(&mut voices[0]);
+        voices[0] = main.with_element(element);
+        Self { voices, ..self }
+    }
+
+    /// Adds a voice played together with the previous ones.
+    pub fn with_voice(self, voice: Voice) -> Self {
+        let mut voices = self.voices;
+        voices.push(voice);
+        Self { voices, ..self }
+    }
+
+    pub fn time_signature(&self) -> &TimeSignature {
+        &self.time_signature
+    }
+
+    /// Returns the voices of the bar, the main voice first.
+    pub fn voices(&self) -> impl Iterator<Item = &Voice> {
+        self.voices.iter()
+    }
+
+    /// Returns the elements of the main voice, in the order they are played.
+    pub fn elements(&self) -> impl Iterator<Item = &BarElement> {
+        self.voices[0].elements()
+    }
+
+    /// Returns the total duration of the elements of the main voice.
+    pub fn duration(&self) -> Duration {
+        self.voices[0].duration()
+    }
+
+    /// Compares the duration of the main voice with the time signature.
+    pub fn fill(&self) -> Fill {
+        self.voices[0].fill(&self.time_signature)
+    }
+
+    /// Compares the duration of each voice with the time signature.
+    pub fn fills(&self) -> Vec<Fill> {
+        self.voices
+            .iter()
+            .map(|v| v.fill(&self.time_signature))
+            .collect()
+    }
+
+    /// Returns true if every voice fills exactly the time signature.
+    pub fn is_complete(&self) -> bool {
+        self.fills().iter().all(|f| *f == Fill::Complete)
+    }
+
+    /// Completes the under-filled voices with silences: first up to the next beat, then one
+    /// silence per beat. The complete and the over-filled voices are left as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// let bar = Bar::new().with_note(C, 4).with_note(D, 8).padded();
+    /// assert!(bar.is_complete());
+    /// assert_eq!(bar.to_string(), "C D _ _ _");
+    /// ```
+    pub fn padded(self) -> Self {
+        let time_signature = self.time_signature;
+        let voices = self
+            .voices
+            .into_iter()
+            .map(|v| v.padded(&time_signature))
+            .collect();
+        Self {
+            voices,
+            time_signature,
+        }
+    }
+
+    /// Returns the position of the element of the main voice at the given index, or `None`
+    /// if there is no such element or if it starts after the end of the bar.
+    pub fn beat_position(&self, index: usize) -> Option<BeatPosition> {
+        let (start, _) = self.voices[0].onsets().nth(index)?;
+        self.position_at(start)
+    }
+
+    /// Returns the position of the given time from the start of the bar, or `None` if it is
+    /// after the end of the bar.
+    pub fn position_at(&self, start: Duration) -> Option<BeatPosition> {
+        beat_starts(&self.time_signature)
+            .enumerate()
+            .find(|(_, (beat_start, beat))| start < *beat_start + *beat)
+            .map(|(beat, (beat_start, _))| BeatPosition {
+                start,
+                beat: beat + 1,
+                offset: start - beat_start,
+            })
+    }
+
+    /// Returns the positions of the elements of the main voice which start inside the bar,
+    /// in order.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// let bar = Bar::new()
+    ///     .with_time_signature(TimeSignature::new(6, 8))
+    ///     .with_note(C, QUARTER.dotted())
+    ///     .with_note(D, 8)
+    ///     .with_note(E, 4);
+    /// let positions = bar.beat_positions();
+    /// assert!(positions[1].is_on_beat());
+    /// assert_eq!(positions[1].beat(), 2);
+    /// assert_eq!(positions[2].to_string(), "2+1/8");
+    /// ```
+    pub fn beat_positions(&self) -> Vec<BeatPosition> {
+        (0..self.voices[0].0.len())
+            .map_while(|index| self.beat_position(index))
+            .collect()
+    }
+
+    /// Returns the elements of all the voices, ordered by the time they start (then by
+    /// voice).
+    pub fn events(&self) -> Vec<Event<'_>> {
+        let mut events = self
+            .voices
+            .iter()
+            .enumerate()
+            .flat_map(|(voice, v)| {
+                v.onsets().map(move |(onset, element)| Event {
+                    onset,
+                    voice,
+                    element,
+                })
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|e| (e.onset, e.voice));
+        events
+    }
+
+    /// Returns the events starting together, grouped by the time they start.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// let bar = Bar::new()
+    ///     .with_note(E, 4)
+    ///     .with_note(F, 4)
+    ///     .with_note(G, 2)
+    ///     .with_voice(Voice::new().with_cluster(&[C, G], 2).with_chord(C.maj(), 2));
+    ///
+    /// let onsets = bar.onsets();
+    /// assert_eq!(onsets.len(), 3);
+    /// assert!(onsets[0].0.is_zero());
+    /// assert_eq!(onsets[0].1.len(), 2);
+    /// assert_eq!(onsets[2].0, HALF);
+    /// ```
+    pub fn onsets(&self) -> Vec<(Duration, Vec<Event<'_>>)> {
+        let mut onsets: Vec<(Duration, Vec<Event>)> = vec![];
+        for event in self.events() {
+            match onsets.last_mut() {
+                Some((onset, events)) if *onset == event.onset => events.push(event),
+                _ => onsets.push((event.onset, vec![event])),
+            }
+        }
+        onsets
+    }
+
+    fn format(&self, format: impl Fn(&BarElement) -> String) -> String {
+        self.voices
+            .iter()
+            .map(|v| v.format(&format))
+            .collect::<Vec<_>>()
+            .join(Self::VOICES_SEPARATOR)
+    }
+}
+
+/// The start and the duration of each beat of the time signature.
+fn beat_starts(time_signature: &TimeSignature) -> impl Iterator<Item = (Duration, Duration)> {
+    time_signature
+        .beats()
+        .into_iter()
+        .scan(Duration::new(0, 1), |start, beat| {
+            let current = *start;
+            *start += beat;
+            Some((current, beat))
+        })
+}
+
+impl BeatPosition {
+    /// The time from the start of the bar.
+    pub fn start(&self) -> Duration {
+        self.start
     }
 
-    pub fn with_chord(self, chord: Chords, fraction: u8) -> Self {
-        let mut items = self.0;
-        items.push(BarElement::Chord(chord, fraction));
-        Self(items)
+    /// The beat, starting from 1.
+    pub fn beat(&self) -> usize {
+        self.beat
     }
 
-    pub fn with_note(self, note: Note, fraction: u8) -> Self {
-        let mut items = self.0;
-        items.push(BarElement::Note(note, fraction));
-        Self(items)
+    /// The time from the start of the beat.
+    pub fn offset(&self) -> Duration {
+        self.offset
     }
 
-    pub fn with_silence(self, fraction: u8) -> Self {
-        let mut items = self.0;
-        items.push(BarElement::Silence(fraction));
-        Self(items)
+    /// Returns true if the element starts on a beat.
+    pub fn is_on_beat(&self) -> bool {
+        self.offset.is_zero()
+    }
+}
+
+impl<'a> Event<'a> {
+    /// The time the element starts, from the beginning of the bar.
+    pub fn onset(&self) -> Duration {
+        self.onset
+    }
+
+    /// The time the element ends, from the beginning of the bar.
+    pub fn end(&self) -> Duration {
+        self.onset + self.element.duration()
+    }
+
+    /// The index of the voice of the element, 0 for the main voice.
+    pub fn voice(&self) -> usize {
+        self.voice
+    }
+
+    pub fn element(&self) -> &'a BarElement {
+        self.element
     }
 }
 
@@ -71,53 +496,64 @@ impl Default for Bar {
     }
 }
 
+impl Display for Voice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(|e| format!("{e}")))
+    }
+}
+
+impl UpperHex for Voice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(|e| format!("{e:X}")))
+    }
+}
+
+impl LowerHex for Voice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(|e| format!("{e:x}")))
+    }
+}
+
 impl Display for Bar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = self
-            .0
-            .iter()
-            .map(|e| format!("{e}"))
-            .collect::<Vec<String>>()
-            .join(Self::SEPARATOR);
-        write!(f, "{s}")
+        write!(f, "{}", self.format(|e| format!("{e}")))
     }
 }
 
 impl UpperHex for Bar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = self
-            .0
-            .iter()
-            .map(|e| format!("{e:X}"))
-            .collect::<Vec<String>>()
-            .join(Self::SEPARATOR);
-        write!(f, "{s}")
+        write!(f, "{}", self.format(|e| format!("{e:X}")))
     }
 }
 
 impl LowerHex for Bar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = self
-            .0
-            .iter()
-            .map(|e| format!("{e:x}"))
-            .collect::<Vec<String>>()
-            .join(Self::SEPARATOR);
-        write!(f, "{s}")
+        write!(f, "{}", self.format(|e| format!("{e:x}")))
     }
 }
 
-// pub fn show(bars: impl Iterator<Item = Bar>) -> String {
-//     let s = bars
-//         .map(|b| b.to_string())
-//         .collect::<Vec<String>>()
-//         .join(" | ");
-//     format!("| {s} |")
-// }
+impl Display for Fill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fill::Complete => write!(f, "complete"),
+            Fill::Under(missing) => write!(f, "under-filled by {missing}"),
+            Fill::Over(extra) => write!(f, "over-filled by {extra}"),
+        }
+    }
+}
+
+impl Display for BeatPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.is_on_beat() {
+            true => write!(f, "{}", self.beat),
+            false => write!(f, "{}+{}", self.beat, self.offset),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::{C, G};
+    use crate::{A, C, D_SHARP, E, EIGHTH, F, G, HALF, QUARTER, WHOLE};
 
     use super::*;
 
@@ -127,13 +563,127 @@ mod tests {
         assert_eq!(bar.to_string(), "C G");
     }
 
-    // #[test]
-    // fn displa_bars() {
-    //     let bar1 = Bar::new().with_chord(C.maj(), 2).with_chord(G.maj(), 2);
-    //     let bar2 = Bar::new().with_chord(C.maj(), 2).with_chord(G.maj(), 2);
-    //     let bars = [bar1, bar2];
+    #[test]
+    fn duration() {
+        let bar = Bar::new()
+            .with_note(C, QUARTER.dotted())
+            .with_note(G, 8)
+            .with_silence(EIGHTH.triplet() * 3)
+            .with_chord(G.maj(), 4);
+        assert_eq!(bar.duration(), WHOLE);
+        assert_eq!(bar.elements().nth(1).unwrap().duration(), EIGHTH);
+    }
+
+    #[test]
+    fn fill() {
+        let bar = Bar::new().with_note(C, 2).with_note(G, 4);
+        assert_eq!(bar.fill(), Fill::Under(QUARTER));
+        assert_eq!(bar.fill().to_string(), "under-filled by 1/4");
+
+        let bar = Bar::new()
+            .with_time_signature(TimeSignature::new(3, 4))
+            .with_chord(C.maj(), 1);
+        assert_eq!(bar.fill(), Fill::Over(QUARTER));
+
+        let bar = Bar::new()
+            .with_time_signature(TimeSignature::additive(&[2, 2, 3], 8))
+            .with_note(C, 4)
+            .with_note(G, 4)
+            .with_note(C, QUARTER.dotted());
+        assert!(bar.is_complete());
+    }
+
+    #[test]
+    fn padded() {
+        let bar = Bar::new().with_note(C, 8).padded();
+        assert!(bar.is_complete());
+        let silences = bar
+            .elements()
+            .skip(1)
+            .map(|e| e.duration())
+            .collect::<Vec<_>>();
+        assert_eq!(silences, [EIGHTH, QUARTER, QUARTER, QUARTER]);
+
+        let bar = Bar::new()
+            .with_time_signature(TimeSignature::new(6, 8))
+            .with_note(C, 8)
+            .padded();
+        let silences = bar
+            .elements()
+            .skip(1)
+            .map(|e| e.duration())
+            .collect::<Vec<_>>();
+        assert_eq!(silences, [QUARTER, QUARTER.dotted()]);
+
+        let bar = Bar::new().with_note(C, 1).with_note(G, 4).padded();
+        assert_eq!(bar.elements().count(), 2);
+    }
+
+    #[test]
+    fn beat_positions() {
+        let bar = Bar::new()
+            .with_time_signature(TimeSignature::new(7, 8))
+            .with_note(C, 8)
+            .with_note(G, 4)
+            .with_note(C, 4)
+            .with_note(G, 8)
+            .with_note(C, 8);
+        let positions = bar.beat_positions();
+        let beats = positions.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(beats, ["1", "1+1/8", "2+1/8", "3+1/8", "3+1/4"]);
+        assert_eq!(positions[3].start(), HALF.dotted() - EIGHTH);
+
+        let bar = Bar::new().with_note(C, 1).with_note(G, 4);
+        assert_eq!(bar.beat_positions().len(), 1);
+        assert_eq!(bar.beat_position(1), None);
+    }
 
-    //     let s = show(bars.into_iter());
-    //     assert_eq!(s, "| C - G | C - G |")
-    // }
+    #[test]
+    fn voices() {
+        let bar = Bar::new()
+            .with_note(E, 4)
+            .with_cluster(&[F, A], 4)
+            .with_note(G, 2)
+            .with_voice(Voice::new().with_chord(C.maj(), 1))
+            .with_voice(Voice::new().with_note(C, 2));
+        assert_eq!(bar.voices().count(), 3);
+        assert_eq!(bar.to_string(), "E <F A> G / C / C");
+        assert_eq!(
+            bar.fills(),
+            [Fill::Complete, Fill::Complete, Fill::Under(HALF)]
+        );
+        assert_eq!(bar.fill(), Fill::Complete);
+        assert!(!bar.is_complete());
+
+        let bar = bar.padded();
+        assert!(bar.is_complete());
+        assert_eq!(bar.to_string(), "E <F A> G / C / C _ _");
+    }
+
+    #[test]
+    fn events() {
+        let bar = Bar::new()
+            .with_note(E, 4)
+            .with_note(F, 4)
+            .with_note(G, 2)
+            .with_voice(Voice::new().with_cluster(&[C, G], 2).with_chord(C.maj(), 2));
+        let events = bar.events();
+        let voices = events.iter().map(|e| e.voice()).collect::<Vec<_>>();
+        assert_eq!(voices, [0, 1, 0, 0, 1]);
+        assert_eq!(events[1].element().notes(), [C, G]);
+        assert_eq!(events[1].end(), HALF);
+
+        let onsets = bar.onsets();
+        let times = onsets.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        assert_eq!(times, [Duration::new(0, 1), QUARTER, HALF]);
+        assert_eq!(onsets[2].1.len(), 2);
+    }
+
+    #[test]
+    fn cluster() {
+        let element = BarElement::Cluster(vec![C, D_SHARP, G], QUARTER);
+        assert_eq!(format!("{element:X}"), "<C D# G>");
+        assert_eq!(format!("{element:x}"), "<C Eb G>");
+        assert!(BarElement::Silence(QUARTER).notes().is_empty());
+    }
 }
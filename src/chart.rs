@@ -0,0 +1,330 @@
+use crate::{
+    score::{Mark, Score, Staff},
+    Bar, BarElement, Spelling,
+};
+
+/// Renders bars as a chord chart: lines of bars separated by `|`, with the section labels,
+/// the repeat signs `|:` and `:|`, and `%` for a bar repeating the previous one. The bars
+/// and the bar lines are aligned in columns.
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let bars = [
+///     Bar::new().with_chord(D.min7(), 1),
+///     Bar::new().with_chord(G.dom7(), 1),
+///     Bar::new().with_chord(C.maj7(), 1),
+///     Bar::new().with_chord(C.maj7(), 1),
+/// ];
+/// let chart = Chart::new().with_bars_per_line(2).render(&bars);
+/// assert_eq!(chart, "| Dm7   | G7 |\n| Cmaj7 | %  |");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chart {
+    bars_per_line: usize,
+    spelling: Spelling,
+    simile: bool,
+}
+
+/// A bar of the chart, with the repeat signs around it.
+struct Cell {
+    repeat_start: bool,
+    repeat_end: bool,
+    text: String,
+}
+
+/// A line of the chart, with the label of its section on its first line.
+struct Line {
+    label: Option<String>,
+    cells: Vec<Cell>,
+}
+
+impl Chart {
+    /// A chart with 4 bars per line, the notes spelled with sharps and `%` for the repeated
+    /// bars.
+    pub fn new() -> Self {
+        Self {
+            bars_per_line: 4,
+            spelling: Spelling::Sharps,
+            simile: true,
+        }
+    }
+
+    pub fn with_bars_per_line(self, bars_per_line: usize) -> Self {
+        Self {
+            bars_per_line: bars_per_line.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_spelling(self, spelling: Spelling) -> Self {
+        Self { spelling, ..self }
+    }
+
+    /// Writes `%` for a bar which repeats the previous one (true by default), or the bar
+    /// itself.
+    pub fn with_simile(self, simile: bool) -> Self {
+        Self { simile, ..self }
+    }
+
+    /// Renders a sequence of bars.
+    pub fn render(&self, bars: &[Bar]) -> String {
+        let texts = bars.iter().map(|b| Some(self.bar(b)));
+        let cells = self.cells(texts.zip(std::iter::repeat(&[][..])));
+        self.layout(self.lines(None, cells))
+    }
+
+    /// Renders a staff of a score, section after section, with the marks of its form.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{score::*, *};
+    ///
+    /// let bb = Measure::chords(Bar::new().with_chord(A_SHARP.maj7(), 1));
+    /// let eb = Measure::chords(Bar::new().with_chord(D_SHARP.dom7(), 1));
+    /// let section = Section::new("A")
+    ///     .with_measure(bb.with_mark(Mark::RepeatStart))
+    ///     .with_measure(eb.with_mark(Mark::RepeatEnd(2)));
+    /// let score = Score::new("Vamp").with_section(section);
+    ///
+    /// let chart = Chart::new().with_spelling(Spelling::Flats);
+    /// assert_eq!(chart.render_score(&score, Staff::Chords), "[A] |: Bbmaj7 | Eb7 :|");
+    /// ```
+    pub fn render_score(&self, score: &Score, staff: Staff) -> String {
+        let lines = score
+            .sections()
+            .flat_map(|section| {
+                let measures = section.measures().map(|m| {
+                    let marks = m.marks().copied().collect::<Vec<_>>();
+                    (m.bar(staff).map(|b| self.bar(b)), marks)
+                });
+                let measures = measures.collect::<Vec<_>>();
+                let cells = self.cells(measures.iter().map(|(t, m)| (t.clone(), &m[..])));
+                self.lines(Some(section.label().to_string()), cells)
+            })
+            .collect();
+        self.layout(lines)
+    }
+
+    /// The text of a bar, with its elements spelled.
+    fn bar(&self, bar: &Bar) -> String {
+        bar.elements()
+            .map(|e| match e {
+                BarElement::Silence(_) => "_".to_string(),
+                BarElement::Chord(chord, _) => self.spelling.chord(chord),
+                BarElement::Note(note, _) => self.spelling.note(*note),
+                BarElement::Cluster(notes, _) => {
+                    let notes = notes.iter().map(|n| self.spelling.note(*n));
+                    format!("<{}>", notes.collect::<Vec<_>>().join(" "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The cells of a section, from the text of each bar (`None` for an empty measure) and
+    /// its marks.
+    fn cells<'a>(&self, measures: impl Iterator<Item = (Option<String>, &'a [Mark])>) -> Vec<Cell> {
+        let mut previous: Option<String> = None;
+        measures
+            .map(|(text, marks)| {
+                // The bar of an ending does not follow the previous one when it is played.
+                let simile = self.simile && !marks.iter().any(|m| matches!(m, Mark::Ending(_)));
+                let bar = match (&text, &previous) {
+                    (Some(text), Some(previous)) if simile && text == previous => "%".to_string(),
+                    (Some(text), _) => text.clone(),
+                    (None, _) => String::new(),
+                };
+                previous = text;
+
+                let before = marks
+                    .iter()
+                    .filter(|m| m.is_before())
+                    .map(|m| m.to_string());
+                let after = marks.iter().filter(|m| m.is_after()).map(|m| m.to_string());
+                let text = before
+                    .chain(std::iter::once(bar))
+                    .chain(after)
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                Cell {
+                    repeat_start: marks.contains(&Mark::RepeatStart),
+                    repeat_end: marks.iter().any(|m| matches!(m, Mark::RepeatEnd(_))),
+                    text,
+                }
+            })
+            .collect()
+    }
+
+    /// Splits the cells of a section into lines.
+    fn lines(&self, label: Option<String>, cells: Vec<Cell>) -> Vec<Line> {
+        let mut lines: Vec<Line> = vec![];
+        for cell in cells {
+            match lines.last_mut() {
+                Some(line) if line.cells.len() < self.bars_per_line => line.cells.push(cell),
+                _ => lines.push(Line {
+                    label: lines.is_empty().then(|| label.clone()).flatten(),
+                    cells: vec![cell],
+                }),
+            }
+        }
+        lines
+    }
+
+    /// Writes the lines, with the bars and the bar lines aligned in columns.
+    fn layout(&self, lines: Vec<Line>) -> String {
+        let columns = lines.iter().map(|l| l.cells.len()).max().unwrap_or(0);
+
+        let widths = (0..columns)
+            .map(|c| {
+                lines
+                    .iter()
+                    .filter_map(|l| l.cells.get(c))
+                    .map(|cell| cell.text.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        // The number of characters before and after the pipe of each column of bar lines.
+        let bar_lines = (0..=columns)
+            .map(|c| {
+                lines
+                    .iter()
+                    .filter(|l| c <= l.cells.len())
+                    .map(|l| bar_line(&l.cells, c))
+                    .fold((0, 0), |(before, after), token| {
+                        let pipe = token.find('|').unwrap_or(0);
+                        (before.max(pipe), after.max(token.len() - pipe - 1))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let labels = lines
+            .iter()
+            .filter_map(|l| l.label.as_ref())
+            .map(|l| l.chars().count() + 3)
+            .max();
+
+        lines
+            .iter()
+            .map(|line| {
+                let mut s = match (labels, &line.label) {
+                    (Some(width), Some(label)) => format!("{:<width$}", format!("[{label}] ")),
+                    (Some(width), None) => " ".repeat(width),
+                    (None, _) => String::new(),
+                };
+                for c in 0..=line.cells.len() {
+                    let token = bar_line(&line.cells, c);
+                    let pipe = token.find('|').unwrap_or(0);
+                    let (before, after) = bar_lines[c];
+                    s.push_str(&" ".repeat(before - pipe));
+                    s.push_str(token);
+                    s.push_str(&" ".repeat(after + pipe + 1 - token.len()));
+
+                    if let Some(cell) = line.cells.get(c) {
+                        s.push_str(&format!(" {:<width$} ", cell.text, width = widths[c]));
+                    }
+                }
+                s.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The bar line before the cell of the given index (or after the last cell).
+fn bar_line(cells: &[Cell], index: usize) -> &'static str {
+    let end = index > 0 && cells[index - 1].repeat_end;
+    let start = cells.get(index).is_some_and(|c| c.repeat_start);
+    match (end, start) {
+        (false, false) => "|",
+        (false, true) => "|:",
+        (true, false) => ":|",
+        (true, true) => ":|:",
+    }
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        score::{Jump, Measure, Section},
+        Key, A, A_SHARP, C, D, E, F, G,
+    };
+
+    #[test]
+    fn lines() {
+        let bars = [
+            Bar::new().with_chord(C.maj7(), 1),
+            Bar::new().with_chord(A.min7(), 1),
+            Bar::new().with_chord(D.min7(), 2).with_chord(G.dom7(), 2),
+        ];
+        let chart = Chart::new().with_bars_per_line(2).render(&bars);
+        assert_eq!(chart, "| Cmaj7  | Am7 |\n| Dm7 G7 |");
+    }
+
+    #[test]
+    fn simile() {
+        let bars = [
+            Bar::new().with_chord(C.maj7(), 1),
+            Bar::new().with_chord(C.maj7(), 1),
+            Bar::new().with_chord(C.maj7(), 1),
+        ];
+        assert_eq!(Chart::new().render(&bars), "| Cmaj7 | % | % |");
+        assert_eq!(
+            Chart::new().with_simile(false).render(&bars),
+            "| Cmaj7 | Cmaj7 | Cmaj7 |"
+        );
+    }
+
+    #[test]
+    fn spelling() {
+        let bars = [Bar::new()
+            .with_chord(A_SHARP.maj7(), 2)
+            .with_note(A_SHARP, 2)];
+        assert_eq!(Chart::new().render(&bars), "| A#maj7 A# |");
+        let chart = Chart::new().with_spelling(Spelling::Key(Key::major(F)));
+        assert_eq!(chart.render(&bars), "| Bbmaj7 Bb |");
+    }
+
+    #[test]
+    fn score() {
+        let chords = |chord| Measure::chords(Bar::new().with_chord(chord, 1));
+        let score = Score::new("Test")
+            .with_section(
+                Section::new("A")
+                    .with_measure(chords(C.maj7()).with_mark(Mark::RepeatStart))
+                    .with_measure(chords(A.min7()))
+                    .with_measure(chords(D.min7()).with_mark(Mark::Ending(1)))
+                    .with_measure(
+                        chords(G.dom7())
+                            .with_mark(Mark::Ending(1))
+                            .with_mark(Mark::RepeatEnd(2)),
+                    )
+                    .with_measure(chords(G.dom7()).with_mark(Mark::Ending(2)))
+                    .with_measure(chords(C.maj7())),
+            )
+            .with_section(
+                Section::new("B")
+                    .with_measure(chords(E.min7()))
+                    .with_measure(chords(E.min7()).with_mark(Mark::DaCapo(Jump::End))),
+            );
+
+        let chart = Chart::new().render_score(&score, Staff::Chords);
+        let lines = chart.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "[A] |: Cmaj7 | Am7    | 1. Dm7 | 1. G7 :|");
+        assert_eq!(lines[1], "    |  2. G7 | Cmaj7  |");
+        assert_eq!(lines[2], "[B] |  Em7   | % D.C. |");
+    }
+}
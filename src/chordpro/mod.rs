@@ -0,0 +1,322 @@
+//! ChordPro chord sheets, the text format of the songbooks: the lyrics with the chords
+//! written inline (`[Am]lyrics [G7]here`), and the directives between braces
+//! (`{title: ...}`, `{key: ...}`, `{start_of_chorus}`...).
+//!
+//! A [`Song`] is read with [`Song::from_chordpro`], transposed with [`Song::transpose`] or
+//! [`Song::transpose_to`], and written back with [`Song::to_chordpro`], or as chords over
+//! the lyrics with [`Song::to_text`].
+//!
+//! # Example
+//! ```
+//! use musika_rs::{chordpro::*, *};
+//!
+//! let song = Song::from_chordpro(
+//!     "{title: Autumn}\n{key: Am}\n\n{start_of_chorus}\n[Am]Leaves are [Dm7]falling\n{end_of_chorus}\n",
+//! )
+//! .unwrap();
+//! assert_eq!(song.title(), "Autumn");
+//! assert_eq!(song.sections()[0].kind(), "chorus");
+//!
+//! let song = song.transpose_to(Key::minor(D));
+//! assert_eq!(song.to_text(), "Autumn\n\nChorus\nDm         Gm7\nLeaves are falling\n");
+//! ```
+
+mod read;
+mod write;
+
+use crate::{
+    chords::{Chord, Chords},
+    Interval, Key, Spelling,
+};
+
+pub use read::ChordProError;
+
+/// A line of a section.
+pub enum Line {
+    /// The lyrics, cut at each chord: the chord and the lyrics sung from it. The lyrics
+    /// before the first chord have no chord, and a blank line has no chunk.
+    Lyrics(Vec<(Option<Chords>, String)>),
+    /// A comment (`{comment: ...}`), eg. "Repeat twice".
+    Comment(String),
+}
+
+/// A section of a song: a verse, a chorus, a bridge... or the lines written outside of any
+/// section, which are split into paragraphs at the blank lines.
+pub struct Section {
+    kind: String,
+    label: String,
+    lines: Vec<Line>,
+}
+
+/// A ChordPro song.
+pub struct Song {
+    title: String,
+    key: Option<Key>,
+    spelling: Spelling,
+    directives: Vec<(String, String)>,
+    sections: Vec<Section>,
+    warnings: Vec<String>,
+}
+
+impl Line {
+    /// A line of lyrics without chords.
+    pub fn lyrics(text: &str) -> Self {
+        Line::Lyrics(match text.is_empty() {
+            true => vec![],
+            false => vec![(None, text.to_string())],
+        })
+    }
+
+    /// Returns the chords of the line.
+    pub fn chords(&self) -> impl Iterator<Item = &Chords> {
+        let chunks = match self {
+            Line::Lyrics(chunks) => chunks.as_slice(),
+            Line::Comment(_) => &[],
+        };
+        chunks.iter().filter_map(|(chord, _)| chord.as_ref())
+    }
+
+    /// Returns the text of the line, without its chords.
+    pub fn text(&self) -> String {
+        match self {
+            Line::Lyrics(chunks) => chunks.iter().map(|(_, lyrics)| lyrics.as_str()).collect(),
+            Line::Comment(comment) => comment.clone(),
+        }
+    }
+}
+
+impl Section {
+    /// A section of the given kind, eg. "verse" or "chorus" (`{start_of_verse}`,
+    /// `{start_of_chorus}`), the empty kind being the lines outside of any section.
+    pub fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            label: String::new(),
+            lines: vec![],
+        }
+    }
+
+    /// Sets the label of the section, eg. "Verse 2".
+    pub fn with_label(self, label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            ..self
+        }
+    }
+
+    pub fn with_line(self, line: Line) -> Self {
+        let mut lines = self.lines;
+        lines.push(line);
+        Self { lines, ..self }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// The name written above the section: its label, or its kind capitalized (eg.
+    /// "Chorus").
+    fn heading(&self) -> String {
+        match self.label.is_empty() {
+            false => self.label.clone(),
+            true => {
+                let mut chars = self.kind.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            }
+        }
+    }
+}
+
+impl Song {
+    /// A song without key, its chords being written with sharps.
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            key: None,
+            spelling: Spelling::Sharps,
+            directives: vec![],
+            sections: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Sets the key (the `{key:}` directive), which also gives the spelling of the chords.
+    pub fn with_key(self, key: Key) -> Self {
+        Self {
+            key: Some(key),
+            spelling: Spelling::Key(key),
+            ..self
+        }
+    }
+
+    /// Adds a directive written in the header, eg. ("artist", "Joseph Kosma").
+    pub fn with_directive(self, name: &str, value: &str) -> Self {
+        let mut directives = self.directives;
+        directives.push((name.to_string(), value.to_string()));
+        Self { directives, ..self }
+    }
+
+    pub fn with_section(self, section: Section) -> Self {
+        let mut sections = self.sections;
+        sections.push(section);
+        Self { sections, ..self }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    /// Returns the value of the first directive with the given name, eg. "capo".
+    pub fn directive(&self, name: &str) -> Option<&str> {
+        self.directives
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the directives of the header other than the title and the key, with their
+    /// value, in the order they are written.
+    pub fn directives(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.directives
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Returns the chords of the song, in the order they are played.
+    pub fn chords(&self) -> impl Iterator<Item = &Chords> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.lines.iter())
+            .flat_map(Line::chords)
+    }
+
+    /// Returns what was skipped when the song was read, eg. "line 7: skipped the unknown
+    /// chord \"Csus4\"".
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// Reads a ChordPro text.
+    ///
+    /// The title (`{title:}`, `{t:}`), the key (`{key:}`) and the other directives of the
+    /// header are read, then the sections (`{start_of_verse}`, `{soc}`, `{start_of_bridge:
+    /// label}`...), the comments (`{comment:}`, `{c:}`...) and the lyrics with their chords.
+    /// The lines of a tab or a grid are kept as they are, and the lines starting with `#`
+    /// are skipped. The chords which are not known and the unknown keys are skipped with a
+    /// warning. A slash chord is read without its bass.
+    pub fn from_chordpro(text: &str) -> Result<Self, ChordProError> {
+        read::song(text)
+    }
+
+    /// Reads a `.cho` (or `.chordpro`) file.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, ChordProError> {
+        Self::from_chordpro(&std::fs::read_to_string(path)?)
+    }
+
+    /// Transposes the song up by the interval: its chords and its key.
+    pub fn transpose(self, interval: Interval) -> Self {
+        let semitones = *interval.as_ref();
+        let key = self
+            .key
+            .map(|key| Key::new(key.tonic() + Interval::from(semitones), key.mode()));
+        let sections = self
+            .sections
+            .into_iter()
+            .map(|section| Section {
+                lines: section
+                    .lines
+                    .into_iter()
+                    .map(|line| transpose_line(line, semitones))
+                    .collect(),
+                ..section
+            })
+            .collect();
+        Self {
+            key,
+            spelling: key.map_or(self.spelling, Spelling::Key),
+            sections,
+            ..self
+        }
+    }
+
+    /// Transposes the song to the key. If the song has no key, its first chord is taken as
+    /// its tonic; and if the modes of the keys are not the same, the song is transposed to
+    /// the relative key of the given one.
+    pub fn transpose_to(self, key: Key) -> Self {
+        let from = self
+            .key
+            .or_else(|| self.chords().next().map(|c| Key::major(c.root())));
+        let Some(from) = from else {
+            return self.with_key(key);
+        };
+        let to = match from.mode() == key.mode() {
+            true => key,
+            false => key.relative(),
+        };
+        let semitones = (to.tonic().pitch_class() as i8 - from.tonic().pitch_class() as i8)
+            .rem_euclid(12) as u8;
+        let song = self.transpose(Interval::from(semitones));
+        match song.key {
+            Some(_) => song,
+            None => Self {
+                spelling: Spelling::Key(to),
+                ..song
+            },
+        }
+    }
+
+    /// The ChordPro text of the song.
+    pub fn to_chordpro(&self) -> String {
+        write::chordpro(self)
+    }
+
+    /// The song as chords over lyrics: the title, then each section with its heading, each
+    /// chord being written above the lyrics sung from it.
+    pub fn to_text(&self) -> String {
+        write::text(self)
+    }
+
+    /// Writes the `.cho` file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_chordpro())
+    }
+}
+
+/// The line with its chords transposed up by the semitones.
+fn transpose_line(line: Line, semitones: u8) -> Line {
+    match line {
+        Line::Lyrics(chunks) => Line::Lyrics(
+            chunks
+                .into_iter()
+                .map(|(chord, lyrics)| (chord.map(|c| transpose_chord(&c, semitones)), lyrics))
+                .collect(),
+        ),
+        comment => comment,
+    }
+}
+
+/// The chord with the same suffix, from the root transposed up by the semitones.
+fn transpose_chord(chord: &Chords, semitones: u8) -> Chords {
+    let root = chord.root() + Interval::from(semitones);
+    Chords::all_chords(root)
+        .find(|c| c.suffix() == chord.suffix())
+        .expect("Song: every chord exists on every root")
+}
@@ -0,0 +1,367 @@
+use std::fmt::Display;
+
+use super::{Line, Section, Song};
+use crate::{
+    chords::{Chord, Chords},
+    Key, Spelling,
+};
+
+/// The errors of reading a ChordPro text.
+#[derive(Debug)]
+pub enum ChordProError {
+    Io(std::io::Error),
+    /// A directive or a chord of a line is not closed.
+    Syntax(usize, String),
+}
+
+/// The state of a song being read.
+struct SongReader {
+    song: Song,
+    /// The section being read, if any.
+    section: Option<Section>,
+    /// Whether a chord was written with a flat, to spell the chords with flats when the song
+    /// has no key.
+    flats: bool,
+    line: usize,
+}
+
+/// The kinds of the sections with a short directive, eg. `{soc}` for `{start_of_chorus}`.
+const SHORT_SECTIONS: [(char, &str); 5] = [
+    ('v', "verse"),
+    ('c', "chorus"),
+    ('b', "bridge"),
+    ('t', "tab"),
+    ('g', "grid"),
+];
+
+/// Reads a ChordPro text, line by line.
+pub(super) fn song(text: &str) -> Result<Song, ChordProError> {
+    let mut reader = SongReader {
+        song: Song::new(""),
+        section: None,
+        flats: false,
+        line: 0,
+    };
+    for (index, line) in text.lines().enumerate() {
+        reader.line = index + 1;
+        reader.read(line)?;
+    }
+    Ok(reader.finish())
+}
+
+impl SongReader {
+    fn read(&mut self, line: &str) -> Result<(), ChordProError> {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            return Ok(());
+        }
+        if trimmed.starts_with('{') {
+            let Some(directive) = trimmed.strip_prefix('{').and_then(|d| d.strip_suffix('}'))
+            else {
+                return Err(self.error("unclosed directive"));
+            };
+            self.directive(directive);
+            return Ok(());
+        }
+
+        let verbatim = self
+            .section
+            .as_ref()
+            .is_some_and(|s| matches!(s.kind.as_str(), "tab" | "grid"));
+        if verbatim {
+            self.push(Line::lyrics(line.trim_end()));
+        } else if trimmed.is_empty() {
+            // A blank line ends a paragraph outside of the sections.
+            match self.section.as_ref().is_some_and(|s| !s.kind.is_empty()) {
+                true => self.push(Line::Lyrics(vec![])),
+                false => self.close(),
+            }
+        } else {
+            let chunks = self.lyrics(line.trim_end())?;
+            self.push(Line::Lyrics(chunks));
+        }
+        Ok(())
+    }
+
+    /// Reads a directive, without its braces.
+    fn directive(&mut self, directive: &str) {
+        let (name, value) = match directive.split_once(':') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => match directive.trim().split_once(' ') {
+                Some((name, value)) => (name, value.trim()),
+                None => (directive.trim(), ""),
+            },
+        };
+        let name = name.to_lowercase();
+
+        match name.as_str() {
+            "title" | "t" => self.song.title = value.to_string(),
+            "subtitle" | "st" => self.header("subtitle", value),
+            "key" => match (self.song.key, key(value)) {
+                (None, Some(key)) => self.song.key = Some(key),
+                (None, None) => self.warn(format!("skipped the unknown key {value:?}")),
+                (Some(_), _) => self.warn(format!("skipped the key change to {value:?}")),
+            },
+            "comment" | "c" | "comment_italic" | "ci" | "comment_box" | "cb" | "highlight" => {
+                self.push(Line::Comment(value.to_string()))
+            }
+            name => match section(name) {
+                Some((true, kind)) => {
+                    self.close();
+                    let label = value
+                        .strip_prefix("label=")
+                        .map_or(value, |label| label.trim_matches('"'));
+                    self.section = Some(Section::new(&kind).with_label(label));
+                }
+                Some((false, kind)) => match &self.section {
+                    Some(section) if section.kind == kind => self.close(),
+                    _ => self.warn(format!("skipped {{{name}}} outside of a {kind}")),
+                },
+                None => self.header(name, value),
+            },
+        }
+    }
+
+    /// Reads the lyrics of a line, cut at each chord.
+    fn lyrics(&mut self, line: &str) -> Result<Vec<(Option<Chords>, String)>, ChordProError> {
+        let mut chunks: Vec<(Option<Chords>, String)> = vec![];
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            lyrics(&mut chunks, &rest[..start]);
+            let Some(end) = rest[start..].find(']') else {
+                return Err(self.error("unclosed chord"));
+            };
+            let symbol = rest[start + 1..start + end].trim();
+            match symbol.parse::<Chords>() {
+                Ok(chord) => {
+                    let accidental = symbol.chars().nth(1);
+                    self.flats |= matches!(accidental, Some('b' | '♭'));
+                    chunks.push((Some(chord), String::new()));
+                }
+                Err(_) => self.warn(format!("skipped the unknown chord {symbol:?}")),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        lyrics(&mut chunks, rest);
+        Ok(chunks)
+    }
+
+    fn header(&mut self, name: &str, value: &str) {
+        self.song
+            .directives
+            .push((name.to_string(), value.to_string()));
+    }
+
+    /// Adds a line to the section being read, or to a new paragraph outside of the sections.
+    fn push(&mut self, line: Line) {
+        self.section
+            .get_or_insert_with(|| Section::new(""))
+            .lines
+            .push(line);
+    }
+
+    /// Ends the section being read.
+    fn close(&mut self) {
+        if let Some(section) = self.section.take() {
+            if !section.kind.is_empty() || !section.lines.is_empty() {
+                self.song.sections.push(section);
+            }
+        }
+    }
+
+    fn warn(&mut self, warning: String) {
+        let warning = format!("line {}: {warning}", self.line);
+        self.song.warnings.push(warning);
+    }
+
+    fn error(&self, message: &str) -> ChordProError {
+        ChordProError::Syntax(self.line, message.to_string())
+    }
+
+    fn finish(mut self) -> Song {
+        self.close();
+        let spelling = match (self.song.key, self.flats) {
+            (Some(key), _) => Spelling::Key(key),
+            (None, true) => Spelling::Flats,
+            (None, false) => Spelling::Sharps,
+        };
+        Song {
+            spelling,
+            ..self.song
+        }
+    }
+}
+
+/// Adds lyrics to the last chunk of a line.
+fn lyrics(chunks: &mut Vec<(Option<Chords>, String)>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match chunks.last_mut() {
+        Some((_, lyrics)) => lyrics.push_str(text),
+        None => chunks.push((None, text.to_string())),
+    }
+}
+
+/// Whether the directive starts or ends a section, and the kind of the section, eg.
+/// `(true, "chorus")` for `start_of_chorus` or `soc`.
+fn section(name: &str) -> Option<(bool, String)> {
+    if let Some(kind) = name.strip_prefix("start_of_") {
+        return Some((true, kind.to_string()));
+    }
+    if let Some(kind) = name.strip_prefix("end_of_") {
+        return Some((false, kind.to_string()));
+    }
+    let mut chars = name.chars();
+    let start = match (chars.next(), chars.next()) {
+        (Some('s'), Some('o')) => true,
+        (Some('e'), Some('o')) => false,
+        _ => return None,
+    };
+    let short = chars.next().filter(|_| chars.next().is_none())?;
+    SHORT_SECTIONS
+        .iter()
+        .find(|(c, _)| *c == short)
+        .map(|(_, kind)| (start, kind.to_string()))
+}
+
+/// The key written as a major or a minor chord, eg. "Bb" or "F#m".
+fn key(value: &str) -> Option<Key> {
+    match value.parse::<Chords>().ok()? {
+        chord @ Chords::Major(_, _) if chord.suffix().is_empty() => Some(Key::major(chord.root())),
+        chord @ Chords::Minor(_, _) if chord.suffix() == "m" => Some(Key::minor(chord.root())),
+        _ => None,
+    }
+}
+
+impl Display for ChordProError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChordProError::Io(e) => write!(f, "{e}"),
+            ChordProError::Syntax(line, message) => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ChordProError {}
+
+impl From<std::io::Error> for ChordProError {
+    fn from(e: std::io::Error) -> Self {
+        ChordProError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mode, A, C, G};
+
+    const SONG: &str = "\
+# A comment of the file
+{title: Little Song}
+{st: Traditional}
+{key: G}
+{capo: 2}
+
+[G]Row, row, [D7]row your boat
+[C]Gently [G]down the stream
+
+{start_of_chorus: Refrain}
+[Em]Merrily, [Am7]merrily
+{c: Twice}
+
+{eoc}
+{sot}
+e|--3--[x]--|
+{end_of_tab}
+";
+
+    #[test]
+    fn header() {
+        let song = Song::from_chordpro(SONG).unwrap();
+        assert_eq!(song.title(), "Little Song");
+        assert_eq!(song.key(), Some(Key::major(G)));
+        assert_eq!(song.directive("subtitle"), Some("Traditional"));
+        assert_eq!(
+            song.directives().collect::<Vec<_>>(),
+            [("subtitle", "Traditional"), ("capo", "2")]
+        );
+        assert_eq!(song.warnings().count(), 0);
+    }
+
+    #[test]
+    fn sections() {
+        let song = Song::from_chordpro(SONG).unwrap();
+        let sections = song.sections();
+        let kinds = sections.iter().map(|s| s.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, ["", "chorus", "tab"]);
+        assert_eq!(sections[1].label(), "Refrain");
+
+        let verse = &sections[0].lines();
+        assert_eq!(verse.len(), 2);
+        assert_eq!(verse[0].text(), "Row, row, row your boat");
+        let chords = verse[0].chords().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(chords, ["G", "D7"]);
+
+        let chorus = sections[1].lines();
+        assert!(matches!(&chorus[1], Line::Comment(c) if c == "Twice"));
+        assert!(matches!(&chorus[2], Line::Lyrics(chunks) if chunks.is_empty()));
+        assert_eq!(sections[2].lines()[0].text(), "e|--3--[x]--|");
+        assert_eq!(song.chords().count(), 6);
+    }
+
+    #[test]
+    fn chunks() {
+        let song = Song::from_chordpro("Oh [Bb]Su[F/A]san[Csus4]na\n").unwrap();
+        let Line::Lyrics(chunks) = &song.sections()[0].lines()[0] else {
+            panic!("not lyrics");
+        };
+        let chunks = chunks
+            .iter()
+            .map(|(chord, lyrics)| (chord.as_ref().map(|c| c.to_string()), lyrics.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chunks,
+            [
+                (None, "Oh "),
+                (Some("A#".to_string()), "Su"),
+                (Some("F".to_string()), "sanna"),
+            ]
+        );
+        assert_eq!(
+            song.warnings().collect::<Vec<_>>(),
+            [r#"line 1: skipped the unknown chord "Csus4""#]
+        );
+        assert_eq!(song.spelling, Spelling::Flats);
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(key("Am"), Some(Key::minor(A)));
+        assert_eq!(key("C").map(|k| k.mode()), Some(Mode::Major));
+        assert_eq!(key("Cmaj7"), None);
+        assert_eq!(key(" C "), Some(Key::major(C)));
+
+        let song = Song::from_chordpro("{key: H}\n{key: C}\n{eov}\n").unwrap();
+        assert_eq!(song.key(), Some(Key::major(C)));
+        assert_eq!(
+            song.warnings().collect::<Vec<_>>(),
+            [
+                r#"line 1: skipped the unknown key "H""#,
+                "line 3: skipped {eov} outside of a verse",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = Song::from_chordpro("{title: A}\n[Am lyrics\n")
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "line 2: unclosed chord");
+        assert!(matches!(
+            Song::from_chordpro("{title: A").err(),
+            Some(ChordProError::Syntax(1, _))
+        ));
+    }
+}
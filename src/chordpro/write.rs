@@ -0,0 +1,191 @@
+use super::{Line, Section, Song};
+use crate::{chords::Chords, Mode, Spelling};
+
+/// The ChordPro text of a song: the directives of the header, then the sections separated
+/// by blank lines.
+pub(super) fn chordpro(song: &Song) -> String {
+    let mut header = vec![];
+    if !song.title.is_empty() {
+        header.push(format!("{{title: {}}}", song.title));
+    }
+    if let Some(key) = song.key {
+        let minor = match key.mode() {
+            Mode::Major => "",
+            Mode::Minor => "m",
+        };
+        header.push(format!(
+            "{{key: {}{minor}}}",
+            song.spelling.note(key.tonic())
+        ));
+    }
+    for (name, value) in &song.directives {
+        header.push(match value.is_empty() {
+            true => format!("{{{name}}}"),
+            false => format!("{{{name}: {value}}}"),
+        });
+    }
+
+    let sections = song.sections.iter().map(|section| {
+        let mut lines = vec![];
+        if !section.kind.is_empty() {
+            lines.push(match section.label.is_empty() {
+                true => format!("{{start_of_{}}}", section.kind),
+                false => format!("{{start_of_{}: {}}}", section.kind, section.label),
+            });
+        }
+        for line in &section.lines {
+            lines.push(match line {
+                Line::Lyrics(chunks) => chunks
+                    .iter()
+                    .map(|(chord, lyrics)| match chord {
+                        Some(chord) => format!("[{}]{lyrics}", song.spelling.chord(chord)),
+                        None => lyrics.clone(),
+                    })
+                    .collect(),
+                Line::Comment(comment) => format!("{{comment: {comment}}}"),
+            });
+        }
+        if !section.kind.is_empty() {
+            lines.push(format!("{{end_of_{}}}", section.kind));
+        }
+        lines.join("\n") + "\n"
+    });
+
+    let blocks = match header.is_empty() {
+        true => vec![],
+        false => vec![header.join("\n") + "\n"],
+    };
+    blocks
+        .into_iter()
+        .chain(sections)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The song as chords over lyrics: the title, then the sections separated by blank lines,
+/// each one with its heading.
+pub(super) fn text(song: &Song) -> String {
+    let title = match song.title.is_empty() {
+        true => vec![],
+        false => vec![format!("{}\n", song.title)],
+    };
+    let sections = song
+        .sections
+        .iter()
+        .map(|section| section_text(section, &song.spelling));
+    title
+        .into_iter()
+        .chain(sections)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn section_text(section: &Section, spelling: &Spelling) -> String {
+    let heading = section.heading();
+    let mut lines = match heading.is_empty() {
+        true => vec![],
+        false => vec![heading],
+    };
+    for line in &section.lines {
+        match line {
+            Line::Lyrics(chunks) => lines.extend(chords_over_lyrics(chunks, spelling)),
+            Line::Comment(comment) => lines.push(comment.clone()),
+        }
+    }
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// A line of chords and a line of lyrics, each chord being written above the lyrics sung
+/// from it. The lyrics are spread with spaces when a chord is longer than its lyrics.
+fn chords_over_lyrics(chunks: &[(Option<Chords>, String)], spelling: &Spelling) -> Vec<String> {
+    let mut chords = String::new();
+    let mut lyrics = String::new();
+    for (chord, text) in chunks {
+        if let Some(chord) = chord {
+            let chords_end = chords.chars().count();
+            let column = match chords_end {
+                0 => 0,
+                end => end + 1,
+            }
+            .max(lyrics.chars().count());
+            pad(&mut chords, column);
+            pad(&mut lyrics, column);
+            chords.push_str(&spelling.chord(chord));
+        }
+        lyrics.push_str(text);
+    }
+
+    let lyrics = lyrics.trim_end().to_string();
+    match (chords.is_empty(), lyrics.is_empty()) {
+        (true, _) => vec![lyrics],
+        (false, true) => vec![chords],
+        (false, false) => vec![chords, lyrics],
+    }
+}
+
+/// Completes the text with spaces up to the column.
+fn pad(text: &mut String, column: usize) {
+    let length = text.chars().count();
+    text.push_str(&" ".repeat(column.saturating_sub(length)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Key, A, A_SHARP, C, D, E, F, G, MAJOR_2ND, MINOR_7TH};
+
+    #[test]
+    fn chordpro() {
+        let text = "{title: Song}\n{key: F}\n{artist: Someone}\n\n[F]One [Bb]two\n\n{start_of_verse: Verse 2}\n[C7]Three\n\n{comment: Slowly}\n{end_of_verse}\n";
+        let song = Song::from_chordpro(text).unwrap();
+        assert_eq!(song.to_chordpro(), text);
+    }
+
+    #[test]
+    fn text() {
+        let verse = Section::new("verse")
+            .with_line(Line::Lyrics(vec![
+                (None, "I ".to_string()),
+                (Some(D.min7()), "know".to_string()),
+                (Some(G.dom13b9b13()), "".to_string()),
+                (Some(C.maj7()), "you".to_string()),
+            ]))
+            .with_line(Line::Lyrics(vec![(Some(A.min()), "".to_string())]))
+            .with_line(Line::Comment("Repeat".to_string()))
+            .with_line(Line::lyrics("The end"));
+        let song = Song::new("Song").with_section(verse);
+        assert_eq!(
+            song.to_text(),
+            "Song\n\nVerse\n  Dm7 G13b9b13 Cmaj7\nI know         you\nAm\nRepeat\nThe end\n"
+        );
+    }
+
+    #[test]
+    fn transpose() {
+        let text = "{key: F}\n[F]One [Bb]two [C7]three\n";
+        let song = Song::from_chordpro(text).unwrap().transpose(MAJOR_2ND);
+        assert_eq!(song.key(), Some(Key::major(G)));
+        assert_eq!(song.to_chordpro(), "{key: G}\n\n[G]One [C]two [D7]three\n");
+
+        let song = song.transpose(MINOR_7TH);
+        assert_eq!(song.to_chordpro(), "{key: F}\n\n[F]One [Bb]two [C7]three\n");
+
+        let song = Song::from_chordpro(text)
+            .unwrap()
+            .transpose_to(Key::minor(E));
+        assert_eq!(song.key(), Some(Key::major(G)));
+
+        let song = Song::from_chordpro("[Bb]One [F]two\n").unwrap();
+        let song = song.transpose_to(Key::major(F));
+        assert_eq!(song.key(), None);
+        assert_eq!(song.to_chordpro(), "[F]One [C]two\n");
+        assert_eq!(
+            song.transpose_to(Key::major(A_SHARP)).to_chordpro(),
+            "[Bb]One [F]two\n"
+        );
+        assert_eq!(
+            Song::new("").transpose_to(Key::major(F)).key(),
+            Some(Key::major(F))
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use super::{Chord, Chords};
+use crate::{Interval, Note};
+use std::fmt::Display;
+
+/// The quality of a chord, derived from the intervals of its notes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quality {
+    Major,
+    Minor,
+    Dominant,
+    HalfDiminished,
+    Diminished,
+    Augmented,
+    Suspended,
+}
+
+/// The degree a note plays inside a chord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Degree {
+    Root,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Seventh,
+    Ninth,
+    Eleventh,
+    Thirteenth,
+}
+
+/// The alteration of a degree, relative to the major scale of the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Alteration {
+    DoubleFlat,
+    Flat,
+    Natural,
+    Sharp,
+}
+
+/// A note of a chord together with the degree it plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChordDegree {
+    degree: Degree,
+    alteration: Alteration,
+    note: Note,
+}
+
+impl Degree {
+    /// The distance in semitones from the root for the natural degree.
+    const fn natural_semitones(&self) -> i8 {
+        match self {
+            Degree::Root => 0,
+            Degree::Second | Degree::Ninth => 2,
+            Degree::Third => 4,
+            Degree::Fourth | Degree::Eleventh => 5,
+            Degree::Fifth => 7,
+            Degree::Seventh => 11,
+            Degree::Thirteenth => 9,
+        }
+    }
+
+    /// Returns true for the degrees which build the chord (1, 2, 3, 4, 5, 7).
+    pub fn is_chord_tone(&self) -> bool {
+        !self.is_tension()
+    }
+
+    /// Returns true for the upper extensions of the chord (9, 11, 13).
+    pub fn is_tension(&self) -> bool {
+        matches!(self, Degree::Ninth | Degree::Eleventh | Degree::Thirteenth)
+    }
+}
+
+impl Alteration {
+    const fn offset(&self) -> i8 {
+        match self {
+            Alteration::DoubleFlat => -2,
+            Alteration::Flat => -1,
+            Alteration::Natural => 0,
+            Alteration::Sharp => 1,
+        }
+    }
+}
+
+impl ChordDegree {
+    pub fn new(degree: Degree, alteration: Alteration, note: Note) -> Self {
+        Self {
+            degree,
+            alteration,
+            note,
+        }
+    }
+
+    pub fn degree(&self) -> Degree {
+        self.degree
+    }
+
+    pub fn alteration(&self) -> Alteration {
+        self.alteration
+    }
+
+    pub fn note(&self) -> Note {
+        self.note
+    }
+
+    /// The distance in semitones from the root of the chord, inside one octave.
+    pub fn semitones(&self) -> u8 {
+        semitones_of(self.degree, self.alteration)
+    }
+
+    pub fn is_chord_tone(&self) -> bool {
+        self.degree.is_chord_tone()
+    }
+
+    pub fn is_tension(&self) -> bool {
+        self.degree.is_tension()
+    }
+}
+
+fn semitones_of(degree: Degree, alteration: Alteration) -> u8 {
+    (degree.natural_semitones() + alteration.offset()).rem_euclid(12) as u8
+}
+
+/// Returns the distance in semitones from the root to the note, inside one octave.
+fn offset(root: Note, note: Note) -> u8 {
+    (note.pitch_class() + 12 - root.pitch_class()) % 12
+}
+
+/// Classifies a note, given as a distance from the root, in the context of all the other
+/// distances present in the chord.
+fn classify(semitones: u8, has: impl Fn(u8) -> bool) -> (Degree, Alteration) {
+    let no_third = !has(3) && !has(4);
+    let diminished = has(3) && has(6) && !has(7) && !has(10) && !has(11);
+
+    match semitones {
+        0 => (Degree::Root, Alteration::Natural),
+        1 => (Degree::Ninth, Alteration::Flat),
+        2 if no_third && !has(5) => (Degree::Second, Alteration::Natural),
+        2 => (Degree::Ninth, Alteration::Natural),
+        3 if has(4) => (Degree::Ninth, Alteration::Sharp),
+        3 => (Degree::Third, Alteration::Flat),
+        4 => (Degree::Third, Alteration::Natural),
+        5 if no_third => (Degree::Fourth, Alteration::Natural),
+        5 => (Degree::Eleventh, Alteration::Natural),
+        6 if has(7) => (Degree::Eleventh, Alteration::Sharp),
+        6 => (Degree::Fifth, Alteration::Flat),
+        7 => (Degree::Fifth, Alteration::Natural),
+        8 if has(7) => (Degree::Thirteenth, Alteration::Flat),
+        8 => (Degree::Fifth, Alteration::Sharp),
+        9 if diminished => (Degree::Seventh, Alteration::DoubleFlat),
+        9 => (Degree::Thirteenth, Alteration::Natural),
+        10 => (Degree::Seventh, Alteration::Flat),
+        _ => (Degree::Seventh, Alteration::Natural),
+    }
+}
+
+impl Chords {
+    /// Returns the notes of the chord together with the degree each one plays,
+    /// ordered by degree.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{chords::{Alteration, Degree}, *};
+    ///
+    /// let chord = A.dom13b9b13();
+    /// let degrees = chord
+    ///     .degrees()
+    ///     .iter()
+    ///     .map(|d| d.to_string())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(degrees, ["1", "3", "5", "b7", "b9", "11", "b13"]);
+    /// assert!(chord.contains_degree(Degree::Ninth, Alteration::Flat));
+    /// ```
+    pub fn degrees(&self) -> Vec<ChordDegree> {
+        let root = self.root();
+        let mut mask = [false; 12];
+        for note in self.notes() {
+            mask[offset(root, *note) as usize] = true;
+        }
+
+        let mut degrees = self
+            .notes()
+            .map(|note| {
+                let (degree, alteration) = classify(offset(root, *note), |s| mask[s as usize]);
+                ChordDegree::new(degree, alteration, *note)
+            })
+            .collect::<Vec<_>>();
+        degrees.sort_by_key(|d| d.degree);
+        degrees
+    }
+
+    /// Returns the note playing the given degree, if the chord has it.
+    pub fn degree(&self, degree: Degree) -> Option<ChordDegree> {
+        self.degrees().into_iter().find(|d| d.degree == degree)
+    }
+
+    /// Returns true if the chord contains the given degree with the given alteration
+    /// (eg. a b9).
+    pub fn contains_degree(&self, degree: Degree, alteration: Alteration) -> bool {
+        self.degrees()
+            .iter()
+            .any(|d| d.degree == degree && d.alteration == alteration)
+    }
+
+    /// Returns the notes which build the chord (root, third, fifth, seventh).
+    pub fn chord_tones(&self) -> Vec<ChordDegree> {
+        self.degrees()
+            .into_iter()
+            .filter(|d| d.is_chord_tone())
+            .collect()
+    }
+
+    /// Returns the upper extensions of the chord (9, 11, 13 and their alterations).
+    pub fn tensions(&self) -> Vec<ChordDegree> {
+        self.degrees()
+            .into_iter()
+            .filter(|d| d.is_tension())
+            .collect()
+    }
+
+    /// Returns the quality of the chord, derived from its third, fifth and seventh.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{chords::Quality, *};
+    ///
+    /// assert_eq!(G.dom13().quality(), Quality::Dominant);
+    /// assert_eq!(B.min7b5().quality(), Quality::HalfDiminished);
+    /// ```
+    pub fn quality(&self) -> Quality {
+        let degrees = self.degrees();
+        let alteration_of = |degree| {
+            degrees
+                .iter()
+                .find(|d| d.degree == degree)
+                .map(|d| d.alteration)
+        };
+
+        let third = alteration_of(Degree::Third);
+        let fifth = alteration_of(Degree::Fifth);
+        let seventh = alteration_of(Degree::Seventh);
+
+        match (third, fifth, seventh) {
+            (None, _, _) => Quality::Suspended,
+            (Some(Alteration::Natural), _, Some(Alteration::Flat)) => Quality::Dominant,
+            (Some(Alteration::Natural), Some(Alteration::Sharp), _) => Quality::Augmented,
+            (Some(Alteration::Natural), _, _) => Quality::Major,
+            (_, Some(Alteration::Flat), Some(Alteration::Flat)) => Quality::HalfDiminished,
+            (_, Some(Alteration::Flat), _) => Quality::Diminished,
+            _ => Quality::Minor,
+        }
+    }
+
+    /// Returns the notes of the chord scale which sit a half step above a chord tone
+    /// and are not part of the chord (eg. the 11 over a major or dominant chord).
+    ///
+    /// The chord scale is the usual one for the quality of the chord (ionian for major,
+    /// dorian for minor, mixolydian for dominant, locrian for half-diminished, etc.),
+    /// with the tensions present in the chord replacing the default ones.
+    pub fn avoid_notes(&self) -> Vec<Note> {
+        let root = self.root();
+        let degrees = self.degrees();
+        let chord_tones = degrees
+            .iter()
+            .filter(|d| d.is_chord_tone())
+            .map(|d| d.semitones())
+            .collect::<Vec<_>>();
+
+        self.quality()
+            .scale_tensions()
+            .iter()
+            .map(|(degree, alteration)| {
+                degrees
+                    .iter()
+                    .find(|d| d.degree == *degree)
+                    .map(|d| d.semitones())
+                    .unwrap_or_else(|| semitones_of(*degree, *alteration))
+            })
+            .filter(|s| !degrees.iter().any(|d| d.semitones() == *s))
+            .filter(|s| chord_tones.contains(&((s + 11) % 12)))
+            .map(|s| (root + Interval::from(s)).base())
+            .collect()
+    }
+}
+
+impl Quality {
+    /// The default tensions (or missing chord tones) of the chord scale for the quality.
+    fn scale_tensions(&self) -> &'static [(Degree, Alteration)] {
+        use Alteration::*;
+        use Degree::*;
+
+        match self {
+            Quality::Major | Quality::Minor | Quality::Dominant => {
+                &[(Ninth, Natural), (Eleventh, Natural), (Thirteenth, Natural)]
+            }
+            Quality::HalfDiminished => &[(Ninth, Flat), (Eleventh, Natural), (Thirteenth, Flat)],
+            Quality::Diminished => &[(Ninth, Natural), (Eleventh, Natural), (Thirteenth, Flat)],
+            Quality::Augmented => &[(Ninth, Natural), (Eleventh, Sharp)],
+            Quality::Suspended => &[(Ninth, Natural), (Third, Natural), (Thirteenth, Natural)],
+        }
+    }
+}
+
+impl Display for Quality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Quality::Major => write!(f, "major"),
+            Quality::Minor => write!(f, "minor"),
+            Quality::Dominant => write!(f, "dominant"),
+            Quality::HalfDiminished => write!(f, "half-diminished"),
+            Quality::Diminished => write!(f, "diminished"),
+            Quality::Augmented => write!(f, "augmented"),
+            Quality::Suspended => write!(f, "suspended"),
+        }
+    }
+}
+
+impl Display for Degree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Degree::Root => write!(f, "1"),
+            Degree::Second => write!(f, "2"),
+            Degree::Third => write!(f, "3"),
+            Degree::Fourth => write!(f, "4"),
+            Degree::Fifth => write!(f, "5"),
+            Degree::Seventh => write!(f, "7"),
+            Degree::Ninth => write!(f, "9"),
+            Degree::Eleventh => write!(f, "11"),
+            Degree::Thirteenth => write!(f, "13"),
+        }
+    }
+}
+
+impl Display for Alteration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Alteration::DoubleFlat => write!(f, "bb"),
+            Alteration::Flat => write!(f, "b"),
+            Alteration::Natural => Ok(()),
+            Alteration::Sharp => write!(f, "#"),
+        }
+    }
+}
+
+impl Display for ChordDegree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.alteration, self.degree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, B, C, D, E, F, G};
+
+    fn names(degrees: Vec<ChordDegree>) -> Vec<String> {
+        degrees.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn quality() {
+        assert_eq!(C.maj().quality(), Quality::Major);
+        assert_eq!(C.maj13().quality(), Quality::Major);
+        assert_eq!(D.min9().quality(), Quality::Minor);
+        assert_eq!(G.dom7().quality(), Quality::Dominant);
+        assert_eq!(G.dom7s5().quality(), Quality::Dominant);
+        assert_eq!(B.min7b5().quality(), Quality::HalfDiminished);
+        assert_eq!(B.dim().quality(), Quality::Diminished);
+        assert_eq!(B.dim7().quality(), Quality::Diminished);
+    }
+
+    #[test]
+    fn degrees() {
+        assert_eq!(names(C.maj7().degrees()), ["1", "3", "5", "7"]);
+        assert_eq!(names(C.min7b5().degrees()), ["1", "b3", "b5", "b7"]);
+        assert_eq!(names(C.dim7().degrees()), ["1", "b3", "b5", "bb7"]);
+        assert_eq!(names(C.dom7s5().degrees()), ["1", "3", "#5", "b7"]);
+        assert_eq!(
+            names(C.dom13().degrees()),
+            ["1", "3", "5", "b7", "9", "11", "13"]
+        );
+    }
+
+    #[test]
+    fn seventh() {
+        let seventh = G.dom7().degree(Degree::Seventh).unwrap();
+        assert_eq!(seventh.note(), F);
+        assert_eq!(seventh.alteration(), Alteration::Flat);
+        assert!(C.maj().degree(Degree::Seventh).is_none());
+    }
+
+    #[test]
+    fn tensions() {
+        assert!(A
+            .dom13b9b13()
+            .contains_degree(Degree::Ninth, Alteration::Flat));
+        assert!(!G.dom13().contains_degree(Degree::Ninth, Alteration::Flat));
+        assert_eq!(names(D.min9().chord_tones()), ["1", "b3", "5", "b7"]);
+        assert_eq!(names(D.min9().tensions()), ["9"]);
+    }
+
+    #[test]
+    fn avoid_notes() {
+        assert_eq!(C.maj7().avoid_notes(), [F]);
+        assert_eq!(G.dom7().avoid_notes(), [C]);
+        assert!(D.min7().avoid_notes().is_empty());
+        assert_eq!(E.min7b5().avoid_notes(), [F]);
+    }
+}
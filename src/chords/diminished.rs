@@ -11,8 +11,11 @@ pub fn dim7(root: Note) -> Chords {
     Chords::diminished_with_steps("dim7", root, steps.into_iter())
 }
 
+/// The builders of the diminished chords, in the order of [`diminished_chords`].
+pub(super) const DIMINISHED: [fn(Note) -> Chords; 2] = [dim, dim7];
+
 pub fn diminished_chords(root: Note) -> impl Iterator<Item = Chords> {
-    [dim(root), dim7(root)].into_iter()
+    DIMINISHED.into_iter().map(move |build| build(root))
 }
 
 #[cfg(test)]
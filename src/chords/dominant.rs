@@ -36,17 +36,12 @@ pub fn dom13b9b13(root: Note) -> Chords {
     Chords::dominant_with_steps("13b9b13", root, steps.into_iter())
 }
 
+/// The builders of the dominant chords, in the order of [`dominant_chords`].
+pub(super) const DOMINANT: [fn(Note) -> Chords; 7] =
+    [dom7, dom7b5, dom7s5, dom9, dom11, dom13, dom13b9b13];
+
 pub fn dominant_chords(root: Note) -> impl Iterator<Item = Chords> {
-    [
-        dom7(root),
-        dom7b5(root),
-        dom7s5(root),
-        dom9(root),
-        dom11(root),
-        dom13(root),
-        dom13b9b13(root),
-    ]
-    .into_iter()
+    DOMINANT.into_iter().map(move |build| build(root))
 }
 
 #[cfg(test)]
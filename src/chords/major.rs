@@ -26,8 +26,11 @@ pub fn maj13(root: Note) -> Chords {
     Chords::major_with_steps("maj13", root, steps.into_iter())
 }
 
+/// The builders of the major chords, in the order of [`major_chords`].
+pub(super) const MAJOR: [fn(Note) -> Chords; 5] = [maj, maj7, maj9, maj11, maj13];
+
 pub fn major_chords(root: Note) -> impl Iterator<Item = Chords> {
-    [maj(root), maj7(root), maj9(root), maj11(root), maj13(root)].into_iter()
+    MAJOR.into_iter().map(move |build| build(root))
 }
 
 #[cfg(test)]
@@ -31,16 +31,11 @@ pub fn min13(root: Note) -> Chords {
     Chords::minor_with_steps("m13", root, steps.into_iter())
 }
 
+/// The builders of the minor chords, in the order of [`minor_chords`].
+pub(super) const MINOR: [fn(Note) -> Chords; 6] = [min, min7, min7b5, min9, min11, min13];
+
 pub fn minor_chords(root: Note) -> impl Iterator<Item = Chords> {
-    [
-        min(root),
-        min7(root),
-        min7b5(root),
-        min9(root),
-        min11(root),
-        min13(root),
-    ]
-    .into_iter()
+    MINOR.into_iter().map(move |build| build(root))
 }
 
 #[cfg(test)]
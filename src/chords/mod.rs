@@ -1,15 +1,23 @@
-use crate::{Note, NoteStepperIterator, Tone};
-use std::fmt::{Display, LowerHex, UpperHex};
+use crate::{Note, NoteStepperIterator, PitchClassSet, Tone, C};
+use std::{
+    fmt::{Display, LowerHex, UpperHex},
+    str::FromStr,
+    sync::OnceLock,
+};
 
+mod analysis;
 mod diminished;
 mod dominant;
 mod major;
 mod minor;
+mod recognize;
 
+pub use analysis::*;
 pub use diminished::*;
 pub use dominant::*;
 pub use major::*;
 pub use minor::*;
+pub use recognize::*;
 
 pub trait Chord {
     fn root(&self) -> Note;
@@ -24,6 +32,80 @@ pub enum Chords {
     Diminished(&'static str, Vec<Note>),
 }
 
+/// The error of reading a chord symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseChordError(String);
+
+/// The usual ways of writing the suffixes, and the suffix of the chords.
+const ALIASES: [(&str, &str); 26] = [
+    ("M", ""),
+    ("maj", ""),
+    ("mi", "m"),
+    ("min", "m"),
+    ("-", "m"),
+    ("M7", "maj7"),
+    ("Maj7", "maj7"),
+    ("ma7", "maj7"),
+    ("Δ", "maj7"),
+    ("Δ7", "maj7"),
+    ("M9", "maj9"),
+    ("Δ9", "maj9"),
+    ("mi7", "m7"),
+    ("min7", "m7"),
+    ("-7", "m7"),
+    ("m7b5", "m7(b5)"),
+    ("-7b5", "m7(b5)"),
+    ("ø", "m7(b5)"),
+    ("ø7", "m7(b5)"),
+    ("-9", "m9"),
+    ("o", "dim"),
+    ("°", "dim"),
+    ("o7", "dim7"),
+    ("°7", "dim7"),
+    ("7(b5)", "7b5"),
+    ("+7", "7#5"),
+];
+
+/// The builders of all the chords, in the order of [`Chords::find`].
+fn builders() -> impl Iterator<Item = fn(Note) -> Chords> {
+    MAJOR
+        .into_iter()
+        .chain(MINOR)
+        .chain(DOMINANT)
+        .chain(DIMINISHED)
+}
+
+/// A chord built on C, with its pitch classes, to look for chords without building them.
+struct Shape {
+    suffix: &'static str,
+    build: fn(Note) -> Chords,
+    classes: PitchClassSet,
+}
+
+impl Shape {
+    /// The shapes of all the chords, computed once.
+    fn all() -> &'static [Shape] {
+        static SHAPES: OnceLock<Vec<Shape>> = OnceLock::new();
+        SHAPES.get_or_init(|| {
+            builders()
+                .map(|build| {
+                    let chord = build(C);
+                    Shape {
+                        suffix: chord.suffix(),
+                        build,
+                        classes: chord.pitch_classes(),
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// The pitch classes of the chord built on the root.
+    fn classes(&self, root: Note) -> PitchClassSet {
+        self.classes.transpose(root.pitch_class() as i8)
+    }
+}
+
 impl Chords {
     fn major<N>(name: &'static str, notes: N) -> Self
     where
@@ -89,24 +171,31 @@ impl Chords {
         Self::diminished(name, notes)
     }
 
+    /// Whether the chord has all the notes, which are compared with the notes of the chord
+    /// in the C4 octave (see [`Chords::contains_pitch_classes`] for any octave).
     pub fn contains_notes<N>(&self, others: &mut N) -> bool
     where
         N: Iterator<Item = Note>,
     {
         let ns = self.inner_notes();
-        others.into_iter().all(|note| ns.contains(&note))
+        others.all(|note| ns.contains(&note))
+    }
+
+    /// Whether the chord has all the pitch classes, whatever the octave of the notes.
+    pub fn contains_pitch_classes(&self, classes: &PitchClassSet) -> bool {
+        classes.is_subset(&self.pitch_classes())
+    }
+
+    /// Returns the pitch classes of the chord.
+    pub fn pitch_classes(&self) -> PitchClassSet {
+        self.inner_notes().iter().copied().collect()
     }
 
     pub fn find<P>(root: Note, predicate: P) -> impl Iterator<Item = Self>
     where
         P: FnMut(&Self) -> bool,
     {
-        let mut chords = major_chords(root).collect::<Vec<_>>();
-        chords.extend(minor_chords(root));
-        chords.extend(dominant_chords(root));
-        chords.extend(diminished_chords(root));
-
-        chords.into_iter().filter(predicate)
+        builders().map(move |build| build(root)).filter(predicate)
     }
 
     pub fn all_chords(root: Note) -> impl Iterator<Item = Self> {
@@ -117,11 +206,20 @@ impl Chords {
     where
         N: Iterator<Item = Note>,
     {
+        // The notes of the chords are in the C4 octave: only their pitch classes are left to
+        // compare, and only the chords which match are built.
         let notes = notes.collect::<Vec<_>>();
+        let in_base = notes.iter().all(|note| *note == note.base());
+        let classes = notes.into_iter().collect::<PitchClassSet>();
+        Shape::all()
+            .iter()
+            .filter(move |shape| in_base && classes.is_subset(&shape.classes(root)))
+            .map(move |shape| (shape.build)(root))
+    }
 
-        Self::find(root, move |chord| {
-            chord.contains_notes(&mut notes.clone().into_iter())
-        })
+    /// Returns the name of the chord without its root (eg. "m7" for Dm7).
+    pub fn suffix(&self) -> &'static str {
+        self.inner_name()
     }
 
     fn inner_notes(&self) -> &Vec<Note> {
@@ -178,6 +276,60 @@ impl Chord for Chords {
     }
 }
 
+/// Reads a chord symbol: a root and a suffix (eg. "Bbmaj7", "F#m7(b5)"). The usual
+/// variants of the suffixes are understood (eg. "C-7", "CΔ", "Bø", "Go7"), and the bass of
+/// a slash chord is ignored.
+///
+/// # Example
+/// ```
+/// use musika_rs::{chords::{Chord, Chords}, *};
+///
+/// let chord: Chords = "Ebm7".parse().unwrap();
+/// assert_eq!(chord.root(), D_SHARP);
+/// assert_eq!(chord.suffix(), "m7");
+/// assert_eq!("C-7/G".parse::<Chords>().unwrap().to_string(), "Cm7");
+/// assert!("Csus4".parse::<Chords>().is_err());
+/// ```
+impl FromStr for Chords {
+    type Err = ParseChordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseChordError(s.to_string());
+        let symbol = s.split('/').next().unwrap_or_default().trim();
+        let split = symbol
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !matches!(c, '#' | 'b' | '♯' | '♭'))
+            .map_or(symbol.len(), |(index, _)| index);
+        let (root, suffix) = symbol.split_at(split);
+
+        let root = root.parse::<Note>().map_err(|_| error())?;
+        let suffix = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == suffix)
+            .map_or(suffix, |(_, suffix)| suffix);
+        Shape::all()
+            .iter()
+            .find(|shape| shape.suffix == suffix)
+            .map(|shape| (shape.build)(root))
+            .ok_or_else(error)
+    }
+}
+
+impl Display for ParseChordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown chord {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseChordError {}
+
+impl From<&Chords> for PitchClassSet {
+    fn from(chord: &Chords) -> Self {
+        chord.pitch_classes()
+    }
+}
+
 impl Display for Chords {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.root(), self.inner_name())
@@ -201,12 +353,65 @@ impl LowerHex for Chords {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{C, E, G};
+    use crate::{C, D, E, G, OCTAVE};
+
+    #[test]
+    fn from_str() {
+        let name = |s: &str| s.parse::<Chords>().map(|c| c.to_string());
+        assert_eq!(name("C").as_deref(), Ok("C"));
+        assert_eq!(name("Cmaj7").as_deref(), Ok("Cmaj7"));
+        assert_eq!(name("CΔ").as_deref(), Ok("Cmaj7"));
+        assert_eq!(name("Dm7").as_deref(), Ok("Dm7"));
+        assert_eq!(name("D-7").as_deref(), Ok("Dm7"));
+        assert_eq!(name("Bm7(b5)").as_deref(), Ok("Bm7(b5)"));
+        assert_eq!(name("Bø").as_deref(), Ok("Bm7(b5)"));
+        assert_eq!(name("Bbo7").as_deref(), Ok("A#dim7"));
+        assert_eq!(name("G13b9b13").as_deref(), Ok("G13b9b13"));
+        assert_eq!(name("F/A").as_deref(), Ok("F"));
+
+        assert_eq!(name("Hm"), Err(ParseChordError("Hm".to_string())));
+        assert_eq!(
+            "C6".parse::<Chords>().err().unwrap().to_string(),
+            r#"unknown chord "C6""#
+        );
+    }
 
     #[test]
     fn find_notes() {
         let notes = [C, E, G];
         let res = Chords::find_contain_notes(C, notes.into_iter()).collect::<Vec<Chords>>();
         assert!(!res.is_empty());
+
+        // The same chords as the ones found by building them all.
+        let found = Chords::find_contain_notes(D, [D, C].into_iter())
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        let built = Chords::find(D, |c| c.contains_notes(&mut [D, C].into_iter()))
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(found, built);
+        assert_eq!(found[..3], ["Dm7", "Dm7(b5)", "Dm9"]);
+        assert!(Chords::find_contain_notes(C, [E + OCTAVE].into_iter())
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn pitch_classes() {
+        let chord = C.dom7();
+        assert_eq!(chord.pitch_classes().forte_number(), "4-27");
+        assert!(chord.contains_notes(&mut [E, C].into_iter()));
+        assert!(!chord.contains_notes(&mut [E + OCTAVE, C].into_iter()));
+        assert!(!chord.contains_notes(&mut [D].into_iter()));
+        let classes = [E + OCTAVE, C].into_iter().collect();
+        assert!(chord.contains_pitch_classes(&classes));
+        assert!(!chord.contains_pitch_classes(&[D].into_iter().collect()));
+
+        let scale = crate::scales::major(C);
+        assert!(!chord
+            .pitch_classes()
+            .is_subset(&PitchClassSet::from(&scale)));
+        assert!(C.maj7().pitch_classes().is_subset(&scale.pitch_classes()));
+        assert_eq!(scale.pitch_classes().forte_number(), "7-35");
     }
 }
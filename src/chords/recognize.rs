@@ -0,0 +1,87 @@
+use super::{Chords, Shape};
+use crate::{Note, PitchClassSet};
+
+/// Returns the chord which matches best the given notes, whatever their octave, or `None`
+/// if there are less than two distinct notes.
+///
+/// The root of the chord must be one of the notes. A chord scores for each of its notes
+/// which is played, and loses for each of its notes which is missing and for each played
+/// note outside of it. Between equal scores, the chord whose root is the lowest note wins,
+/// then the simplest chord.
+///
+/// # Example
+/// ```
+/// use musika_rs::{chords::recognize, *};
+///
+/// let chord = recognize(&[D, F, A, C + OCTAVE]).unwrap();
+/// assert_eq!(format!("{chord}"), "Dm7");
+///
+/// // A shell voicing, without the fifth.
+/// let chord = recognize(&[G - OCTAVE, B, F]).unwrap();
+/// assert_eq!(format!("{chord}"), "G7");
+/// ```
+pub fn recognize(notes: &[Note]) -> Option<Chords> {
+    let played = notes.iter().copied().collect::<PitchClassSet>();
+    if played.len() < 2 {
+        return None;
+    }
+    let bass = notes.iter().min()?.pitch_class();
+
+    let mut best: Option<(i32, &Shape, Note)> = None;
+    for root in played.pitch_classes() {
+        let root = Note::from(root as i8);
+        for shape in Shape::all() {
+            let notes = shape.classes(root);
+            let matched = (notes & played).len() as i32;
+            let missing = (notes & !played).len() as i32;
+            let extra = (played & !notes).len() as i32;
+            let score =
+                4 * (2 * matched - 2 * missing - extra) + i32::from(root.pitch_class() == bass);
+
+            let better = match &best {
+                Some((best, _, _)) => score > *best,
+                None => true,
+            };
+            if better {
+                best = Some((score, shape, root));
+            }
+        }
+    }
+    best.map(|(_, shape, root)| (shape.build)(root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, A_SHARP, B, C, D, D_SHARP, E, F, F_SHARP, G, OCTAVE};
+
+    fn name(notes: &[Note]) -> Option<String> {
+        recognize(notes).map(|c| c.to_string())
+    }
+
+    #[test]
+    fn triads() {
+        assert_eq!(name(&[C, E, G]).as_deref(), Some("C"));
+        assert_eq!(name(&[E, G, C + OCTAVE]).as_deref(), Some("C"));
+        assert_eq!(name(&[A, C, E]).as_deref(), Some("Am"));
+        assert_eq!(name(&[B, D, F]).as_deref(), Some("Bdim"));
+    }
+
+    #[test]
+    fn sevenths() {
+        assert_eq!(name(&[C, E, G, B]).as_deref(), Some("Cmaj7"));
+        assert_eq!(name(&[G, B, D, F]).as_deref(), Some("G7"));
+        assert_eq!(name(&[C, D_SHARP, A_SHARP]).as_deref(), Some("Cm7"));
+        assert_eq!(
+            name(&[D - OCTAVE, F_SHARP, C + OCTAVE]).as_deref(),
+            Some("D7")
+        );
+    }
+
+    #[test]
+    fn too_few_notes() {
+        assert!(recognize(&[]).is_none());
+        assert!(recognize(&[C, C + OCTAVE]).is_none());
+        assert_eq!(name(&[C, E]).as_deref(), Some("C"));
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Sub},
+};
+
+/// A duration, as a fraction of the whole note, always kept reduced.
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let dotted = QUARTER.dotted();
+/// assert_eq!(dotted, QUARTER + EIGHTH);
+/// assert_eq!(EIGHTH.triplet() * 3, QUARTER);
+/// assert_eq!(Duration::from(8), EIGHTH);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Duration {
+    numerator: u32,
+    denominator: u32,
+}
+
+pub const WHOLE: Duration = Duration::whole(1);
+pub const HALF: Duration = Duration::whole(2);
+pub const QUARTER: Duration = Duration::whole(4);
+pub const EIGHTH: Duration = Duration::whole(8);
+pub const SIXTEENTH: Duration = Duration::whole(16);
+pub const THIRTY_SECOND: Duration = Duration::whole(32);
+pub const SIXTY_FOURTH: Duration = Duration::whole(64);
+
+impl Duration {
+    /// No duration at all.
+    pub const ZERO: Duration = Duration {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    /// Creates the duration numerator / denominator of a whole note.
+    ///
+    /// # Panics
+    /// Panics if the denominator is zero.
+    pub fn new(numerator: u32, denominator: u32) -> Self {
+        assert!(denominator != 0, "Duration: the denominator is zero");
+        let gcd = gcd(numerator, denominator);
+        Self {
+            numerator: numerator / gcd,
+            denominator: denominator / gcd,
+        }
+    }
+
+    /// The duration 1 / fraction of a whole note (eg. 4 for a quarter).
+    const fn whole(fraction: u32) -> Self {
+        Self {
+            numerator: 1,
+            denominator: fraction,
+        }
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    /// The duration with one dot: half of its value is added.
+    pub fn dotted(self) -> Self {
+        self.with_dots(1)
+    }
+
+    /// The duration with two dots: half and a quarter of its value are added.
+    pub fn double_dotted(self) -> Self {
+        self.with_dots(2)
+    }
+
+    /// The duration with the given number of dots, each one adding half of the previous one.
+    ///
+    /// # Panics
+    /// Panics if there are more than 8 dots.
+    pub fn with_dots(self, dots: u8) -> Self {
+        assert!(dots <= 8, "Duration: more than 8 dots");
+        let power = 1 << dots;
+        self * (2 * power - 1) / power
+    }
+
+    /// The duration inside a tuplet, where `actual` notes are played in the time of `normal`
+    /// ones (eg. 3:2 for a triplet, 5:4 for a quintuplet).
+    ///
+    /// # Panics
+    /// Panics if `actual` is zero.
+    pub fn tuplet(self, actual: u32, normal: u32) -> Self {
+        self * normal / actual
+    }
+
+    /// The duration inside a triplet (3:2).
+    pub fn triplet(self) -> Self {
+        self.tuplet(3, 2)
+    }
+
+    /// Ties the duration with another one.
+    pub fn tie(self, other: Self) -> Self {
+        self + other
+    }
+
+    /// The fraction of the whole note (eg. 4 for a quarter), if the duration is a simple one.
+    pub fn fraction(&self) -> Option<u8> {
+        match self.numerator {
+            1 => u8::try_from(self.denominator).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the duration can be written as a single note, possibly dotted.
+    pub fn is_simple(&self) -> bool {
+        self.denominator.is_power_of_two()
+            && (self.numerator + 1).is_power_of_two()
+            && self.denominator * 2 > self.numerator
+    }
+
+    /// Splits the duration into simple durations (possibly dotted, never longer than a whole
+    /// note), from the longest one, to be written as notes tied together. A tuplet duration
+    /// cannot be split and is returned as it is.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// assert_eq!((HALF + EIGHTH).split(), [HALF, EIGHTH]);
+    /// assert_eq!((HALF + QUARTER + SIXTEENTH).split(), [HALF.dotted(), SIXTEENTH]);
+    /// ```
+    pub fn split(self) -> Vec<Duration> {
+        if !self.denominator.is_power_of_two() {
+            return vec![self];
+        }
+
+        let mut parts = vec![];
+        let mut rest = self;
+        let mut base = WHOLE;
+        while !rest.is_zero() {
+            while base > rest {
+                base = base / 2;
+            }
+            let part = match base < WHOLE && base.dotted() <= rest {
+                true => base.dotted(),
+                false => base,
+            };
+            parts.push(part);
+            rest = rest - part;
+        }
+        parts
+    }
+
+    /// Returns the subtraction of the durations, or `None` if the other one is longer.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (a, b, denominator) = self.common(other);
+        a.checked_sub(b).map(|n| Self::new(n, denominator))
+    }
+
+    /// The duration as a fraction of a whole note.
+    pub fn as_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// The numerators of both durations with their common denominator.
+    fn common(&self, other: Self) -> (u32, u32, u32) {
+        let denominator =
+            self.denominator / gcd(self.denominator, other.denominator) * other.denominator;
+        (
+            self.numerator * (denominator / self.denominator),
+            other.numerator * (denominator / other.denominator),
+            denominator,
+        )
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    match b {
+        0 => a.max(1),
+        _ => gcd(b, a % b),
+    }
+}
+
+impl Default for Duration {
+    /// No duration at all.
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl From<u8> for Duration {
+    /// The shorthand for 1 / fraction of a whole note (eg. 4 for a quarter).
+    ///
+    /// # Panics
+    /// Panics if the fraction is 0, which is not a note value.
+    fn from(fraction: u8) -> Self {
+        assert!(fraction != 0, "Duration: 0 is not a note value");
+        Self::new(1, fraction as u32)
+    }
+}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let a = self.numerator as u64 * other.denominator as u64;
+        let b = other.numerator as u64 * self.denominator as u64;
+        a.cmp(&b)
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let (a, b, denominator) = self.common(rhs);
+        Self::new(a + b, denominator)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    /// # Panics
+    /// Panics if the other duration is longer.
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("Duration: the subtraction is negative")
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Duration;
+
+    /// # Panics
+    /// Panics if the numerator overflows.
+    fn mul(self, rhs: u32) -> Self::Output {
+        let numerator = self.numerator.checked_mul(rhs);
+        Self::new(
+            numerator.expect("Duration: the multiplication overflows"),
+            self.denominator,
+        )
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Duration;
+
+    /// # Panics
+    /// Panics if `rhs` is zero, or if the denominator overflows.
+    fn div(self, rhs: u32) -> Self::Output {
+        let denominator = self.denominator.checked_mul(rhs);
+        Self::new(
+            self.numerator,
+            denominator.expect("Duration: the division overflows"),
+        )
+    }
+}
+
+impl Sum for Duration {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |a, b| a + b)
+    }
+}
+
+impl<'a> Sum<&'a Duration> for Duration {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduced() {
+        assert_eq!(Duration::new(2, 8), QUARTER);
+        assert_eq!(Duration::new(0, 4), Duration::new(0, 1));
+        assert_eq!(Duration::from(16), SIXTEENTH);
+        assert_eq!(Duration::new(6, 16).to_string(), "3/8");
+        assert_eq!(Duration::default(), Duration::ZERO);
+        assert!(Duration::default().is_zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "Duration: 0 is not a note value")]
+    fn zero_shorthand() {
+        let _ = Duration::from(0);
+    }
+
+    #[test]
+    fn dots() {
+        assert_eq!(QUARTER.dotted(), Duration::new(3, 8));
+        assert_eq!(HALF.double_dotted(), Duration::new(7, 8));
+        assert_eq!(QUARTER.with_dots(0), QUARTER);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duration: more than 8 dots")]
+    fn too_many_dots() {
+        let _ = QUARTER.with_dots(32);
+    }
+
+    #[test]
+    fn tuplets() {
+        assert_eq!(EIGHTH.triplet(), Duration::new(1, 12));
+        assert_eq!(SIXTEENTH.tuplet(5, 4) * 5, QUARTER);
+        assert_eq!(QUARTER.triplet().fraction(), Some(6));
+        assert!(!EIGHTH.triplet().is_simple());
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(QUARTER + QUARTER, HALF);
+        assert_eq!(WHOLE - QUARTER, QUARTER.dotted() * 2);
+        assert_eq!(QUARTER.checked_sub(HALF), None);
+        assert_eq!([HALF, QUARTER, QUARTER].iter().sum::<Duration>(), WHOLE);
+        assert_eq!(HALF.tie(EIGHTH), Duration::new(5, 8));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duration: the division overflows")]
+    fn division_overflow() {
+        let _ = Duration::new(1, u32::MAX) / 2;
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(QUARTER > EIGHTH.dotted());
+        assert!(EIGHTH.triplet() < EIGHTH);
+        assert_eq!(QUARTER.max(HALF), HALF);
+    }
+
+    #[test]
+    fn split() {
+        assert!(QUARTER.dotted().is_simple());
+        assert!(!Duration::new(5, 8).is_simple());
+        assert!(WHOLE.dotted().is_simple());
+        assert!(!Duration::new(5, 2).is_simple());
+        assert_eq!(Duration::new(3, 2).split(), [WHOLE, HALF]);
+        assert_eq!(Duration::new(7, 16).split(), [QUARTER.dotted(), SIXTEENTH]);
+        assert_eq!(EIGHTH.triplet().split(), [EIGHTH.triplet()]);
+    }
+}
@@ -0,0 +1,255 @@
+//! A model of the fretboard of string instruments (guitar, bass) and of the chord shapes
+//! which can be played on it.
+//!
+//! The strings are numbered from the lowest one (0) to the highest one, and the frets are
+//! absolute: with a capo on the 2nd fret, the fret 2 plays the "open" strings.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{fretboard::Fretboard, *};
+//!
+//! let fretboard = Fretboard::guitar();
+//! for fingering in fretboard.chord_shapes(&C.maj()).iter().take(3) {
+//!     println!("{fingering}");
+//! }
+//! ```
+
+use crate::Note;
+use std::fmt::Display;
+
+mod render;
+mod shapes;
+
+pub use shapes::*;
+
+/// The open notes of the strings of an instrument, from the lowest string to the highest one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tuning {
+    name: &'static str,
+    strings: Vec<Note>,
+}
+
+impl Tuning {
+    pub fn new(name: &'static str, strings: Vec<Note>) -> Self {
+        Self { name, strings }
+    }
+
+    /// The standard guitar tuning: E2 A2 D3 G3 B3 E4.
+    pub fn guitar() -> Self {
+        Self::new("standard", Self::notes(&[-20, -15, -10, -5, -1, 4]))
+    }
+
+    /// The guitar tuning with the lowest string down to D2: D2 A2 D3 G3 B3 E4.
+    pub fn drop_d() -> Self {
+        Self::new("drop D", Self::notes(&[-22, -15, -10, -5, -1, 4]))
+    }
+
+    /// The standard four strings bass tuning: E1 A1 D2 G2.
+    pub fn bass() -> Self {
+        Self::new("bass", Self::notes(&[-32, -27, -22, -17]))
+    }
+
+    /// The standard five strings bass tuning: B0 E1 A1 D2 G2.
+    pub fn bass5() -> Self {
+        Self::new("5 strings bass", Self::notes(&[-37, -32, -27, -22, -17]))
+    }
+
+    fn notes(strings: &[i8]) -> Vec<Note> {
+        strings.iter().map(|n| Note::from(*n)).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The open notes, from the lowest string to the highest one.
+    pub fn strings(&self) -> impl Iterator<Item = &Note> {
+        self.strings.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    fn open(&self, string: usize) -> Option<Note> {
+        self.strings.get(string).copied()
+    }
+}
+
+/// A place on the fretboard: a string (0 is the lowest one) and a fret (0 is the open string).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    string: usize,
+    fret: u8,
+}
+
+impl Position {
+    pub fn new(string: usize, fret: u8) -> Self {
+        Self { string, fret }
+    }
+
+    pub fn string(&self) -> usize {
+        self.string
+    }
+
+    pub fn fret(&self) -> u8 {
+        self.fret
+    }
+}
+
+/// The fretboard of an instrument: its tuning, its number of frets and an optional capo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fretboard {
+    tuning: Tuning,
+    frets: u8,
+    capo: u8,
+}
+
+impl Fretboard {
+    pub fn new(tuning: Tuning, frets: u8) -> Self {
+        Self {
+            tuning,
+            frets,
+            capo: 0,
+        }
+    }
+
+    /// A six strings guitar in standard tuning with 22 frets.
+    pub fn guitar() -> Self {
+        Self::new(Tuning::guitar(), 22)
+    }
+
+    /// A four strings bass in standard tuning with 20 frets.
+    pub fn bass() -> Self {
+        Self::new(Tuning::bass(), 20)
+    }
+
+    /// Places a capo on the given fret.
+    pub fn with_capo(self, capo: u8) -> Self {
+        debug_assert!(capo <= self.frets, "The capo is beyond the last fret");
+        Self { capo, ..self }
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    pub fn frets(&self) -> u8 {
+        self.frets
+    }
+
+    pub fn capo(&self) -> u8 {
+        self.capo
+    }
+
+    /// The number of strings.
+    pub fn strings(&self) -> usize {
+        self.tuning.len()
+    }
+
+    /// Returns true if the fret can be played, given the capo and the number of frets.
+    pub fn is_playable(&self, fret: u8) -> bool {
+        self.capo <= fret && fret <= self.frets
+    }
+
+    /// Returns the note played at the given position, if the position is on the fretboard
+    /// and the note fits in a [`Note`].
+    pub fn note_at(&self, position: Position) -> Option<Note> {
+        let open = self.tuning.open(position.string)?;
+        if !self.is_playable(position.fret) {
+            return None;
+        }
+        let fret = i8::try_from(position.fret).ok()?;
+        i8::from(open).checked_add(fret).map(Note::from)
+    }
+
+    /// Returns all the positions where the given note (in its octave) can be played.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{fretboard::{Fretboard, Position}, *};
+    ///
+    /// let positions = Fretboard::guitar().positions(E);
+    /// assert!(positions.contains(&Position::new(5, 0)));
+    /// assert!(positions.contains(&Position::new(4, 5)));
+    /// ```
+    pub fn positions(&self, note: Note) -> Vec<Position> {
+        self.tuning
+            .strings()
+            .enumerate()
+            .filter_map(|(string, open)| {
+                let fret = i8::from(note) as i16 - i8::from(*open) as i16;
+                u8::try_from(fret)
+                    .ok()
+                    .filter(|fret| self.is_playable(*fret))
+                    .map(|fret| Position::new(string, fret))
+            })
+            .collect()
+    }
+}
+
+impl Display for Tuning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let strings = self
+            .strings
+            .iter()
+            .map(|n| format!("{n:X}{}", n.scientific_octave()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{} [{strings}]", self.name)
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.string, self.fret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, C, E, OCTAVE};
+
+    #[test]
+    fn tuning() {
+        assert_eq!(Tuning::guitar().to_string(), "standard [E2 A2 D3 G3 B3 E4]");
+        assert_eq!(Tuning::bass().to_string(), "bass [E1 A1 D2 G2]");
+        assert_eq!(Tuning::drop_d().len(), 6);
+    }
+
+    #[test]
+    fn note_at() {
+        let fretboard = Fretboard::guitar();
+        assert_eq!(fretboard.note_at(Position::new(5, 0)), Some(E));
+        assert_eq!(fretboard.note_at(Position::new(1, 3)), Some(C - OCTAVE));
+        assert_eq!(fretboard.note_at(Position::new(6, 0)), None);
+        assert_eq!(fretboard.note_at(Position::new(0, 23)), None);
+
+        let fretboard = Fretboard::new(Tuning::guitar(), u8::MAX);
+        assert_eq!(
+            fretboard.note_at(Position::new(5, 120)),
+            Some(Note::from(124))
+        );
+        assert_eq!(fretboard.note_at(Position::new(5, 124)), None);
+        assert_eq!(fretboard.note_at(Position::new(0, 200)), None);
+    }
+
+    #[test]
+    fn positions() {
+        let fretboard = Fretboard::guitar();
+        let positions = fretboard.positions(A - OCTAVE);
+        assert_eq!(positions, [Position::new(0, 5), Position::new(1, 0)]);
+    }
+
+    #[test]
+    fn capo() {
+        let fretboard = Fretboard::guitar().with_capo(2);
+        assert_eq!(fretboard.note_at(Position::new(0, 1)), None);
+        assert!(fretboard.positions(E - OCTAVE - OCTAVE).is_empty());
+    }
+}
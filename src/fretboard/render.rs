@@ -0,0 +1,273 @@
+use super::{Fingering, Fretboard, Position};
+use crate::{chords::Chords, Bar, BarElement, Note};
+
+impl Fretboard {
+    /// The number of columns taken by a whole note in the tablature.
+    const WHOLE_WIDTH: usize = 32;
+
+    /// The minimum number of columns of an element, enough for a two digits fret.
+    const MIN_WIDTH: usize = 3;
+
+    /// Renders the bars as a tablature, one line per string (the highest string on top),
+    /// with the width of each element proportional to its duration.
+    ///
+    /// The notes are played on the lowest fret available, moved by octaves when they are out
+    /// of the range of the instrument, and the chords with their easiest fingering.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{fretboard::Fretboard, *};
+    ///
+    /// let bar = Bar::new().with_note(C, 4).with_note(E, 4).with_chord(G.maj(), 2);
+    /// println!("{}", Fretboard::guitar().tab(&[bar]));
+    /// ```
+    pub fn tab(&self, bars: &[Bar]) -> String {
+        let strings = self.strings();
+        let mut lines = vec![String::from("|"); strings];
+
+        for bar in bars {
+            for element in bar.elements() {
+                let duration = element.duration();
+                let width = (Self::WHOLE_WIDTH * duration.numerator() as usize
+                    / duration.denominator() as usize)
+                    .max(Self::MIN_WIDTH);
+                let frets = self.tab_frets(element);
+                for (string, line) in lines.iter_mut().enumerate() {
+                    let cell = frets[string].map_or(String::new(), |f| f.to_string());
+                    line.push_str(&format!("{cell:-<width$}"));
+                }
+            }
+
+            for line in lines.iter_mut() {
+                line.push('|');
+            }
+        }
+
+        let labels = self.labels();
+        lines
+            .iter()
+            .zip(labels)
+            .rev()
+            .map(|(line, label)| format!("{label}{line}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the easiest fingering of the chord as a chord box.
+    pub fn chord_box(&self, chord: &Chords) -> Option<String> {
+        let fingering = self.chord_shapes(chord).into_iter().next()?;
+        Some(format!("{chord}\n{}", fingering.diagram()))
+    }
+
+    /// The fret played on each string for the element of a bar: the lowest fret for a note,
+    /// the easiest fingering for a chord, and the lowest free string for each note of a
+    /// cluster, from the lowest note.
+    fn tab_frets(&self, element: &BarElement) -> Vec<Option<u8>> {
+        let mut frets = vec![None; self.strings()];
+        match element {
+            BarElement::Silence(_) => {}
+            BarElement::Note(note, _) => {
+                if let Some(position) = self.playable_position(*note) {
+                    frets[position.string()] = Some(position.fret());
+                }
+            }
+            BarElement::Chord(chord, _) => {
+                if let Some(fingering) = self.chord_shapes(chord).into_iter().next() {
+                    frets = fingering.frets().copied().collect();
+                }
+            }
+            BarElement::Cluster(notes, _) => {
+                let mut notes = notes.clone();
+                notes.sort();
+                for note in notes {
+                    let position = self
+                        .positions(note)
+                        .into_iter()
+                        .filter(|p| frets[p.string()].is_none())
+                        .min_by_key(|p| p.fret());
+                    if let Some(position) = position {
+                        frets[position.string()] = Some(position.fret());
+                    }
+                }
+            }
+        }
+        frets
+    }
+
+    /// The position with the lowest fret for the note, moving the note by the fewest octaves
+    /// when it is out of the range of the instrument.
+    fn playable_position(&self, note: Note) -> Option<Position> {
+        let note = i8::from(note) as i16;
+        [0, -12, 12, -24, 24, -36, 36]
+            .into_iter()
+            .filter_map(|shift| i8::try_from(note + shift).ok())
+            .find_map(|n| {
+                self.positions(Note::from(n))
+                    .into_iter()
+                    .min_by_key(|p| p.fret())
+            })
+    }
+
+    /// The name of each open string, all with the same width.
+    fn labels(&self) -> Vec<String> {
+        let names = self
+            .tuning
+            .strings()
+            .map(|n| format!("{n:X}"))
+            .collect::<Vec<_>>();
+        let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+        names.iter().map(|n| format!("{n:<width$}")).collect()
+    }
+}
+
+impl Fingering {
+    /// The minimum number of frets shown in a chord box.
+    const DIAGRAM_FRETS: u8 = 4;
+
+    /// Renders the fingering as a chord box: the open (o) and the muted (x) strings on top,
+    /// then the nut (or the number of the first fret shown) and a grid with a dot (O) on
+    /// each fret played. The lowest string is on the left.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{fretboard::Fretboard, *};
+    ///
+    /// let fretboard = Fretboard::guitar();
+    /// let fingering = &fretboard.caged_shapes(&C.maj())[0];
+    /// println!("{}", fingering.diagram());
+    /// ```
+    pub fn diagram(&self) -> String {
+        let highest = self.fretted().max().unwrap_or(self.capo);
+        let near_nut = highest <= self.capo + Self::DIAGRAM_FRETS;
+        let first = match near_nut {
+            true => self.capo + 1,
+            false => self.lowest_fret().unwrap_or(self.capo + 1),
+        };
+        let rows = (highest + 1 - first).max(Self::DIAGRAM_FRETS);
+        let width = (self.frets.len() * 2).saturating_sub(1);
+
+        let markers = self
+            .frets
+            .iter()
+            .map(|f| match f {
+                None => "x",
+                Some(f) if *f == self.capo => "o",
+                Some(_) => " ",
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut lines = vec![markers.trim_end().to_string()];
+        match near_nut {
+            true => lines.push("=".repeat(width)),
+            false => lines.push(format!("{} {first}fr", "-".repeat(width))),
+        }
+
+        for fret in first..first + rows {
+            let row = self
+                .frets
+                .iter()
+                .map(|f| match f {
+                    Some(f) if *f == fret => "O",
+                    _ => "|",
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(row);
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fretboard::Shape, A, C, E, G, OCTAVE};
+
+    #[test]
+    fn tab() {
+        let fretboard = Fretboard::guitar();
+        let bar = Bar::new()
+            .with_note(C, 4)
+            .with_note(E, 4)
+            .with_silence(4)
+            .with_note(G, 4);
+        let tab = fretboard.tab(&[bar]);
+        let lines = tab.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "E|--------0---------------3-------|");
+        assert_eq!(lines[1], "B|1-------------------------------|");
+        assert_eq!(lines[5], "E|--------------------------------|");
+    }
+
+    #[test]
+    fn tab_chords() {
+        let fretboard = Fretboard::guitar();
+        let bars = [
+            Bar::new().with_chord(C.maj(), 1),
+            Bar::new().with_chord(A.min7(), 2).with_silence(2),
+        ];
+        let tab = fretboard.tab(&bars);
+        let lines = tab.lines().collect::<Vec<_>>();
+        assert!(lines.iter().all(|l| l.len() == lines[0].len()));
+        assert!(lines[4].starts_with("A|3---"));
+        assert_eq!(lines[4].matches('|').count(), 3);
+    }
+
+    #[test]
+    fn tab_cluster() {
+        let fretboard = Fretboard::guitar();
+        let tab = fretboard.tab(&[Bar::new().with_cluster(&[C - OCTAVE, E], 1)]);
+        let lines = tab.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("E|0-"));
+        assert!(lines[4].starts_with("A|3-"));
+    }
+
+    #[test]
+    fn bass_tab() {
+        let fretboard = Fretboard::bass();
+        let tab = fretboard.tab(&[Bar::new().with_note(E, 1)]);
+        assert_eq!(tab.lines().count(), 4);
+        assert!(tab.starts_with("G|9--"));
+    }
+
+    #[test]
+    fn diagram() {
+        let fingering = Fingering::new(
+            Shape::Free,
+            0,
+            vec![None, Some(0), Some(2), Some(0), Some(1), Some(0)],
+        );
+        assert_eq!(
+            fingering.diagram(),
+            [
+                "x o   o   o",
+                "===========",
+                "| | | | O |",
+                "| | O | | |",
+                "| | | | | |",
+                "| | | | | |",
+            ]
+            .join("\n")
+        );
+
+        let fingering = Fingering::new(
+            Shape::Free,
+            0,
+            vec![Some(8), Some(10), Some(10), Some(9), Some(8), Some(8)],
+        );
+        let diagram = fingering.diagram();
+        assert!(diagram.lines().nth(1).unwrap().ends_with(" 8fr"));
+        assert_eq!(diagram.lines().nth(2).unwrap(), "O | | | O O");
+
+        let fingering = Fingering::new(Shape::Free, 0, vec![]);
+        assert!(fingering.diagram().lines().all(str::is_empty));
+    }
+
+    #[test]
+    fn chord_box() {
+        let chord_box = Fretboard::guitar().chord_box(&C.maj()).unwrap();
+        assert!(chord_box.starts_with("C\nx"));
+    }
+}
@@ -0,0 +1,482 @@
+use super::{Fretboard, Position, Tuning};
+use crate::{
+    chords::{Alteration, Chord, Chords, Degree, Quality},
+    voicings::{self, VoicingKind},
+    Note,
+};
+use std::{collections::HashSet, fmt::Display};
+
+/// The five open chord shapes of the CAGED system, moved up the neck.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CagedShape {
+    C,
+    A,
+    G,
+    E,
+    D,
+}
+
+/// Where a fingering comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// One of the CAGED shapes.
+    Caged(CagedShape),
+    /// A drop-2 voicing on four adjacent strings.
+    Drop2,
+    /// A drop-3 voicing on four strings, skipping the string above the bass.
+    Drop3,
+    /// A root position shape found by searching the fretboard.
+    Free,
+}
+
+/// The fret played on each string, from the lowest string to the highest one (`None` for a
+/// muted string).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingering {
+    shape: Shape,
+    pub(super) capo: u8,
+    pub(super) frets: Vec<Option<u8>>,
+}
+
+impl Fingering {
+    pub fn new(shape: Shape, capo: u8, frets: Vec<Option<u8>>) -> Self {
+        Self { shape, capo, frets }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The fret played on each string, from the lowest string to the highest one.
+    pub fn frets(&self) -> impl Iterator<Item = &Option<u8>> {
+        self.frets.iter()
+    }
+
+    /// The positions played, from the lowest string to the highest one.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        self.frets
+            .iter()
+            .enumerate()
+            .filter_map(|(string, fret)| fret.map(|fret| Position::new(string, fret)))
+    }
+
+    /// The notes sounding on the given fretboard, from the lowest string to the highest one.
+    pub fn notes(&self, fretboard: &Fretboard) -> Vec<Note> {
+        self.positions()
+            .filter_map(|p| fretboard.note_at(p))
+            .collect()
+    }
+
+    /// The frets pressed by the fingers, the open strings excluded.
+    pub(super) fn fretted(&self) -> impl Iterator<Item = u8> + '_ {
+        self.frets
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|f| *f > self.capo)
+    }
+
+    /// The lowest fret pressed by a finger, if any.
+    pub fn lowest_fret(&self) -> Option<u8> {
+        self.fretted().min()
+    }
+
+    /// The number of frets between the lowest and the highest fret pressed by the fingers.
+    pub fn stretch(&self) -> u8 {
+        match (self.fretted().min(), self.fretted().max()) {
+            (Some(min), Some(max)) => max - min,
+            _ => 0,
+        }
+    }
+
+    /// The number of fingers needed, playing the lowest fret with a barre when several
+    /// strings need it.
+    pub fn fingers(&self) -> usize {
+        let Some(lowest) = self.lowest_fret() else {
+            return 0;
+        };
+        let on_lowest = self.fretted().filter(|f| *f == lowest).count();
+        let others = self.fretted().filter(|f| *f != lowest).count();
+        others + on_lowest.min(1)
+    }
+
+    /// The number of strings played.
+    pub fn sounding(&self) -> usize {
+        self.frets.iter().flatten().count()
+    }
+
+    /// The number of muted strings between two played strings.
+    pub fn inner_mutes(&self) -> usize {
+        let first = self.frets.iter().position(|f| f.is_some());
+        let last = self.frets.iter().rposition(|f| f.is_some());
+        match (first, last) {
+            (Some(first), Some(last)) => self.frets[first..=last]
+                .iter()
+                .filter(|f| f.is_none())
+                .count(),
+            _ => 0,
+        }
+    }
+
+    /// Returns true if the lowest fret is played with a barre.
+    pub fn has_barre(&self) -> bool {
+        self.lowest_fret()
+            .is_some_and(|lowest| self.fretted().filter(|f| *f == lowest).count() > 1)
+    }
+
+    /// A score of how hard the fingering is to play, the lower the easier: it grows with
+    /// the stretch, the number of fingers, the barre, the muted strings and the position on
+    /// the neck.
+    pub fn difficulty(&self) -> u32 {
+        let position = self.lowest_fret().map_or(0, |f| f - self.capo) as u32;
+        self.stretch() as u32 * 2
+            + self.fingers() as u32
+            + self.has_barre() as u32 * 2
+            + (self.frets.len() - self.sounding()) as u32 * 2
+            + self.inner_mutes() as u32 * 4
+            + position / 2
+    }
+}
+
+/// The offsets from the fret of the root for the CAGED shapes, from the lowest string to
+/// the highest one, together with the string of the root.
+type CagedTemplate = (CagedShape, usize, [Option<i8>; 6]);
+
+#[rustfmt::skip]
+const CAGED_MAJOR: [CagedTemplate; 5] = [
+    (CagedShape::C, 1, [None, Some(0), Some(-1), Some(-3), Some(-2), Some(-3)]),
+    (CagedShape::A, 1, [None, Some(0), Some(2), Some(2), Some(2), Some(0)]),
+    (CagedShape::G, 0, [Some(0), Some(-1), Some(-3), Some(-3), Some(-3), Some(0)]),
+    (CagedShape::E, 0, [Some(0), Some(2), Some(2), Some(1), Some(0), Some(0)]),
+    (CagedShape::D, 2, [None, None, Some(0), Some(2), Some(3), Some(2)]),
+];
+
+#[rustfmt::skip]
+const CAGED_MINOR: [CagedTemplate; 5] = [
+    (CagedShape::C, 1, [None, Some(0), Some(-2), Some(-3), Some(-2), None]),
+    (CagedShape::A, 1, [None, Some(0), Some(2), Some(2), Some(1), Some(0)]),
+    (CagedShape::G, 0, [Some(0), Some(-2), Some(-3), Some(-3), Some(0), Some(0)]),
+    (CagedShape::E, 0, [Some(0), Some(2), Some(2), Some(0), Some(0), Some(0)]),
+    (CagedShape::D, 2, [None, None, Some(0), Some(2), Some(3), Some(1)]),
+];
+
+#[rustfmt::skip]
+const CAGED_DOMINANT: [CagedTemplate; 5] = [
+    (CagedShape::C, 1, [None, Some(0), Some(-1), Some(0), Some(-2), Some(-3)]),
+    (CagedShape::A, 1, [None, Some(0), Some(2), Some(0), Some(2), Some(0)]),
+    (CagedShape::G, 0, [Some(0), Some(-1), Some(-3), Some(-3), Some(-3), Some(-2)]),
+    (CagedShape::E, 0, [Some(0), Some(2), Some(0), Some(1), Some(0), Some(0)]),
+    (CagedShape::D, 2, [None, None, Some(0), Some(2), Some(1), Some(2)]),
+];
+
+impl Fretboard {
+    /// Returns the playable fingerings of the chord, from the easiest to the hardest one.
+    ///
+    /// The fingerings are the CAGED shapes (on a guitar in standard tuning), the drop-2 and
+    /// drop-3 voicings, and all the root position shapes which fit under four fingers within
+    /// four frets.
+    pub fn chord_shapes(&self, chord: &Chords) -> Vec<Fingering> {
+        let mut seen = HashSet::new();
+        let mut fingerings = self
+            .caged_shapes(chord)
+            .into_iter()
+            .chain(self.drop_shapes(chord, VoicingKind::Drop2))
+            .chain(self.drop_shapes(chord, VoicingKind::Drop3))
+            .chain(self.search(chord))
+            .filter(|f| seen.insert(f.frets.clone()))
+            .collect::<Vec<_>>();
+
+        fingerings.sort_by_key(|f| f.difficulty());
+        fingerings
+    }
+
+    /// Returns the CAGED shapes of the chord. Only the major and minor triads and the
+    /// dominant seventh chords have CAGED shapes, on a six strings guitar in standard tuning.
+    pub fn caged_shapes(&self, chord: &Chords) -> Vec<Fingering> {
+        if self.tuning != Tuning::guitar() {
+            return vec![];
+        }
+
+        let templates = match (chord.quality(), chord.notes().count()) {
+            (Quality::Major, 3) => &CAGED_MAJOR,
+            (Quality::Minor, 3) => &CAGED_MINOR,
+            (Quality::Dominant, 4) => &CAGED_DOMINANT,
+            _ => return vec![],
+        };
+
+        let root = chord.root().pitch_class() as i16;
+        let mut fingerings = vec![];
+        for (shape, string, offsets) in templates {
+            let open = self.tuning.strings[*string].pitch_class() as i16;
+            let base = (root - open).rem_euclid(12);
+
+            for fret in [base, base + 12, base + 24] {
+                let frets = offsets
+                    .iter()
+                    .map(|offset| offset.map(|o| fret + o as i16))
+                    .collect::<Vec<_>>();
+                let playable = frets
+                    .iter()
+                    .flatten()
+                    .all(|f| u8::try_from(*f).is_ok_and(|f| self.is_playable(f)));
+                if playable {
+                    let frets = frets.iter().map(|f| f.map(|f| f as u8)).collect();
+                    fingerings.push(Fingering::new(Shape::Caged(*shape), self.capo, frets));
+                }
+            }
+        }
+
+        fingerings
+    }
+
+    /// Returns the drop-2 (on four adjacent strings) or the drop-3 (on four strings skipping
+    /// the one above the bass) voicings of the chord, in all their inversions.
+    pub fn drop_shapes(&self, chord: &Chords, kind: VoicingKind) -> Vec<Fingering> {
+        let strings = self.strings();
+        let (shape, sets) = match kind {
+            VoicingKind::Drop2 => (
+                Shape::Drop2,
+                (0..strings.saturating_sub(3))
+                    .map(|s| [s, s + 1, s + 2, s + 3])
+                    .collect::<Vec<_>>(),
+            ),
+            VoicingKind::Drop3 => (
+                Shape::Drop3,
+                (0..strings.saturating_sub(4))
+                    .map(|s| [s, s + 2, s + 3, s + 4])
+                    .collect::<Vec<_>>(),
+            ),
+            _ => return vec![],
+        };
+
+        let mut fingerings = vec![];
+        for notes in voicings::inversions(chord, kind) {
+            for set in &sets {
+                for octaves in -4i16..=4 {
+                    let frets = notes
+                        .iter()
+                        .zip(set)
+                        .map(|(note, string)| {
+                            let open = i8::from(self.tuning.strings[*string]) as i16;
+                            i8::from(*note) as i16 + octaves * 12 - open
+                        })
+                        .collect::<Vec<_>>();
+                    if !frets
+                        .iter()
+                        .all(|f| u8::try_from(*f).is_ok_and(|f| self.is_playable(f)))
+                    {
+                        continue;
+                    }
+
+                    let mut all = vec![None; strings];
+                    for (fret, string) in frets.iter().zip(set) {
+                        all[*string] = Some(*fret as u8);
+                    }
+                    let fingering = Fingering::new(shape, self.capo, all);
+                    if fingering.stretch() <= 4 {
+                        fingerings.push(fingering);
+                    }
+                }
+            }
+        }
+
+        fingerings
+    }
+
+    /// Searches all the root position fingerings which fit in a window of four frets.
+    fn search(&self, chord: &Chords) -> Vec<Fingering> {
+        let root = chord.root().pitch_class();
+        let allowed = chord.notes().map(|n| n.pitch_class()).collect::<Vec<_>>();
+        let required = required(chord);
+        let minimum = self.strings().min(required.len().max(3));
+
+        let mut found = vec![];
+        let mut seen = HashSet::new();
+        for start in self.capo..=self.frets {
+            let window = (start.max(self.capo + 1), (start + 3).min(self.frets));
+            let mut frets = Vec::with_capacity(self.strings());
+            self.search_string(root, &allowed, window, &mut frets, &mut |frets| {
+                let fingering = Fingering::new(Shape::Free, self.capo, frets.to_vec());
+                let notes = fingering.notes(self);
+                let complete = required
+                    .iter()
+                    .all(|pc| notes.iter().any(|n| n.pitch_class() == *pc));
+                let open_strings = frets.iter().flatten().any(|f| *f == self.capo);
+                let near_nut = fingering.fretted().all(|f| f <= self.capo + 4);
+                if complete
+                    && (!open_strings || near_nut)
+                    && fingering.sounding() >= minimum
+                    && fingering.fingers() <= 4
+                    && fingering.inner_mutes() <= 1
+                    && seen.insert(frets.to_vec())
+                {
+                    found.push(fingering);
+                }
+            });
+        }
+
+        found
+    }
+
+    fn search_string(
+        &self,
+        root: u8,
+        allowed: &[u8],
+        window: (u8, u8),
+        frets: &mut Vec<Option<u8>>,
+        found: &mut impl FnMut(&[Option<u8>]),
+    ) {
+        let string = frets.len();
+        if string == self.strings() {
+            found(frets);
+            return;
+        }
+
+        let bass = frets.iter().all(|f| f.is_none());
+        let candidates = std::iter::once(self.capo).chain(window.0..=window.1);
+        for fret in candidates {
+            let Some(note) = self.note_at(Position::new(string, fret)) else {
+                continue;
+            };
+            let pitch_class = note.pitch_class();
+            if (bass && pitch_class != root) || !allowed.contains(&pitch_class) {
+                continue;
+            }
+
+            frets.push(Some(fret));
+            self.search_string(root, allowed, window, frets, found);
+            frets.pop();
+        }
+
+        frets.push(None);
+        self.search_string(root, allowed, window, frets, found);
+        frets.pop();
+    }
+}
+
+/// The pitch classes a shape must contain: all the notes of the chord, except the natural
+/// fifth and, for the major and dominant thirteenth chords, the natural eleventh.
+fn required(chord: &Chords) -> Vec<u8> {
+    let quality = chord.quality();
+    let has_thirteenth = chord.degree(Degree::Thirteenth).is_some();
+
+    chord
+        .degrees()
+        .iter()
+        .filter(|d| !(d.degree() == Degree::Fifth && d.alteration() == Alteration::Natural))
+        .filter(|d| {
+            !(d.degree() == Degree::Eleventh
+                && d.alteration() == Alteration::Natural
+                && has_thirteenth
+                && matches!(quality, Quality::Major | Quality::Dominant))
+        })
+        .map(|d| d.note().pitch_class())
+        .collect()
+}
+
+impl Display for CagedShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CagedShape::C => write!(f, "C"),
+            CagedShape::A => write!(f, "A"),
+            CagedShape::G => write!(f, "G"),
+            CagedShape::E => write!(f, "E"),
+            CagedShape::D => write!(f, "D"),
+        }
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shape::Caged(shape) => write!(f, "{shape} shape"),
+            Shape::Drop2 => write!(f, "drop-2"),
+            Shape::Drop3 => write!(f, "drop-3"),
+            Shape::Free => write!(f, "shape"),
+        }
+    }
+}
+
+impl Display for Fingering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = match self.frets.iter().flatten().any(|f| *f > 9) {
+            true => "-",
+            false => "",
+        };
+        let frets = self
+            .frets
+            .iter()
+            .map(|f| f.map_or("x".to_string(), |f| f.to_string()))
+            .collect::<Vec<_>>()
+            .join(separator);
+        write!(f, "{frets} ({})", self.shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, C, E, G};
+
+    fn frets(fingerings: &[Fingering]) -> Vec<String> {
+        fingerings.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn caged() {
+        let fretboard = Fretboard::guitar();
+        let shapes = frets(&fretboard.caged_shapes(&C.maj()));
+        assert!(shapes.contains(&"x32010 (C shape)".to_string()));
+        assert!(shapes.contains(&"x35553 (A shape)".to_string()));
+        assert!(shapes.contains(&"8-10-10-9-8-8 (E shape)".to_string()));
+
+        let shapes = frets(&fretboard.caged_shapes(&E.min()));
+        assert!(shapes.contains(&"022000 (E shape)".to_string()));
+
+        let shapes = frets(&fretboard.caged_shapes(&G.dom7()));
+        assert!(shapes.contains(&"320001 (G shape)".to_string()));
+
+        assert!(Fretboard::bass().caged_shapes(&C.maj()).is_empty());
+    }
+
+    #[test]
+    fn drop_shapes() {
+        let fretboard = Fretboard::guitar();
+        let shapes = fretboard.drop_shapes(&C.maj7(), VoicingKind::Drop2);
+        assert!(!shapes.is_empty());
+        for shape in &shapes {
+            assert_eq!(shape.sounding(), 4);
+            assert_eq!(shape.inner_mutes(), 0);
+            let notes = shape.notes(&fretboard);
+            assert!(notes.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        let shapes = fretboard.drop_shapes(&C.maj7(), VoicingKind::Drop3);
+        assert!(shapes.iter().all(|s| s.inner_mutes() == 1));
+    }
+
+    #[test]
+    fn chord_shapes() {
+        let fretboard = Fretboard::guitar();
+        let shapes = fretboard.chord_shapes(&A.min7());
+        assert!(!shapes.is_empty());
+        assert!(shapes
+            .windows(2)
+            .all(|w| w[0].difficulty() <= w[1].difficulty()));
+        assert!(frets(&shapes).contains(&"x02010 (shape)".to_string()));
+
+        for shape in &shapes {
+            let notes = shape.notes(&fretboard);
+            assert!(notes
+                .iter()
+                .all(|n| A.min7().notes().any(|c| c.pitch_class() == n.pitch_class())));
+        }
+    }
+
+    #[test]
+    fn capo() {
+        let fretboard = Fretboard::guitar().with_capo(2);
+        let shapes = fretboard.chord_shapes(&C.maj());
+        assert!(shapes.iter().all(|s| s.frets().flatten().all(|f| *f >= 2)));
+    }
+}
@@ -0,0 +1,461 @@
+//! The feel of a performance: swing, accents, instruments playing ahead or behind the beat
+//! and humanization.
+//!
+//! A [`Groove`] turns the events of a [`Timeline`] into [`PlayedEvent`]s, the written bars
+//! being left untouched.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{groove::*, timeline::*, *};
+//!
+//! let bars = [Bar::new().with_note(C, 8).with_note(D, 8).with_note(E, 4)];
+//! let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+//! let played = Groove::new().with_swing(Swing::triplet()).apply(&timeline);
+//!
+//! // The first eighth lasts two thirds of the beat.
+//! assert!((played[1].start() - 2.0 / 3.0).abs() < 1e-9);
+//! ```
+
+use crate::{
+    timeline::{TimedEvent, Timeline},
+    BarElement, Duration, EIGHTH, SIXTEENTH,
+};
+
+/// The swing of pairs of notes: the first note of each pair is lengthened and the second
+/// one is shortened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swing {
+    long: f64,
+    unit: Duration,
+}
+
+/// The feel applied to the events of a timeline.
+///
+/// # Example
+/// ```
+/// use musika_rs::{groove::*, timeline::*, *};
+///
+/// let bars = [Bar::new()
+///     .with_note(C, 4)
+///     .with_note(D, 4)
+///     .with_voice(Voice::new().with_note(C, 2))];
+/// let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+/// let played = Groove::new()
+///     .with_accents(&[110, 70])
+///     .with_offset(1, 0.02)
+///     .apply(&timeline);
+///
+/// assert_eq!(played[0].velocity(), 110);
+/// assert_eq!(played[1].start(), 0.02);
+/// assert_eq!(played[2].velocity(), 70);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Groove {
+    swing: Swing,
+    velocity: u8,
+    accents: Vec<u8>,
+    offsets: Vec<(usize, f64)>,
+    humanize: Option<Humanize>,
+}
+
+/// Random deviations of the timing and of the velocity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Humanize {
+    timing: f64,
+    velocity: u8,
+    seed: u64,
+}
+
+/// An event of a timeline, as played with a groove.
+#[derive(Clone, Copy)]
+pub struct PlayedEvent<'a> {
+    event: TimedEvent<'a>,
+    start: f64,
+    duration: f64,
+    velocity: u8,
+}
+
+impl Swing {
+    /// No swing: the notes are played as written.
+    pub fn straight() -> Self {
+        Self::percent(50.0)
+    }
+
+    /// The ratio of the duration of the first note of each pair to the second one (eg. 2:1 for
+    /// a triplet feel, 3:2 for a lighter swing).
+    pub fn ratio(long: u32, short: u32) -> Self {
+        assert!(long + short > 0, "Swing: the ratio is empty");
+        Self {
+            long: long as f64 / (long + short) as f64,
+            unit: EIGHTH,
+        }
+    }
+
+    /// The duration of the first note of each pair, in percents of the pair (50 is straight,
+    /// 66.7 is a triplet feel).
+    pub fn percent(percent: f64) -> Self {
+        Self {
+            long: percent.clamp(0.0, 100.0) / 100.0,
+            unit: EIGHTH,
+        }
+    }
+
+    /// The triplet feel of the jazz eighths (2:1).
+    pub fn triplet() -> Self {
+        Self::ratio(2, 1)
+    }
+
+    /// The triplet feel applied to the sixteenths (2:1).
+    pub fn shuffle() -> Self {
+        Self::triplet().with_unit(SIXTEENTH)
+    }
+
+    /// Sets the duration of the notes of the pairs (eighths by default).
+    pub fn with_unit(self, unit: Duration) -> Self {
+        assert!(!unit.is_zero(), "Swing: the unit is zero");
+        Self { unit, ..self }
+    }
+
+    /// The duration of the first note of each pair, as a fraction of the pair.
+    pub fn long(&self) -> f64 {
+        self.long
+    }
+
+    pub fn unit(&self) -> Duration {
+        self.unit
+    }
+
+    pub fn is_straight(&self) -> bool {
+        (self.long - 0.5).abs() < f64::EPSILON
+    }
+
+    /// The swung time of a time from the start of a bar, in whole notes. The pairs start
+    /// with the bar.
+    fn swung(&self, time: Duration) -> f64 {
+        let pair = self.unit * 2;
+        let pairs = (time.numerator() as u64 * pair.denominator() as u64)
+            / (time.denominator() as u64 * pair.numerator() as u64);
+        let rest = time - pair * pairs as u32;
+        let rest = match rest < self.unit {
+            true => rest.as_f64() * 2.0 * self.long,
+            false => {
+                let short = (rest - self.unit).as_f64() * 2.0 * (1.0 - self.long);
+                pair.as_f64() * self.long + short
+            }
+        };
+        pair.as_f64() * pairs as f64 + rest
+    }
+}
+
+impl Default for Swing {
+    fn default() -> Self {
+        Self::straight()
+    }
+}
+
+impl Groove {
+    /// Plays the events as written, with a velocity of 80.
+    pub fn new() -> Self {
+        Self {
+            swing: Swing::straight(),
+            velocity: 80,
+            accents: vec![],
+            offsets: vec![],
+            humanize: None,
+        }
+    }
+
+    pub fn with_swing(self, swing: Swing) -> Self {
+        Self { swing, ..self }
+    }
+
+    /// Sets the velocity of the events which are not accented.
+    pub fn with_velocity(self, velocity: u8) -> Self {
+        Self {
+            velocity: velocity.min(127),
+            ..self
+        }
+    }
+
+    /// Sets the velocity of the events starting on each beat of the bars. The accents are
+    /// repeated if the bar has more beats.
+    pub fn with_accents(self, accents: &[u8]) -> Self {
+        Self {
+            accents: accents.iter().map(|a| *a.min(&127)).collect(),
+            ..self
+        }
+    }
+
+    /// Plays a voice behind the beat (a positive offset, in seconds) or ahead of it (a
+    /// negative offset).
+    pub fn with_offset(self, voice: usize, seconds: f64) -> Self {
+        let mut offsets = self.offsets;
+        offsets.retain(|(v, _)| *v != voice);
+        offsets.push((voice, seconds));
+        Self { offsets, ..self }
+    }
+
+    /// Moves each event randomly by up to the given timing (in seconds) and changes its
+    /// velocity by up to the given velocity. The same seed always gives the same
+    /// performance.
+    pub fn with_humanize(self, timing: f64, velocity: u8, seed: u64) -> Self {
+        Self {
+            humanize: Some(Humanize {
+                timing: timing.abs(),
+                velocity,
+                seed,
+            }),
+            ..self
+        }
+    }
+
+    pub fn swing(&self) -> Swing {
+        self.swing
+    }
+
+    /// Plays the events of the timeline, ordered by the time they start.
+    pub fn apply<'a>(&self, timeline: &Timeline<'a>) -> Vec<PlayedEvent<'a>> {
+        let tempo = timeline.tempo();
+        let mut random = self.humanize.map(|h| Random::new(h.seed));
+
+        let mut played = timeline
+            .events()
+            .map(|event| {
+                let bar_start = timeline
+                    .bar_start(event.bar())
+                    .expect("Groove: the event is outside the timeline");
+                let onset = event.start() - bar_start;
+
+                // The times in whole notes, then in seconds.
+                let start = bar_start.as_f64() + self.swing.swung(onset);
+                let end = bar_start.as_f64() + self.swing.swung(onset + event.duration());
+                let unit = tempo.unit().as_f64();
+                let mut start_seconds = tempo.seconds_at(start / unit);
+                let end_seconds = tempo.seconds_at(end / unit);
+                let duration = end_seconds - start_seconds;
+
+                start_seconds += self
+                    .offsets
+                    .iter()
+                    .find(|(voice, _)| *voice == event.voice())
+                    .map_or(0.0, |(_, seconds)| *seconds);
+
+                let mut velocity = self.velocity(event);
+
+                if let (Some(random), Some(humanize)) = (random.as_mut(), self.humanize) {
+                    start_seconds += humanize.timing * random.next_signed();
+                    let deviation = humanize.velocity as f64 * random.next_signed();
+                    // A silence stays silent.
+                    if velocity > 0 {
+                        velocity = (velocity as f64 + deviation).round().clamp(1.0, 127.0) as u8;
+                    }
+                }
+
+                PlayedEvent {
+                    event: *event,
+                    start: start_seconds.max(0.0),
+                    duration,
+                    velocity,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        played.sort_by(|a, b| a.start.total_cmp(&b.start));
+        played
+    }
+
+    /// The velocity of the event, from its position in the bar.
+    fn velocity(&self, event: &TimedEvent) -> u8 {
+        if matches!(event.element(), BarElement::Silence(_)) {
+            return 0;
+        }
+
+        event
+            .position()
+            .filter(|p| p.is_on_beat() && !self.accents.is_empty())
+            .map(|p| self.accents[(p.beat() - 1) % self.accents.len()])
+            .unwrap_or(self.velocity)
+    }
+}
+
+impl Default for Groove {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> PlayedEvent<'a> {
+    /// The written event.
+    pub fn event(&self) -> &TimedEvent<'a> {
+        &self.event
+    }
+
+    pub fn element(&self) -> &'a BarElement {
+        self.event.element()
+    }
+
+    /// The time the event starts, in seconds.
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    /// The duration of the event, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// The velocity of the event, from 1 to 127 (0 for a silence).
+    pub fn velocity(&self) -> u8 {
+        self.velocity
+    }
+}
+
+/// A xorshift generator, enough for humanizing a performance.
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // The seed is mixed by a splitmix64 step, and the state of a xorshift generator must
+        // not be zero.
+        let mut x = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self((x ^ (x >> 31)).max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A number between -1 and 1.
+    fn next_signed(&mut self) -> f64 {
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        unit * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{timeline::TempoMap, Bar, TimeSignature, Voice, C, D, E, F, QUARTER};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn eighths() -> Bar {
+        Bar::new()
+            .with_note(C, 8)
+            .with_note(D, 8)
+            .with_note(E, 8)
+            .with_note(F, 8)
+            .with_note(C, 2)
+    }
+
+    #[test]
+    fn swing() {
+        let swing = Swing::ratio(3, 2);
+        assert_eq!(swing.long(), 0.6);
+        assert!(Swing::percent(50.0).is_straight());
+        assert!(close(swing.swung(EIGHTH), 0.15));
+        assert!(close(swing.swung(QUARTER + EIGHTH), 0.4));
+        assert!(close(Swing::shuffle().swung(SIXTEENTH), 1.0 / 12.0));
+    }
+
+    #[test]
+    fn straight() {
+        let bars = [eighths()];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let played = Groove::new().apply(&timeline);
+        let starts = played.iter().map(|p| p.start()).collect::<Vec<_>>();
+        assert_eq!(starts, [0.0, 0.5, 1.0, 1.5, 2.0]);
+        assert!(played.iter().all(|p| p.velocity() == 80));
+    }
+
+    #[test]
+    fn swung() {
+        let bars = [eighths(), eighths()];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let played = Groove::new().with_swing(Swing::triplet()).apply(&timeline);
+        assert!(close(played[1].start(), 2.0 / 3.0));
+        assert!(close(played[1].duration(), 1.0 / 3.0));
+        assert!(close(played[3].start(), 1.0 + 2.0 / 3.0));
+        assert!(close(played[4].start(), 2.0));
+        assert!(close(played[5].start(), 4.0));
+
+        // The written bars are unchanged.
+        assert_eq!(timeline.events().nth(1).unwrap().start(), EIGHTH);
+    }
+
+    #[test]
+    fn accents() {
+        let bars = [Bar::new()
+            .with_time_signature(TimeSignature::new(3, 4))
+            .with_note(C, 4)
+            .with_note(D, 8)
+            .with_note(E, 8)
+            .with_silence(4)];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let played = Groove::new()
+            .with_velocity(60)
+            .with_accents(&[120, 90])
+            .apply(&timeline);
+        let velocities = played.iter().map(|p| p.velocity()).collect::<Vec<_>>();
+        assert_eq!(velocities, [120, 90, 60, 0]);
+    }
+
+    #[test]
+    fn offsets() {
+        let bars = [eighths().with_voice(Voice::new().with_note(C, 1))];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let played = Groove::new()
+            .with_offset(0, 0.1)
+            .with_offset(1, -0.05)
+            .with_offset(0, 0.02)
+            .apply(&timeline);
+        assert_eq!(played[0].event().voice(), 1);
+        assert_eq!(played[0].start(), 0.0);
+        assert_eq!(played[1].start(), 0.02);
+    }
+
+    #[test]
+    fn humanize() {
+        let bars = [eighths()];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let groove = Groove::new().with_humanize(0.01, 10, 42);
+        let first = groove.apply(&timeline);
+        let second = groove.apply(&timeline);
+
+        let starts = |played: &[PlayedEvent]| played.iter().map(|p| p.start()).collect::<Vec<_>>();
+        assert_eq!(starts(&first), starts(&second));
+        assert!(first
+            .iter()
+            .zip([0.0, 0.5, 1.0, 1.5, 2.0])
+            .all(|(p, start)| (p.start() - start).abs() <= 0.01));
+        assert!(first.iter().all(|p| (70..=90).contains(&p.velocity())));
+
+        let other = Groove::new().with_humanize(0.01, 10, 7).apply(&timeline);
+        assert_ne!(starts(&first), starts(&other));
+
+        let bars = [Bar::new().with_note(C, 2).with_silence(2)];
+        let timeline = Timeline::new(&bars, &TempoMap::new(60.0));
+        let played = Groove::new().with_humanize(0.01, 10, 42).apply(&timeline);
+        assert!((70..=90).contains(&played[0].velocity()));
+        assert_eq!(played[1].velocity(), 0);
+    }
+
+    #[test]
+    fn seeds() {
+        for seed in [0, 1, 0x9E37_79B9_7F4A_7C15, u64::MAX] {
+            let mut random = Random::new(seed);
+            let numbers = (0..4).map(|_| random.next()).collect::<Vec<_>>();
+            assert!(numbers.iter().all(|n| *n != 0), "{seed}");
+            assert!((0..100).all(|_| random.next_signed().abs() <= 1.0));
+        }
+    }
+}
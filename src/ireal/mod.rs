@@ -0,0 +1,115 @@
+//! iReal Pro charts, the chord charts most jazz standards circulate as.
+//!
+//! An [`IReal`] song is read from its chart string ([`IReal::from_chart`]), or from an
+//! `irealbook://` link holding one or more songs with their title, composer, style and key
+//! ([`IReal::from_url`]). The chart is read into a [`Score`] of chord measures, with its
+//! sections and its form: repeats, endings, segno, coda, D.C. and D.S.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{ireal::*, score::*, *};
+//!
+//! let chart = "{*AT44D-7 |G7 |C^7 |x }[*BC-7 F7 |Bb^7 |E-7b5 A7 |D-7 Z";
+//! let song = IReal::from_chart("Exercise", chart).unwrap();
+//! let score = song.score();
+//!
+//! let labels = score.sections().map(|s| s.label()).collect::<Vec<_>>();
+//! assert_eq!(labels, ["A", "B"]);
+//! assert_eq!(score.measures().count(), 8);
+//! assert_eq!(score.unroll().len(), 12);
+//! assert_eq!(format!("{}", score.measures().nth(3).unwrap().bar(Staff::Chords).unwrap()), "Cmaj7");
+//! ```
+
+mod read;
+
+use crate::{score::Score, Key};
+
+pub use read::IRealError;
+
+/// An iReal Pro song: its chart read into a score.
+pub struct IReal {
+    title: String,
+    composer: String,
+    style: String,
+    key: Option<Key>,
+    score: Score,
+    warnings: Vec<String>,
+}
+
+impl IReal {
+    /// Reads a chart string (not obfuscated), eg. `[T44C^7 |A-7 |D-7 G7 |C^7 Z`.
+    ///
+    /// The bar lines (`|`, `[`, `]`, `{`, `}`, `Z`) give the measures and the repeats, the
+    /// section markers (`*A`, `*B`, `*i` for the intro, `*V` for the verse) the sections,
+    /// `T44` the time signatures, `N1`, `N2`... the endings, `S` the segno, `Q` the to coda
+    /// then the coda, and the comments `<D.C. al Coda>`, `<D.S. al Fine>`, `<Fine>` or
+    /// `<3x>` the other marks. `x` repeats the previous measure, and `r` the previous two.
+    ///
+    /// The chords of a measure share its beats, the first ones getting the extra beats (eg.
+    /// 2, 1 and 1 beats for three chords in 4/4), `p` (a slash) holding the previous chord
+    /// for a beat, and `n` being no chord. The chords are mapped to the crate's ones without
+    /// their bass; a chord which is not known is read as the nearest one (eg. C7alt as C7)
+    /// with a warning. The alternate chords (between parentheses) and the layout symbols
+    /// are skipped.
+    pub fn from_chart(title: &str, chart: &str) -> Result<Self, IRealError> {
+        let (score, warnings) = read::chart(title, chart)?;
+        Ok(Self {
+            title: title.to_string(),
+            composer: String::new(),
+            style: String::new(),
+            key: None,
+            score,
+            warnings,
+        })
+    }
+
+    /// Reads the songs of an `irealbook://` link: for each song, its title, composer,
+    /// style, key, an unused field and its chart, separated by `=`.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{ireal::*, *};
+    ///
+    /// let url = "irealbook://Vamp=Doe%20John=Medium%20Swing=F=n=[T44F7 |Bb7 Z";
+    /// let songs = IReal::from_url(url).unwrap();
+    /// assert_eq!(songs[0].title(), "Vamp");
+    /// assert_eq!(songs[0].composer(), "Doe John");
+    /// assert_eq!(songs[0].key(), Some(Key::major(F)));
+    /// assert_eq!(songs[0].score().measures().count(), 2);
+    /// ```
+    pub fn from_url(url: &str) -> Result<Vec<Self>, IRealError> {
+        read::url(url)
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn composer(&self) -> &str {
+        &self.composer
+    }
+
+    /// The style of the song, eg. "Medium Swing" or "Bossa Nova".
+    pub fn style(&self) -> &str {
+        &self.style
+    }
+
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    /// The chords of the song, with a measure per bar of the chart.
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    pub fn into_score(self) -> Score {
+        self.score
+    }
+
+    /// Returns what was approximated or skipped when the chart was read, eg. "measure 3:
+    /// read \"C7alt\" as C7".
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+}
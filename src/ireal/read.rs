@@ -0,0 +1,569 @@
+use std::{fmt::Display, iter::Peekable, str::Chars};
+
+use super::IReal;
+use crate::{
+    chords::Chords,
+    score::{self, Jump, Mark, Score},
+    Bar, Key, TimeSignature,
+};
+
+/// The errors of reading an iReal Pro chart.
+#[derive(Debug)]
+pub enum IRealError {
+    /// The chart is obfuscated, as in the `irealb://` links.
+    Obfuscated,
+    /// The link or the chart cannot be read.
+    Syntax(String),
+}
+
+/// What is played during a beat (or a slot) of a measure.
+#[derive(Clone, Debug, PartialEq)]
+enum Slot {
+    /// A chord, with the symbol of the crate (eg. "Cmaj7").
+    Chord(String),
+    NoChord,
+    /// The previous chord goes on (`p`).
+    Hold,
+}
+
+/// A measure of the chart, before its bar is built.
+struct Measure {
+    slots: Vec<Slot>,
+    time: TimeSignature,
+    marks: Vec<Mark>,
+    section: usize,
+}
+
+/// The state of a chart being read.
+struct ChartReader {
+    measures: Vec<Measure>,
+    sections: Vec<String>,
+    slots: Vec<Slot>,
+    /// The marks of the measure being read.
+    marks: Vec<Mark>,
+    time: TimeSignature,
+    ending: Option<u8>,
+    /// The number of times of the next repeat, from a `<3x>` comment.
+    times: Option<u8>,
+    codas: usize,
+    warnings: Vec<String>,
+}
+
+/// The iReal qualities and the suffixes of the crate's chords.
+const QUALITIES: [(&str, &str); 24] = [
+    ("", ""),
+    ("^", "maj7"),
+    ("^7", "maj7"),
+    ("^9", "maj9"),
+    ("^11", "maj11"),
+    ("^13", "maj13"),
+    ("-", "m"),
+    ("-7", "m7"),
+    ("-9", "m9"),
+    ("-11", "m11"),
+    ("-13", "m13"),
+    ("h", "m7(b5)"),
+    ("h7", "m7(b5)"),
+    ("-7b5", "m7(b5)"),
+    ("o", "dim"),
+    ("o7", "dim7"),
+    ("7", "7"),
+    ("7b5", "7b5"),
+    ("7#5", "7#5"),
+    ("+7", "7#5"),
+    ("9", "9"),
+    ("11", "11"),
+    ("13", "13"),
+    ("7b9b13", "13b9b13"),
+];
+
+/// Reads the songs of an `irealbook://` link.
+pub(super) fn url(url: &str) -> Result<Vec<IReal>, IRealError> {
+    if url.starts_with("irealb://") || url.contains("1r34LbKcu7") {
+        return Err(IRealError::Obfuscated);
+    }
+    let songs = url.strip_prefix("irealbook://").ok_or_else(|| {
+        IRealError::Syntax("the link does not start with irealbook://".to_string())
+    })?;
+    let fields = decode(songs);
+    let fields = fields.split('=').collect::<Vec<_>>();
+
+    let mut songs = vec![];
+    for song in fields
+        .chunks(6)
+        .filter(|song| song.iter().any(|f| !f.is_empty()))
+    {
+        let [title, composer, style, key, _, chart] = song else {
+            let title = song[0];
+            return Err(IRealError::Syntax(format!(
+                "missing the chart of {title:?}"
+            )));
+        };
+        let mut song = IReal::from_chart(title, chart)?;
+        song.composer = composer.to_string();
+        song.style = style.to_string();
+        song.key = self::key(key);
+        songs.push(song);
+    }
+    Ok(songs)
+}
+
+/// Reads a chart into a score of chord measures, and the warnings.
+pub(super) fn chart(title: &str, chart: &str) -> Result<(Score, Vec<String>), IRealError> {
+    if chart.starts_with("1r34LbKcu7") {
+        return Err(IRealError::Obfuscated);
+    }
+    // The shortcuts of the iReal storage: spaces, a bar line, and a bar line and a repeated
+    // measure.
+    let chart = chart
+        .replace("XyQ", "   ")
+        .replace("LZ", " |")
+        .replace("Kcl", "| x");
+
+    let mut reader = ChartReader {
+        measures: vec![],
+        sections: vec![],
+        slots: vec![],
+        marks: vec![],
+        time: TimeSignature::default(),
+        ending: None,
+        times: None,
+        codas: 0,
+        warnings: vec![],
+    };
+    let mut chars = chart.chars().peekable();
+    while let Some(c) = chars.next() {
+        reader.read(c, &mut chars);
+    }
+    reader.close();
+
+    if reader.measures.is_empty() {
+        return Err(IRealError::Syntax(format!(
+            "the chart of {title:?} has no measure"
+        )));
+    }
+    Ok((reader.score(title), reader.warnings))
+}
+
+impl ChartReader {
+    fn read(&mut self, c: char, chars: &mut Peekable<Chars>) {
+        match c {
+            ' ' | ',' | 'f' | 's' | 'l' | 'Y' | 'U' => {}
+            '|' => self.close(),
+            '[' | ']' | 'Z' => {
+                self.close();
+                self.ending = None;
+            }
+            '{' => {
+                self.close();
+                self.ending = None;
+                self.marks.push(Mark::RepeatStart);
+            }
+            '}' => {
+                let times = self.times.take().unwrap_or(2);
+                self.end_mark(Mark::RepeatEnd(times));
+                self.close();
+                self.ending = None;
+            }
+            '*' => {
+                self.close();
+                let label = match chars.next() {
+                    Some('i') => "Intro".to_string(),
+                    Some('V') => "Verse".to_string(),
+                    Some(c) => c.to_string(),
+                    None => String::new(),
+                };
+                self.sections.push(label);
+            }
+            'T' => {
+                let digits = (chars.next(), chars.next());
+                match time(digits) {
+                    Some(time) => self.time = time,
+                    None => self.warn(format!("skipped the time signature {digits:?}")),
+                }
+            }
+            'N' => {
+                self.ending = chars
+                    .next_if(char::is_ascii_digit)
+                    .and_then(|d| d.to_digit(10))
+                    .map(|d| d as u8)
+                    .filter(|d| *d > 0);
+            }
+            'S' => self.marks.push(Mark::Segno),
+            'Q' => {
+                self.codas += 1;
+                match self.codas {
+                    1 => self.marks.push(Mark::ToCoda),
+                    _ => self.marks.push(Mark::Coda),
+                }
+            }
+            '<' => {
+                let comment = chars.by_ref().take_while(|c| *c != '>').collect::<String>();
+                self.comment(&comment);
+            }
+            '(' => chars.by_ref().take_while(|c| *c != ')').for_each(drop),
+            'x' => {
+                let previous = self.measures.last().map(|m| m.slots.clone());
+                self.slots = previous.unwrap_or_default();
+            }
+            'r' => {
+                let count = self.measures.len();
+                if count >= 2 {
+                    self.slots = self.measures[count - 2].slots.clone();
+                    let last = self.measures[count - 1].slots.clone();
+                    self.close();
+                    self.slots = last;
+                }
+            }
+            'p' => self.slots.push(Slot::Hold),
+            'n' => self.slots.push(Slot::NoChord),
+            'W' => {
+                // An invisible root, with a bass only.
+                while chars
+                    .next_if(|c| matches!(c, '/' | '#' | 'b' | 'A'..='G'))
+                    .is_some()
+                {}
+                self.slots.push(Slot::Hold);
+            }
+            'A'..='G' => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|c| is_quality(*c)) {
+                    symbol.push(c);
+                }
+                let slot = self.chord(&symbol);
+                self.slots.push(slot);
+            }
+            c => self.warn(format!("skipped {c:?}")),
+        }
+    }
+
+    /// The slot of a chord symbol, eg. "Bb^7" or "E-7b5/Bb".
+    fn chord(&mut self, symbol: &str) -> Slot {
+        let symbol = symbol.split('/').next().unwrap_or_default();
+        let split = match symbol[1..].starts_with(['#', 'b']) {
+            true => 2,
+            false => 1,
+        };
+        let (root, quality) = symbol.split_at(split);
+
+        // The longest known quality the chord starts with.
+        let (known, suffix) = QUALITIES
+            .iter()
+            .filter(|(q, _)| quality.starts_with(q))
+            .max_by_key(|(q, _)| q.len())
+            .unwrap_or(&QUALITIES[0]);
+        let name = format!("{root}{suffix}");
+        match name.parse::<Chords>() {
+            Ok(chord) => {
+                if known != &quality {
+                    self.warn(format!("read {symbol:?} as {chord}"));
+                }
+                Slot::Chord(chord.to_string())
+            }
+            Err(_) => {
+                self.warn(format!("skipped the unknown chord {symbol:?}"));
+                Slot::NoChord
+            }
+        }
+    }
+
+    /// Reads a comment: a jump, the fine, or the number of times of the next repeat.
+    fn comment(&mut self, comment: &str) {
+        let lower = comment.trim().to_lowercase();
+        let jump = match (lower.contains("coda"), lower.contains("fine")) {
+            (true, _) => Jump::Coda,
+            (_, true) => Jump::Fine,
+            _ => Jump::End,
+        };
+        if lower.starts_with("d.c.") {
+            self.end_mark(Mark::DaCapo(jump));
+        } else if lower.starts_with("d.s.") {
+            self.end_mark(Mark::DalSegno(jump));
+        } else if lower == "fine" {
+            self.end_mark(Mark::Fine);
+        } else if let Some(times) = lower.strip_suffix('x').and_then(|t| t.parse().ok()) {
+            self.times = Some(times);
+        }
+    }
+
+    /// Sets a mark at the end of the measure being read, or of the previous one when the
+    /// measure is not started.
+    fn end_mark(&mut self, mark: Mark) {
+        match (self.slots.is_empty(), self.measures.last_mut()) {
+            (true, Some(measure)) => measure.marks.push(mark),
+            _ => self.marks.push(mark),
+        }
+    }
+
+    /// Ends the measure being read, if it has any chord.
+    fn close(&mut self) {
+        if self.slots.is_empty() {
+            return;
+        }
+        let mut slots = std::mem::take(&mut self.slots);
+        // A measure starting with a slash goes on with the last chord.
+        if slots[0] == Slot::Hold {
+            let last = self.measures.last().and_then(|m| {
+                m.slots
+                    .iter()
+                    .rev()
+                    .find(|s| matches!(s, Slot::Chord(_) | Slot::NoChord))
+            });
+            slots[0] = last.cloned().unwrap_or(Slot::NoChord);
+        }
+
+        let mut marks = std::mem::take(&mut self.marks);
+        if let Some(ending) = self.ending {
+            marks.insert(0, Mark::Ending(ending));
+        }
+        if self.sections.is_empty() {
+            self.sections.push(String::new());
+        }
+        self.measures.push(Measure {
+            slots,
+            time: self.time.clone(),
+            marks,
+            section: self.sections.len() - 1,
+        });
+    }
+
+    fn warn(&mut self, warning: String) {
+        let measure = self.measures.len() + 1;
+        self.warnings.push(format!("measure {measure}: {warning}"));
+    }
+
+    /// The score of the measures, a section per section marker.
+    fn score(&self, title: &str) -> Score {
+        let mut score = Score::new(title);
+        for (index, label) in self.sections.iter().enumerate() {
+            let measures = self.measures.iter().filter(|m| m.section == index);
+            let section = measures.fold(score::Section::new(label), |section, measure| {
+                let chords = score::Measure::chords(measure.bar());
+                let chords = measure
+                    .marks
+                    .iter()
+                    .fold(chords, |chords, mark| chords.with_mark(*mark));
+                section.with_measure(chords)
+            });
+            if section.measures().next().is_some() {
+                score = score.with_section(section);
+            }
+        }
+        score
+    }
+}
+
+impl Measure {
+    /// The bar of the chords: the slots share the beats of the measure, the first ones
+    /// getting the extra beats, and a held slot lengthening the previous chord.
+    fn bar(&self) -> Bar {
+        let beats = self.time.numerator() as u32;
+        let count = self.slots.len() as u32;
+        let durations = (0..count).map(|index| match count <= beats {
+            true => {
+                let extra = (index < beats % count) as u32;
+                self.time.unit() * (beats / count + extra)
+            }
+            false => self.time.duration() / count,
+        });
+
+        let mut chords: Vec<(&Slot, crate::Duration)> = vec![];
+        for (slot, duration) in self.slots.iter().zip(durations) {
+            match (slot, chords.last_mut()) {
+                (Slot::Hold, Some((_, last))) => *last += duration,
+                (slot, _) => chords.push((slot, duration)),
+            }
+        }
+
+        let bar = Bar::new().with_time_signature(self.time.clone());
+        chords
+            .into_iter()
+            .fold(bar, |bar, (slot, duration)| match slot {
+                Slot::Chord(symbol) => match symbol.parse::<Chords>() {
+                    Ok(chord) => bar.with_chord(chord, duration),
+                    Err(_) => bar.with_silence(duration),
+                },
+                _ => bar.with_silence(duration),
+            })
+    }
+}
+
+/// Returns true if the character can be part of the quality or the bass of a chord.
+fn is_quality(c: char) -> bool {
+    c.is_ascii_digit() || "^-+#bhoasudlt/ABCDEFG".contains(c)
+}
+
+/// The time signature of the two digits after `T`, eg. 4/4 for `T44` and 12/8 for `T12`.
+fn time(digits: (Option<char>, Option<char>)) -> Option<TimeSignature> {
+    let (Some(first), Some(second)) = digits else {
+        return None;
+    };
+    let (numerator, denominator) = match (first.to_digit(10)?, second.to_digit(10)?) {
+        (1, 2) => (12, 8),
+        (n, d) if n > 0 && matches!(d, 2 | 4 | 8) => (n, d),
+        _ => return None,
+    };
+    Some(TimeSignature::new(numerator as u8, denominator as u8))
+}
+
+/// The key of a song, eg. "Eb" or "C-" for C minor.
+fn key(key: &str) -> Option<Key> {
+    match key.strip_suffix('-') {
+        Some(tonic) => tonic.parse().ok().map(Key::minor),
+        None => key.parse().ok().map(Key::major),
+    }
+}
+
+/// Replaces the escaped characters of a link, eg. "%20" by a space.
+fn decode(text: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, escaped) {
+            (b'%', Some(escaped)) => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl Display for IRealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IRealError::Obfuscated => write!(f, "the chart is obfuscated"),
+            IRealError::Syntax(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for IRealError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{score::Staff, A_SHARP, C, D};
+
+    fn bars(song: &IReal) -> Vec<String> {
+        let bars = song
+            .score()
+            .measures()
+            .map(|m| m.bar(Staff::Chords).unwrap());
+        bars.map(|b| b.to_string()).collect()
+    }
+
+    fn marks(song: &IReal, measure: usize) -> Vec<Mark> {
+        let measure = song.score().measures().nth(measure).unwrap();
+        measure.marks().copied().collect()
+    }
+
+    #[test]
+    fn measures() {
+        let chart = "[T44C^7 A-7 |D-7 G7 C7 |F^7 p p Bb7 |Eh7 A7b9 D-7 G7 C^7 |n |p Ab-6 ]";
+        let song = IReal::from_chart("", chart).unwrap();
+        assert_eq!(
+            bars(&song),
+            [
+                "Cmaj7 Am7",
+                "Dm7 G7 C7",
+                "Fmaj7 A#7",
+                "Em7(b5) A7 Dm7 G7 Cmaj7",
+                "_",
+                "_ G#m",
+            ]
+        );
+        let measure = song.score().measures().nth(1).unwrap();
+        let bar = measure.bar(Staff::Chords).unwrap();
+        let durations = bar.elements().map(|e| e.duration()).collect::<Vec<_>>();
+        assert_eq!(durations, [crate::HALF, crate::QUARTER, crate::QUARTER]);
+        assert_eq!(
+            song.warnings().collect::<Vec<_>>(),
+            [
+                r#"measure 4: read "A7b9" as A7"#,
+                r#"measure 6: read "Ab-6" as G#m"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn form() {
+        let chart = "{*AT34C |N1D-7 G7 }|N2G7 |C Z[*BS F |Q C <D.S. al Coda> ][*CQ G7 |C Z";
+        let song = IReal::from_chart("", chart).unwrap();
+        let labels = song
+            .score()
+            .sections()
+            .map(|s| s.label())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["A", "B", "C"]);
+        assert_eq!(marks(&song, 0), [Mark::RepeatStart]);
+        assert_eq!(marks(&song, 1), [Mark::Ending(1), Mark::RepeatEnd(2)]);
+        assert_eq!(marks(&song, 2), [Mark::Ending(2)]);
+        assert_eq!(marks(&song, 3), [Mark::Ending(2)]);
+        assert_eq!(marks(&song, 4), [Mark::Segno]);
+        assert_eq!(marks(&song, 5), [Mark::ToCoda, Mark::DalSegno(Jump::Coda)]);
+        assert_eq!(marks(&song, 6), [Mark::Coda]);
+        let measure = song.score().measures().next().unwrap();
+        let time = measure.bar(Staff::Chords).unwrap().time_signature();
+        assert_eq!(time, &TimeSignature::new(3, 4));
+        assert_eq!(song.score().unroll().len(), 11);
+    }
+
+    #[test]
+    fn repeats() {
+        let song = IReal::from_chart("", "<3x>{C |F }|G7 |x |C |r|  |Kcl Z").unwrap();
+        assert_eq!(bars(&song), ["C", "F", "G7", "G7", "C", "G7", "C", "C"]);
+        assert_eq!(marks(&song, 1), [Mark::RepeatEnd(3)]);
+
+        let song = IReal::from_chart("", "T68C-7XyQ, F7LZBb^7 Z").unwrap();
+        assert_eq!(bars(&song), ["Cm7 F7", "A#maj7"]);
+        let measure = song.score().measures().next().unwrap();
+        let time = measure.bar(Staff::Chords).unwrap().time_signature();
+        assert_eq!(time, &TimeSignature::new(6, 8));
+    }
+
+    #[test]
+    fn links() {
+        let url = "irealbook://Blues=Doe%20John=Swing=C-=n=[T44C-7 |F7 Z\
+                   =Second=Ray=Bossa=Bb=n=[Bb^7 |x Z";
+        let songs = IReal::from_url(url).unwrap();
+        assert_eq!(songs.len(), 2);
+        assert_eq!(songs[0].key(), Some(Key::minor(C)));
+        assert_eq!(songs[1].title(), "Second");
+        assert_eq!(songs[1].style(), "Bossa");
+        assert_eq!(songs[1].key(), Some(Key::major(A_SHARP)));
+        assert_eq!(songs[1].score().measures().count(), 2);
+        assert_eq!(key("D-"), Some(Key::minor(D)));
+        assert_eq!(decode("a%20b%2"), "a b%2");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            IReal::from_url("irealb://Song=1r34LbKcu7..."),
+            Err(IRealError::Obfuscated)
+        ));
+        assert_eq!(
+            IReal::from_url("irealbook://Song=Composer=Style")
+                .err()
+                .unwrap()
+                .to_string(),
+            r#"missing the chart of "Song""#
+        );
+        assert_eq!(
+            IReal::from_chart("Empty", "[ | Z")
+                .err()
+                .unwrap()
+                .to_string(),
+            r#"the chart of "Empty" has no measure"#
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    chords::{Chord, Chords},
+    Note,
+};
+use std::fmt::Display;
+
+/// The mode of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// A key: a tonic and a mode, with its key signature.
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let key = Key::major(F);
+/// assert_eq!(key.signature(), -1);
+/// assert_eq!(key.spell(A_SHARP), "Bb");
+/// assert_eq!(Key::minor(E).to_string(), "E minor");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    tonic: Note,
+    mode: Mode,
+}
+
+/// How the black keys are written: always with sharps, always with flats, or following a key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Spelling {
+    #[default]
+    Sharps,
+    Flats,
+    Key(Key),
+}
+
+impl Key {
+    pub fn new(tonic: Note, mode: Mode) -> Self {
+        Self {
+            tonic: tonic.base(),
+            mode,
+        }
+    }
+
+    pub fn major(tonic: Note) -> Self {
+        Self::new(tonic, Mode::Major)
+    }
+
+    pub fn minor(tonic: Note) -> Self {
+        Self::new(tonic, Mode::Minor)
+    }
+
+    pub fn tonic(&self) -> Note {
+        self.tonic
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The relative key: the minor key of a major key, and the major key of a minor key.
+    pub fn relative(&self) -> Self {
+        match self.mode {
+            Mode::Major => Self::minor(Note::from(i8::from(self.tonic) - 3)),
+            Mode::Minor => Self::major(Note::from(i8::from(self.tonic) + 3)),
+        }
+    }
+
+    /// The number of sharps (positive) or flats (negative) of the key signature, from -5
+    /// (Db major) to 6 (F# major).
+    pub fn signature(&self) -> i8 {
+        let major = match self.mode {
+            Mode::Major => *self,
+            Mode::Minor => self.relative(),
+        };
+        let fifths = (major.tonic.pitch_class() as i8 * 7).rem_euclid(12);
+        match fifths {
+            f if f > 6 => f - 12,
+            f => f,
+        }
+    }
+
+    /// Writes the note with the accidentals of the key: sharps in the sharp keys, flats in
+    /// the flat keys. Without any accidental (C major, A minor), the usual chromatic notes
+    /// are used: C#, Eb, F#, Ab and Bb.
+    pub fn spell(&self, note: Note) -> String {
+        let flats = match self.signature() {
+            0 => matches!(note.pitch_class(), 3 | 8 | 10),
+            s => s < 0,
+        };
+        match flats {
+            true => format!("{note:x}"),
+            false => format!("{note:X}"),
+        }
+    }
+}
+
+impl Spelling {
+    /// Writes the note.
+    pub fn note(&self, note: Note) -> String {
+        match self {
+            Spelling::Sharps => format!("{note:X}"),
+            Spelling::Flats => format!("{note:x}"),
+            Spelling::Key(key) => key.spell(note),
+        }
+    }
+
+    /// Writes the symbol of the chord, with its root spelled (eg. Bbmaj7 instead of A#maj7).
+    pub fn chord(&self, chord: &Chords) -> String {
+        format!("{}{}", self.note(chord.root()), chord.suffix())
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Major => write!(f, "major"),
+            Mode::Minor => write!(f, "minor"),
+        }
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.spell(self.tonic), self.mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, A_SHARP, C, C_SHARP, D, D_SHARP, E, F, F_SHARP, G, G_SHARP};
+
+    #[test]
+    fn signature() {
+        assert_eq!(Key::major(C).signature(), 0);
+        assert_eq!(Key::major(G).signature(), 1);
+        assert_eq!(Key::major(E).signature(), 4);
+        assert_eq!(Key::major(F_SHARP).signature(), 6);
+        assert_eq!(Key::major(C_SHARP).signature(), -5);
+        assert_eq!(Key::major(A_SHARP).signature(), -2);
+        assert_eq!(Key::minor(A).signature(), 0);
+        assert_eq!(Key::minor(D).signature(), -1);
+        assert_eq!(Key::minor(E).signature(), 1);
+    }
+
+    #[test]
+    fn relative() {
+        assert_eq!(Key::major(C).relative(), Key::minor(A));
+        assert_eq!(Key::minor(E).relative(), Key::major(G));
+    }
+
+    #[test]
+    fn spell() {
+        assert_eq!(Key::major(D_SHARP).to_string(), "Eb major");
+        assert_eq!(Key::major(E).spell(G_SHARP), "G#");
+        assert_eq!(Key::major(C).spell(A_SHARP), "Bb");
+        assert_eq!(Key::major(C).spell(F_SHARP), "F#");
+        assert_eq!(Key::minor(G).to_string(), "G minor");
+    }
+
+    #[test]
+    fn spelling() {
+        let chord = A_SHARP.maj7();
+        assert_eq!(Spelling::Sharps.chord(&chord), "A#maj7");
+        assert_eq!(Spelling::Flats.chord(&chord), "Bbmaj7");
+        assert_eq!(Spelling::Key(Key::major(F)).chord(&chord), "Bbmaj7");
+        assert_eq!(Spelling::default().note(D_SHARP), "D#");
+    }
+}
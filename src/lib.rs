@@ -36,14 +36,37 @@
 //! println!("{scale:x}");
 //! ```
 
+pub mod abc;
 mod bar;
+mod chart;
+pub mod chordpro;
 pub mod chords;
+mod duration;
+pub mod fretboard;
+pub mod groove;
 mod interval;
+pub mod ireal;
+mod key;
+pub mod lilypond;
+pub mod midi;
+pub mod musicxml;
 mod note;
+mod pitch_class_set;
 pub mod scales;
+pub mod score;
+pub mod synth;
+mod time_signature;
+pub mod timeline;
 mod tone;
+pub mod tuning;
+pub mod voicings;
 
 pub use bar::*;
+pub use chart::*;
+pub use duration::*;
 pub use interval::*;
+pub use key::*;
 pub use note::*;
+pub use pitch_class_set::*;
+pub use time_signature::*;
 pub use tone::*;
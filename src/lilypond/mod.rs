@@ -0,0 +1,134 @@
+//! LilyPond sources, to engrave exercises as PDF.
+//!
+//! A [`LilyPond`] document holds staves of bars, a piano staff for the right hand and the
+//! left hand, and the chord names written above them (`\chordmode`). The notes are spelled
+//! with the key, and written with relative (`\relative`) or absolute pitches.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{lilypond::*, *};
+//!
+//! let rh = [Bar::new()
+//!     .with_note(A, 8)
+//!     .with_note(C, 8)
+//!     .with_note(D, 8)
+//!     .with_note(E, 8)
+//!     .with_note(G, 2)];
+//! let lh = [Bar::new().with_chord(D.min7(), 1)];
+//! let chords = [Bar::new().with_chord(D.min9(), 1)];
+//! let ly = LilyPond::new("Exercise")
+//!     .with_chords(&chords)
+//!     .with_piano(&rh, &lh)
+//!     .to_lilypond();
+//!
+//! assert!(ly.contains("\\new PianoStaff <<"));
+//! assert!(ly.contains("a8 c d e g2 |"));
+//! assert!(ly.contains("<d' f a c>1 |"));
+//! assert!(ly.contains("d1:m9 |"));
+//! ```
+
+mod write;
+
+pub use crate::musicxml::Clef;
+
+use crate::{Bar, Key};
+
+/// How the octave of the notes is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pitches {
+    /// The first note is written with its octave, and each next note in the octave nearest
+    /// to the previous one (`\relative`), with `'` or `,` when it is further than a fourth.
+    #[default]
+    Relative,
+    /// Each note is written with its octave, c' being the middle C.
+    Absolute,
+}
+
+/// A staff of a document, or the two staves of a piano.
+enum Staves<'a> {
+    Staff(Clef, Vec<&'a Bar>),
+    Piano(Vec<&'a Bar>, Vec<&'a Bar>),
+}
+
+/// A LilyPond document.
+pub struct LilyPond<'a> {
+    title: String,
+    key: Option<Key>,
+    pitches: Pitches,
+    chords: Vec<&'a Bar>,
+    staves: Vec<Staves<'a>>,
+}
+
+impl<'a> LilyPond<'a> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            key: None,
+            pitches: Pitches::default(),
+            chords: vec![],
+            staves: vec![],
+        }
+    }
+
+    /// Sets the key signature, which also gives the spelling of the notes (sharps by
+    /// default).
+    pub fn with_key(self, key: Key) -> Self {
+        Self {
+            key: Some(key),
+            ..self
+        }
+    }
+
+    /// Sets how the octave of the notes is written (relative by default).
+    pub fn with_pitches(self, pitches: Pitches) -> Self {
+        Self { pitches, ..self }
+    }
+
+    /// Writes the chords of the bars as chord names above the staves.
+    pub fn with_chords(self, bars: impl IntoIterator<Item = &'a Bar>) -> Self {
+        Self {
+            chords: bars.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Adds a staff, below the previous ones.
+    pub fn with_staff(self, clef: Clef, bars: impl IntoIterator<Item = &'a Bar>) -> Self {
+        let mut staves = self.staves;
+        staves.push(Staves::Staff(clef, bars.into_iter().collect()));
+        Self { staves, ..self }
+    }
+
+    /// Adds a piano staff, below the previous staves: the right hand in treble clef, and the
+    /// left hand in bass clef.
+    pub fn with_piano(
+        self,
+        right_hand: impl IntoIterator<Item = &'a Bar>,
+        left_hand: impl IntoIterator<Item = &'a Bar>,
+    ) -> Self {
+        let mut staves = self.staves;
+        staves.push(Staves::Piano(
+            right_hand.into_iter().collect(),
+            left_hand.into_iter().collect(),
+        ));
+        Self { staves, ..self }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The content of the `.ly` file.
+    ///
+    /// The durations which cannot be written as a single note are written as tied notes,
+    /// the tuplets with `\tuplet`, and the voices of a bar with `<< { } \\ { } >>`. A voice
+    /// shorter than its time signature is completed with spacer rests.
+    pub fn to_lilypond(&self) -> String {
+        write::document(self)
+    }
+
+    /// Writes the `.ly` file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_lilypond())
+    }
+}
@@ -0,0 +1,562 @@
+use super::{Clef, LilyPond, Pitches, Staves};
+use crate::{
+    chords::{Chord, Chords},
+    Bar, BarElement, Duration, Note, Spelling, TimeSignature,
+};
+
+/// The version of LilyPond the documents are written for.
+const VERSION: &str = "2.24.0";
+
+/// The `\chordmode` modifiers of each chord suffix, eg. "m7.5-" for m7(b5).
+const MODIFIERS: [(&str, &str); 20] = [
+    ("", ""),
+    ("maj7", "maj7"),
+    ("maj9", "maj9"),
+    ("maj11", "maj11"),
+    ("maj13", "maj13"),
+    ("m", "m"),
+    ("m7", "m7"),
+    ("m7(b5)", "m7.5-"),
+    ("m9", "m9"),
+    ("m11", "m11"),
+    ("m13", "m13"),
+    ("7", "7"),
+    ("7b5", "7.5-"),
+    ("7#5", "7.5+"),
+    ("9", "9"),
+    ("11", "11"),
+    ("13", "13"),
+    ("13b9b13", "7.9-.13-"),
+    ("dim", "dim"),
+    ("dim7", "dim7"),
+];
+
+/// A LilyPond text being written, indented by two spaces.
+struct Ly {
+    text: String,
+    depth: usize,
+}
+
+/// The notes of a staff being written: the spelling, and the previous note and duration
+/// which the next ones are written from.
+struct Music {
+    spelling: Spelling,
+    pitches: Pitches,
+    /// The staff position of the previous note, counted in steps from C0.
+    reference: i32,
+    duration: Option<String>,
+}
+
+/// The LilyPond text of a document.
+pub(super) fn document(document: &LilyPond) -> String {
+    let spelling = document.key.map_or(Spelling::Sharps, Spelling::Key);
+    let mut ly = Ly::new();
+    ly.line(&format!("\\version \"{VERSION}\""));
+    ly.line("");
+    ly.open("\\header {");
+    ly.line(&format!("title = \"{}\"", escape(&document.title)));
+    ly.close("}");
+    ly.line("");
+
+    ly.open("\\score {");
+    ly.open("<<");
+    if !document.chords.is_empty() {
+        ly.open("\\new ChordNames \\chordmode {");
+        let mut music = Music::new(spelling, document.pitches);
+        for bar in &document.chords {
+            ly.line(&format!("{} |", music.chords(bar)));
+        }
+        ly.close("}");
+    }
+    for staves in &document.staves {
+        match staves {
+            Staves::Staff(clef, bars) => staff(&mut ly, document, *clef, bars),
+            Staves::Piano(right_hand, left_hand) => {
+                ly.open("\\new PianoStaff <<");
+                staff(&mut ly, document, Clef::Treble, right_hand);
+                staff(&mut ly, document, Clef::Bass, left_hand);
+                ly.close(">>");
+            }
+        }
+    }
+    ly.close(">>");
+    ly.line("\\layout { }");
+    ly.close("}");
+    ly.text
+}
+
+/// Writes a staff: its clef, key and time signature, then a line per bar.
+fn staff(ly: &mut Ly, document: &LilyPond, clef: Clef, bars: &[&Bar]) {
+    let spelling = document.key.map_or(Spelling::Sharps, Spelling::Key);
+    let mut music = Music::new(spelling, document.pitches);
+    ly.open(match document.pitches {
+        Pitches::Relative => "\\new Staff \\relative {",
+        Pitches::Absolute => "\\new Staff {",
+    });
+    if clef == Clef::Bass {
+        ly.line("\\clef bass");
+    }
+    if let Some(key) = document.key {
+        ly.line(&format!(
+            "\\key {} \\{}",
+            name(&spelling, key.tonic()),
+            key.mode()
+        ));
+    }
+
+    let mut time: Option<&TimeSignature> = None;
+    for bar in bars {
+        if time != Some(bar.time_signature()) {
+            ly.line(&format!("\\time {}", meter(bar.time_signature())));
+            time = Some(bar.time_signature());
+        }
+        ly.line(&format!("{} |", music.bar(bar)));
+    }
+    ly.close("}");
+}
+
+impl Music {
+    fn new(spelling: Spelling, pitches: Pitches) -> Self {
+        Self {
+            spelling,
+            pitches,
+            // F3: without a starting pitch, the first note of `\relative` is absolute.
+            reference: 3 * 7 + 3,
+            duration: None,
+        }
+    }
+
+    /// The voices of a bar, with `<< { } \\ { } >>` when there are more than one.
+    fn bar(&mut self, bar: &Bar) -> String {
+        let expected = bar.time_signature().duration();
+        let voices = bar
+            .voices()
+            .map(|voice| {
+                let elements = voice.elements().collect::<Vec<_>>();
+                let missing = expected
+                    .checked_sub(voice.duration())
+                    .unwrap_or(Duration::new(0, 1));
+                self.voice(&elements, missing)
+            })
+            .collect::<Vec<_>>();
+        match &voices[..] {
+            [voice] => voice.clone(),
+            voices => format!("<< {{ {} }} >>", voices.join(" } \\\\ { ")),
+        }
+    }
+
+    /// The elements of a voice, the tuplets being grouped in a `\tuplet`, and the missing
+    /// duration written as spacer rests.
+    fn voice(&mut self, elements: &[&BarElement], missing: Duration) -> String {
+        let mut tokens = vec![];
+        let mut index = 0;
+        while index < elements.len() {
+            match tuplet(elements[index].duration()) {
+                Some((actual, normal)) => {
+                    let run = elements[index..]
+                        .iter()
+                        .take_while(|e| tuplet(e.duration()) == Some((actual, normal)))
+                        .collect::<Vec<_>>();
+                    let notes = run
+                        .iter()
+                        .map(|e| {
+                            let written = e.duration() * actual / normal;
+                            self.element(e, &[length(written)])
+                        })
+                        .collect::<Vec<_>>();
+                    tokens.push(format!(
+                        "\\tuplet {actual}/{normal} {{ {} }}",
+                        notes.join(" ")
+                    ));
+                    index += run.len();
+                }
+                None => {
+                    let element = elements[index];
+                    tokens.push(self.element(element, &lengths(element.duration())));
+                    index += 1;
+                }
+            }
+        }
+        if !missing.is_zero() {
+            for silence in lengths(missing) {
+                let duration = self.duration(silence);
+                tokens.push(format!("s{duration}"));
+            }
+        }
+        tokens.join(" ")
+    }
+
+    /// An element, written as notes tied together when it has more than one length.
+    fn element(&mut self, element: &BarElement, lengths: &[String]) -> String {
+        let pieces = lengths.iter().map(|length| {
+            let duration = self.duration(length.clone());
+            match element {
+                BarElement::Silence(_) => format!("r{duration}"),
+                BarElement::Note(note, _) => format!("{}{duration}", self.pitch(*note)),
+                BarElement::Chord(chord, _) => format!("{}{duration}", self.chord(&stacked(chord))),
+                BarElement::Cluster(notes, _) => format!("{}{duration}", self.chord(notes)),
+            }
+        });
+        let separator = match element {
+            BarElement::Silence(_) => " ",
+            _ => "~ ",
+        };
+        pieces.collect::<Vec<_>>().join(separator)
+    }
+
+    /// The chord names of a bar (`\chordmode`), a silence after a chord being written as no
+    /// chord (N.C.), and the other silences and notes as spacer rests.
+    fn chords(&mut self, bar: &Bar) -> String {
+        let mut after_chord = false;
+        let tokens = bar.elements().map(|element| {
+            // A chord name lasts the whole duration, whatever it is.
+            let length = match element.duration().is_simple() {
+                true => length(element.duration()),
+                false => scaled(element.duration()),
+            };
+            let duration = self.duration(length);
+            let token = match element {
+                BarElement::Chord(chord, _) => {
+                    let root = name(&self.spelling, chord.root());
+                    match modifier(chord) {
+                        "" => format!("{root}{duration}"),
+                        modifier => format!("{root}{duration}:{modifier}"),
+                    }
+                }
+                _ if after_chord => format!("r{duration}"),
+                _ => format!("s{duration}"),
+            };
+            after_chord = matches!(element, BarElement::Chord(_, _));
+            token
+        });
+        tokens.collect::<Vec<_>>().join(" ")
+    }
+
+    /// The notes of a chord or a cluster, the next note being written from the first one.
+    fn chord(&mut self, notes: &[Note]) -> String {
+        let mut first = None;
+        let pitches = notes
+            .iter()
+            .map(|note| {
+                let pitch = self.pitch(*note);
+                first.get_or_insert(self.reference);
+                pitch
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = first {
+            self.reference = first;
+        }
+        format!("<{}>", pitches.join(" "))
+    }
+
+    /// A note spelled with the key, with its octave marks.
+    fn pitch(&mut self, note: Note) -> String {
+        let spelled = self.spelling.note(note);
+        let letter = spelled.chars().next().unwrap_or('C');
+        let alter = spelled.matches('#').count() as i8 - spelled.matches('b').count() as i8;
+        let octave = (i8::from(note) - alter - step(letter)).div_euclid(12) + 4;
+        let position = octave as i32 * 7 + "CDEFGAB".find(letter).unwrap_or(0) as i32;
+
+        let marks = match self.pitches {
+            Pitches::Absolute => octave as i32 - 3,
+            Pitches::Relative => (position - self.reference + 3).div_euclid(7),
+        };
+        self.reference = position;
+        let marks = match marks >= 0 {
+            true => "'".repeat(marks as usize),
+            false => ",".repeat(marks.unsigned_abs() as usize),
+        };
+        format!("{}{marks}", name(&self.spelling, note))
+    }
+
+    /// The length to write, nothing when it is the one of the previous note.
+    fn duration(&mut self, length: String) -> String {
+        match self.duration.as_ref() == Some(&length) {
+            true => String::new(),
+            false => {
+                self.duration = Some(length.clone());
+                length
+            }
+        }
+    }
+}
+
+/// The name of a note without its octave, eg. "bes" for Bb or "fis" for F#.
+fn name(spelling: &Spelling, note: Note) -> String {
+    let spelled = spelling.note(note);
+    let mut chars = spelled.chars();
+    let letter = chars.next().unwrap_or('C').to_ascii_lowercase();
+    let accidentals = chars.map(|c| match c {
+        '#' => "is",
+        _ => "es",
+    });
+    let mut name = accidentals.fold(letter.to_string(), |name, accidental| name + accidental);
+    // Eb and Ab are written "es" and "as".
+    if name.starts_with("ee") || name.starts_with("ae") {
+        name.remove(1);
+    }
+    name
+}
+
+/// The `\chordmode` modifiers of the chord, eg. "7.9-.13-".
+fn modifier(chord: &Chords) -> &'static str {
+    let modifier = MODIFIERS
+        .iter()
+        .find(|(suffix, _)| *suffix == chord.suffix());
+    match (modifier, chord) {
+        (Some((_, modifier)), _) => modifier,
+        (None, Chords::Major(_, _)) => "",
+        (None, Chords::Minor(_, _)) => "m",
+        (None, Chords::Dominant(_, _)) => "7",
+        (None, Chords::Diminished(_, _)) => "dim",
+    }
+}
+
+/// The notes of a chord stacked upward from its root, in root position.
+fn stacked(chord: &Chords) -> Vec<Note> {
+    let mut notes: Vec<Note> = vec![];
+    for note in chord.notes() {
+        let mut note = *note;
+        while notes.last().is_some_and(|last| note <= *last) {
+            note = note + crate::OCTAVE;
+        }
+        notes.push(note);
+    }
+    notes
+}
+
+/// The pitch class of a note letter (C being 0).
+fn step(letter: char) -> i8 {
+    match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        _ => 11,
+    }
+}
+
+/// The tuplet (actual and normal notes) a duration is written in, if it is not a simple
+/// duration.
+fn tuplet(duration: Duration) -> Option<(u32, u32)> {
+    if duration.denominator().is_power_of_two() {
+        return None;
+    }
+    [(3, 2), (5, 4), (7, 4)]
+        .into_iter()
+        .find(|(actual, normal)| (duration * *actual / *normal).is_simple())
+}
+
+/// The length of a simple duration, eg. "4." for a dotted quarter.
+fn length(duration: Duration) -> String {
+    let dots = (duration.numerator() + 1).trailing_zeros() - 1;
+    let value = duration.denominator() >> dots;
+    format!("{value}{}", ".".repeat(dots as usize))
+}
+
+/// The lengths of the notes tied together to write a duration, or a scaled whole note when
+/// it cannot be written with notes.
+fn lengths(duration: Duration) -> Vec<String> {
+    if duration.is_simple() {
+        vec![length(duration)]
+    } else if duration.denominator().is_power_of_two() {
+        duration.split().into_iter().map(length).collect()
+    } else {
+        vec![scaled(duration)]
+    }
+}
+
+/// A whole note scaled to the duration, eg. "1*5/8".
+fn scaled(duration: Duration) -> String {
+    format!("1*{}/{}", duration.numerator(), duration.denominator())
+}
+
+fn meter(time: &TimeSignature) -> String {
+    match time.is_additive() {
+        true => {
+            let groups = time.groups().map(|g| g.to_string());
+            let groups = groups.collect::<Vec<_>>().join(",");
+            format!("{groups} {}/{}", time.numerator(), time.denominator())
+        }
+        false => format!("{}/{}", time.numerator(), time.denominator()),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Ly {
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            depth: 0,
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        if !line.is_empty() {
+            self.text.push_str(&"  ".repeat(self.depth));
+            self.text.push_str(line);
+        }
+        self.text.push('\n');
+    }
+
+    fn open(&mut self, line: &str) {
+        self.line(line);
+        self.depth += 1;
+    }
+
+    fn close(&mut self, line: &str) {
+        self.depth -= 1;
+        self.line(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Key, Voice, A, A_SHARP, B, C, C_SHARP, D, D_SHARP, E, EIGHTH, F, F_SHARP, G, G_SHARP,
+        OCTAVE, QUARTER, SIXTEENTH,
+    };
+
+    /// The bars of a staff, without the header of the document.
+    fn staff(bars: &[Bar], key: Option<Key>, pitches: Pitches) -> Vec<String> {
+        let document = LilyPond::new("").with_pitches(pitches);
+        let document = match key {
+            Some(key) => document.with_key(key),
+            None => document,
+        };
+        let text = document.with_staff(Clef::Treble, bars).to_lilypond();
+        text.lines()
+            .filter_map(|line| line.trim().strip_suffix(" |"))
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn pitches() {
+        let bar = Bar::new()
+            .with_note(C, 4)
+            .with_note(G, 4)
+            .with_note(A_SHARP + OCTAVE, 4)
+            .with_note(C - OCTAVE, 4);
+        let key = Some(Key::major(F));
+        assert_eq!(staff(&[bar], key, Pitches::Relative), ["c'4 g' bes c,,"]);
+        let bar = Bar::new()
+            .with_note(C, 4)
+            .with_note(G, 4)
+            .with_note(A_SHARP + OCTAVE, 4)
+            .with_note(C - OCTAVE, 4);
+        assert_eq!(staff(&[bar], key, Pitches::Absolute), ["c'4 g' bes' c"]);
+
+        let bar = Bar::new().with_note(D + OCTAVE, 4).with_note(F, 4);
+        assert_eq!(staff(&[bar], None, Pitches::Relative), ["d''4 f, s2"]);
+        assert_eq!(name(&Spelling::Flats, C_SHARP), "des");
+        assert_eq!(name(&Spelling::Flats, D_SHARP), "es");
+        assert_eq!(name(&Spelling::Flats, G_SHARP), "as");
+        assert_eq!(name(&Spelling::Sharps, F_SHARP), "fis");
+    }
+
+    #[test]
+    fn chords_and_clusters() {
+        let bar = Bar::new()
+            .with_chord(G.dom7(), 2)
+            .with_cluster(&[B, E], 4)
+            .with_note(G, 4);
+        assert_eq!(
+            staff(&[bar], None, Pitches::Relative),
+            ["<g' b d f>2 <b, e>4 g'"]
+        );
+        assert_eq!(stacked(&C.maj7()), [C, E, G, B + OCTAVE]);
+    }
+
+    #[test]
+    fn durations() {
+        let bar = Bar::new()
+            .with_note(C, QUARTER.dotted())
+            .with_note(D, EIGHTH * 5)
+            .with_silence(SIXTEENTH)
+            .with_silence(SIXTEENTH);
+        assert_eq!(
+            staff(&[bar], None, Pitches::Relative),
+            ["c'4. d2~ d8 r16 r"]
+        );
+
+        let bar = Bar::new()
+            .with_note(E, EIGHTH.triplet())
+            .with_note(D, EIGHTH.triplet())
+            .with_note(C, EIGHTH.triplet())
+            .with_note(B, QUARTER.triplet())
+            .with_note(A, EIGHTH.triplet());
+        assert_eq!(
+            staff(&[bar], None, Pitches::Relative),
+            ["\\tuplet 3/2 { e'8 d c b4 a8 } s2"]
+        );
+        assert_eq!(lengths(Duration::new(1, 9)), ["1*1/9"]);
+        assert_eq!(length(SIXTEENTH.double_dotted()), "16..");
+    }
+
+    #[test]
+    fn voices_and_times() {
+        let bars = [
+            Bar::new()
+                .with_time_signature(TimeSignature::new(3, 4))
+                .with_note(E, 2)
+                .with_note(D, 4)
+                .with_voice(Voice::new().with_note(C, 2).with_note(B, 4)),
+            Bar::new()
+                .with_time_signature(TimeSignature::additive(&[2, 2, 3], 8))
+                .with_note(C, QUARTER.double_dotted() * 2),
+        ];
+        let text = LilyPond::new("")
+            .with_staff(Clef::Bass, &bars)
+            .to_lilypond();
+        assert!(text.contains("\\clef bass\n"));
+        assert!(text.contains("\\time 3/4\n"));
+        assert!(text.contains("<< { e'2 d4 } \\\\ { c2 b4 } >> |"));
+        assert!(text.contains("\\time 2,2,3 7/8\n"));
+        assert!(text.contains("c2.. |"));
+    }
+
+    #[test]
+    fn chord_names() {
+        let bars = [
+            Bar::new().with_chord(D.min9(), 2).with_chord(G.dom13(), 2),
+            Bar::new().with_chord(A.dom13b9b13(), 2).with_silence(2),
+            Bar::new()
+                .with_chord(B.min7b5(), EIGHTH * 5)
+                .with_chord(A_SHARP.maj(), EIGHTH * 3),
+        ];
+        let text = LilyPond::new("")
+            .with_key(Key::major(F))
+            .with_chords(&bars)
+            .to_lilypond();
+        assert!(text.contains("\\new ChordNames \\chordmode {\n"));
+        assert!(text.contains("d2:m9 g:13 |"));
+        assert!(text.contains("a:7.9-.13- r |"));
+        assert!(text.contains("b1*5/8:m7.5- bes4. |"));
+        assert_eq!(modifier(&C.dom7s5()), "7.5+");
+    }
+
+    #[test]
+    fn document() {
+        let rh = [Bar::new().with_note(E, 2).with_note(G, 2)];
+        let lh = [Bar::new().with_chord(C.maj7(), 1)];
+        let text = LilyPond::new("\"Blue\" Exercise")
+            .with_key(Key::minor(D))
+            .with_piano(&rh, &lh)
+            .to_lilypond();
+
+        assert!(text.starts_with("\\version \"2.24.0\"\n"));
+        assert!(text.contains("title = \"\\\"Blue\\\" Exercise\""));
+        assert!(text.contains("\\new PianoStaff <<\n"));
+        assert_eq!(text.matches("\\new Staff \\relative {").count(), 2);
+        assert_eq!(text.matches("\\key d \\minor").count(), 2);
+        assert!(text.contains("<c' e g b>1 |"));
+        assert!(!text.contains("ChordNames"));
+    }
+}
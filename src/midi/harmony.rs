@@ -0,0 +1,144 @@
+use super::Midi;
+use crate::{
+    chords::{recognize, Chord, Chords},
+    timeline::Timeline,
+    Bar, BarElement, Duration, Note,
+};
+
+/// The span of time a chord is recognized on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Segment {
+    /// A chord per beat, the same chords on following beats being merged.
+    #[default]
+    Beat,
+    /// A chord per bar.
+    Bar,
+}
+
+impl Midi {
+    /// Recognizes the chords played by all the tracks (but the drums, on the channel 9), on
+    /// each beat or on each bar of the first track. A segment without any chord is a
+    /// silence.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{midi::*, *};
+    ///
+    /// let piano = Track::new("Piano")
+    ///     .with_bar(Bar::new().with_cluster(&[D, F, A, C + OCTAVE], 1))
+    ///     .with_bar(Bar::new().with_cluster(&[G - OCTAVE, B, F], 1));
+    /// let bass = Track::new("Bass")
+    ///     .with_channel(1)
+    ///     .with_notes(&[D - OCTAVE, G - OCTAVE], 1);
+    /// let midi = Midi::new().with_track(piano).with_track(bass);
+    ///
+    /// let bars = midi.harmony(Segment::Bar);
+    /// assert_eq!(Chart::new().render(&bars), "| Dm7 | G7 |");
+    /// ```
+    pub fn harmony(&self, segment: Segment) -> Vec<Bar> {
+        let notes = self
+            .tracks
+            .iter()
+            .filter(|t| t.channel() != 9)
+            .flat_map(|track| {
+                let timeline = Timeline::new(track.bars(), &self.tempo);
+                timeline
+                    .events()
+                    .map(|e| (e.start(), e.start() + e.duration(), e.element().notes()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut bar_start = Duration::new(0, 1);
+        let layout = self.tracks.first().into_iter().flat_map(|t| t.bars());
+        layout
+            .map(|bar| {
+                let time_signature = bar.time_signature();
+                let spans = match segment {
+                    Segment::Beat => time_signature.beats(),
+                    Segment::Bar => vec![time_signature.duration()],
+                };
+
+                let mut elements: Vec<BarElement> = vec![];
+                let mut start = bar_start;
+                for span in spans {
+                    let end = start + span;
+                    let sounding = notes
+                        .iter()
+                        .filter(|(s, e, _)| *s < end && *e > start)
+                        .flat_map(|(_, _, notes)| notes.iter().copied())
+                        .collect::<Vec<Note>>();
+                    let element = match recognize(&sounding) {
+                        Some(chord) => BarElement::Chord(chord, span),
+                        None => BarElement::Silence(span),
+                    };
+                    merge(&mut elements, element);
+                    start = end;
+                }
+                bar_start = start;
+
+                elements.into_iter().fold(
+                    Bar::new().with_time_signature(time_signature.clone()),
+                    Bar::with_element,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Adds the element, or lengthens the last element if it is the same chord or a silence.
+fn merge(elements: &mut Vec<BarElement>, element: BarElement) {
+    let merged = match (elements.pop(), element) {
+        (Some(BarElement::Silence(a)), BarElement::Silence(b)) => vec![BarElement::Silence(a + b)],
+        (Some(BarElement::Chord(a, d1)), BarElement::Chord(b, d2)) if same(&a, &b) => {
+            vec![BarElement::Chord(a, d1 + d2)]
+        }
+        (Some(last), element) => vec![last, element],
+        (None, element) => vec![element],
+    };
+    elements.extend(merged);
+}
+
+fn same(a: &Chords, b: &Chords) -> bool {
+    a.root() == b.root() && a.suffix() == b.suffix()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{midi::Track, Chart, A, A_SHARP, C, D, E, F, G, OCTAVE};
+
+    #[test]
+    fn beats() {
+        let piano = Track::new("Piano")
+            .with_chord(C.maj7(), 2)
+            .with_chord(A.min7(), 4)
+            .with_chord(A.min7(), 4)
+            .with_silence(1)
+            .with_cluster(&[D, F, A], 1);
+        let midi = Midi::new().with_track(piano);
+
+        let bars = midi.harmony(Segment::Beat);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[0].to_string(), "Cmaj7 Am7");
+        assert_eq!(bars[0].duration(), crate::WHOLE);
+        assert_eq!(bars[1].to_string(), "_");
+        assert_eq!(bars[2].to_string(), "Dm");
+    }
+
+    #[test]
+    fn drums() {
+        let piano = Track::new("Piano").with_notes(&[C, E], 2);
+        // Played with the piano, the Bb of the drums would make a C7.
+        let drums = Track::new("Drums")
+            .with_channel(9)
+            .with_notes(&[A_SHARP, A_SHARP], 2);
+        let bass = Track::new("Bass").with_channel(1).with_note(G - OCTAVE, 1);
+
+        let midi = Midi::new().with_track(piano).with_track(drums);
+        assert_eq!(Chart::new().render(&midi.harmony(Segment::Bar)), "| C |");
+
+        let midi = midi.with_track(bass);
+        assert_eq!(Chart::new().render(&midi.harmony(Segment::Bar)), "| C |");
+    }
+}
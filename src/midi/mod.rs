@@ -0,0 +1,271 @@
+//! Standard MIDI Files.
+//!
+//! A [`Midi`] file holds [`Track`]s of bars, played with the tempo map, the time signatures
+//! of the bars of the first track and an optional key signature. The file is written in the
+//! format 1 (a conductor track, then a track per part) or in the format 0 (a single track).
+//!
+//! # Example
+//! ```
+//! use musika_rs::{midi::*, timeline::TempoMap, *};
+//!
+//! let piano = Track::new("Piano")
+//!     .with_program(0)
+//!     .with_chord(D.min7(), 1)
+//!     .with_chord(G.dom7(), 1)
+//!     .with_chord(C.maj7(), 1);
+//! let midi = Midi::new()
+//!     .with_tempo(TempoMap::new(100.0))
+//!     .with_key(Key::major(C))
+//!     .with_track(piano);
+//!
+//! let bytes = midi.to_bytes();
+//! assert_eq!(&bytes[..4], b"MThd");
+//! ```
+
+mod harmony;
+mod read;
+mod write;
+
+pub use harmony::*;
+pub use read::MidiError;
+
+use crate::{
+    chords::Chords, groove::Groove, timeline::TempoMap, Bar, BarElement, Duration, Key, Note,
+    TimeSignature,
+};
+
+/// The layout of the tracks of a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// A single track with all the parts (format 0).
+    Single,
+    /// A conductor track with the tempo and the signatures, then a track per part
+    /// (format 1).
+    #[default]
+    Multiple,
+}
+
+/// A part of a MIDI file: a sequence of bars played by an instrument on a channel.
+pub struct Track {
+    name: String,
+    channel: u8,
+    program: u8,
+    groove: Groove,
+    time_signature: TimeSignature,
+    bars: Vec<Bar>,
+}
+
+/// A Standard MIDI File.
+pub struct Midi {
+    format: Format,
+    ticks_per_quarter: u16,
+    tempo: TempoMap,
+    key: Option<Key>,
+    tracks: Vec<Track>,
+}
+
+impl Track {
+    /// An empty track, played by a piano (program 0) on the first channel.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            channel: 0,
+            program: 0,
+            groove: Groove::new(),
+            time_signature: TimeSignature::common(),
+            bars: vec![],
+        }
+    }
+
+    /// Sets the channel of the track, from 0 to 15 (9 is the channel of the drums).
+    pub fn with_channel(self, channel: u8) -> Self {
+        assert!(channel < 16, "Track: the channel {channel} is not in 0..16");
+        Self { channel, ..self }
+    }
+
+    /// Sets the General MIDI program of the track, from 0 to 127.
+    pub fn with_program(self, program: u8) -> Self {
+        assert!(
+            program < 128,
+            "Track: the program {program} is not in 0..128"
+        );
+        Self { program, ..self }
+    }
+
+    /// Sets the groove the bars are played with (the velocities, the swing...).
+    pub fn with_groove(self, groove: Groove) -> Self {
+        Self { groove, ..self }
+    }
+
+    /// Sets the time signature of the bars created by the elements added to the track.
+    pub fn with_time_signature(self, time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            ..self
+        }
+    }
+
+    pub fn with_bar(self, bar: Bar) -> Self {
+        let mut bars = self.bars;
+        bars.push(bar);
+        Self { bars, ..self }
+    }
+
+    pub fn with_bars(self, bars: impl IntoIterator<Item = Bar>) -> Self {
+        bars.into_iter()
+            .fold(self, |track, bar| track.with_bar(bar))
+    }
+
+    pub fn with_note(self, note: Note, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Note(note, duration.into()))
+    }
+
+    pub fn with_chord(self, chord: Chords, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Chord(chord, duration.into()))
+    }
+
+    pub fn with_cluster(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Cluster(notes.to_vec(), duration.into()))
+    }
+
+    pub fn with_notes(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        let duration = duration.into();
+        notes
+            .iter()
+            .fold(self, |track, note| track.with_note(*note, duration))
+    }
+
+    pub fn with_silence(self, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Silence(duration.into()))
+    }
+
+    /// Adds an element to the last bar, or to a new bar if it does not fit in the last one.
+    pub fn with_element(self, element: BarElement) -> Self {
+        let mut bars = self.bars;
+        let bar = match bars.pop() {
+            Some(bar)
+                if bar.time_signature() == &self.time_signature
+                    && bar.duration() + element.duration() <= self.time_signature.duration() =>
+            {
+                bar
+            }
+            Some(bar) => {
+                bars.push(bar);
+                Bar::new().with_time_signature(self.time_signature.clone())
+            }
+            None => Bar::new().with_time_signature(self.time_signature.clone()),
+        };
+        bars.push(bar.with_element(element));
+        Self { bars, ..self }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    pub fn program(&self) -> u8 {
+        self.program
+    }
+
+    pub fn bars(&self) -> impl Iterator<Item = &Bar> {
+        self.bars.iter()
+    }
+}
+
+impl Midi {
+    /// An empty file in the format 1, with 480 ticks per quarter note and a tempo of 120.
+    pub fn new() -> Self {
+        Self {
+            format: Format::Multiple,
+            ticks_per_quarter: 480,
+            tempo: TempoMap::default(),
+            key: None,
+            tracks: vec![],
+        }
+    }
+
+    pub fn with_format(self, format: Format) -> Self {
+        Self { format, ..self }
+    }
+
+    pub fn with_ticks_per_quarter(self, ticks_per_quarter: u16) -> Self {
+        assert!(
+            ticks_per_quarter > 0 && ticks_per_quarter < 0x8000,
+            "Midi: the ticks per quarter {ticks_per_quarter} are not in 1..32768"
+        );
+        Self {
+            ticks_per_quarter,
+            ..self
+        }
+    }
+
+    pub fn with_tempo(self, tempo: TempoMap) -> Self {
+        Self { tempo, ..self }
+    }
+
+    /// Sets the key signature of the file.
+    pub fn with_key(self, key: Key) -> Self {
+        Self {
+            key: Some(key),
+            ..self
+        }
+    }
+
+    pub fn with_track(self, track: Track) -> Self {
+        let mut tracks = self.tracks;
+        tracks.push(track);
+        Self { tracks, ..self }
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn ticks_per_quarter(&self) -> u16 {
+        self.ticks_per_quarter
+    }
+
+    pub fn tempo(&self) -> &TempoMap {
+        &self.tempo
+    }
+
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    pub fn tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter()
+    }
+
+    /// Reads the content of a `.mid` file (format 0 or 1), the notes being quantized to the
+    /// grid (eg. a sixteenth note). Each channel of each track becomes a track, and the notes
+    /// which overlap are laid in several voices.
+    pub fn from_bytes(bytes: &[u8], grid: Duration) -> Result<Self, MidiError> {
+        read::file(bytes, grid)
+    }
+
+    /// Reads a `.mid` file, the notes being quantized to the grid.
+    pub fn read(path: impl AsRef<std::path::Path>, grid: Duration) -> Result<Self, MidiError> {
+        Self::from_bytes(&std::fs::read(path)?, grid)
+    }
+
+    /// The content of the `.mid` file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        write::file(self)
+    }
+
+    /// Writes the `.mid` file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+}
+
+impl Default for Midi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
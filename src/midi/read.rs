@@ -0,0 +1,550 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use super::{Format, Midi, Track};
+use crate::{timeline::TempoMap, Bar, BarElement, Duration, Key, Note, TimeSignature, Voice};
+
+/// The errors of reading a MIDI file.
+#[derive(Debug)]
+pub enum MidiError {
+    Io(std::io::Error),
+    /// The data does not start with a MIDI header.
+    NotMidi,
+    /// The data ends in the middle of a chunk or of an event.
+    Truncated,
+    /// The format 2 (independent sequences) is not supported.
+    UnsupportedFormat(u16),
+    /// The time division in SMPTE frames is not supported.
+    Timecode,
+    /// A data byte without any previous status byte.
+    RunningStatus,
+    /// A data byte of an event with its high bit set, like a status byte.
+    InvalidData(u8),
+    /// The notes last longer than 10 000 bars.
+    TooLong,
+}
+
+/// The most bars read from a file.
+const LONGEST: usize = 10_000;
+
+/// The messages of a track chunk which matter for the import.
+#[derive(Default)]
+struct Chunk {
+    name: Option<String>,
+    programs: HashMap<u8, u8>,
+    notes: Vec<Played>,
+}
+
+/// A note, from its note on to its note off.
+struct Played {
+    channel: u8,
+    key: u8,
+    start: u64,
+    end: u64,
+}
+
+/// The meta events shared by all the tracks.
+#[derive(Default)]
+struct Conductor {
+    tempos: Vec<(u64, u32)>,
+    time_signatures: Vec<(u64, TimeSignature)>,
+    key: Option<Key>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+/// Reads a Standard MIDI File, the notes being quantized to the grid.
+pub(super) fn file(data: &[u8], grid: Duration) -> Result<Midi, MidiError> {
+    assert!(!grid.is_zero(), "Midi: the grid is zero");
+
+    let mut reader = Reader::new(data);
+    if reader.bytes(4).ok() != Some(b"MThd") {
+        return Err(MidiError::NotMidi);
+    }
+    let length = reader.u32()? as usize;
+    let format = match reader.u16()? {
+        0 => Format::Single,
+        1 => Format::Multiple,
+        format => return Err(MidiError::UnsupportedFormat(format)),
+    };
+    let count = reader.u16()?;
+    let division = reader.u16()?;
+    if division & 0x8000 != 0 {
+        return Err(MidiError::Timecode);
+    }
+    reader.bytes(length.saturating_sub(6))?;
+
+    let mut conductor = Conductor::default();
+    let mut chunks = vec![];
+    for _ in 0..count {
+        let kind = reader.bytes(4)?;
+        let length = reader.u32()? as usize;
+        let data = reader.bytes(length)?;
+        if kind == b"MTrk" {
+            chunks.push(chunk(data, &mut conductor)?);
+        }
+    }
+
+    let ticks_per_quarter = division.max(1);
+    let whole = ticks_per_quarter as u32 * 4;
+    let grid_ticks = grid.as_f64() * whole as f64;
+    let quantize = |tick: u64| grid * (tick as f64 / grid_ticks).round() as u32;
+
+    // Checked before quantizing the ticks: even bars of 16 whole notes would be too many.
+    let last = chunks
+        .iter()
+        .flat_map(|c| c.notes.iter())
+        .map(|n| n.end)
+        .max();
+    if last.is_some_and(|last| last > whole as u64 * LONGEST as u64 * 16) {
+        return Err(MidiError::TooLong);
+    }
+
+    // The bars, from the time signatures, until the end of the last note.
+    let end = chunks
+        .iter()
+        .flat_map(|c| c.notes.iter())
+        .map(|n| quantize(n.end).max(quantize(n.start) + grid))
+        .max();
+    let changes = conductor
+        .time_signatures
+        .iter()
+        .map(|(tick, time_signature)| (Duration::new(*tick as u32, whole), time_signature))
+        .collect::<Vec<_>>();
+    let mut layout = vec![];
+    let mut start = Duration::new(0, 1);
+    while end.is_some_and(|end| start < end) {
+        if layout.len() == LONGEST {
+            return Err(MidiError::TooLong);
+        }
+        let time_signature = changes
+            .iter()
+            .rfind(|(at, _)| *at <= start)
+            .map_or(TimeSignature::common(), |(_, t)| (*t).clone());
+        let next = start + time_signature.duration();
+        layout.push((start, time_signature));
+        start = next;
+    }
+
+    let mut tempo = TempoMap::new(120.0);
+    for (tick, micros) in &conductor.tempos {
+        let bpm = 60_000_000.0 / (*micros).max(1) as f64;
+        tempo = tempo.with_tempo(Duration::new(*tick as u32, whole), bpm);
+    }
+
+    let mut midi = Midi::new()
+        .with_format(format)
+        .with_ticks_per_quarter(ticks_per_quarter.min(0x7FFF))
+        .with_tempo(tempo);
+    if let Some(key) = conductor.key {
+        midi = midi.with_key(key);
+    }
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let mut channels = chunk.notes.iter().map(|n| n.channel).collect::<Vec<_>>();
+        channels.sort();
+        channels.dedup();
+
+        for channel in channels {
+            let notes = chunk
+                .notes
+                .iter()
+                .filter(|n| n.channel == channel)
+                .map(|n| {
+                    let start = quantize(n.start);
+                    let end = quantize(n.end).max(start + grid);
+                    (start, end, Note::from_midi(n.key))
+                });
+            let name = chunk
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Track {}", index + 1));
+            let track = Track::new(&name)
+                .with_channel(channel)
+                .with_program(*chunk.programs.get(&channel).unwrap_or(&0))
+                .with_bars(bars(notes, &layout));
+            midi = midi.with_track(track);
+        }
+    }
+
+    Ok(midi)
+}
+
+/// Reads the messages of a track chunk.
+fn chunk(data: &[u8], conductor: &mut Conductor) -> Result<Chunk, MidiError> {
+    let mut reader = Reader::new(data);
+    let mut chunk = Chunk::default();
+    let mut open: HashMap<(u8, u8), Vec<u64>> = HashMap::new();
+    let mut running: Option<u8> = None;
+    let mut tick = 0;
+
+    while !reader.is_empty() {
+        tick += reader.variable()?;
+        let first = reader.byte()?;
+
+        match first {
+            0xFF => {
+                running = None;
+                let kind = reader.byte()?;
+                let length = reader.variable()? as usize;
+                let data = reader.bytes(length)?;
+                match (kind, data) {
+                    (0x03, name) => {
+                        let name = String::from_utf8_lossy(name).to_string();
+                        chunk.name.get_or_insert(name);
+                    }
+                    (0x51, [a, b, c]) => {
+                        let micros = u32::from_be_bytes([0, *a, *b, *c]);
+                        conductor.tempos.push((tick, micros));
+                    }
+                    (0x58, [numerator, denominator, ..]) if *numerator > 0 && *denominator < 7 => {
+                        let time_signature = TimeSignature::new(*numerator, 1 << denominator);
+                        conductor.time_signatures.push((tick, time_signature));
+                    }
+                    (0x59, [signature, mode]) => {
+                        let fifths = (*signature as i8 as i16 * 7).rem_euclid(12) as i8;
+                        let key = Key::major(Note::from(fifths));
+                        conductor.key.get_or_insert(match mode {
+                            0 => key,
+                            _ => key.relative(),
+                        });
+                    }
+                    (0x2F, _) => break,
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                running = None;
+                let length = reader.variable()? as usize;
+                reader.bytes(length)?;
+            }
+            _ => {
+                // Without a status byte, the previous status is used (the running status).
+                let (status, data) = match first & 0x80 {
+                    0 => (running.ok_or(MidiError::RunningStatus)?, first),
+                    _ => (first, reader.data()?),
+                };
+                running = Some(status);
+
+                let channel = status & 0x0F;
+                match status & 0xF0 {
+                    0x90 | 0x80 => {
+                        let velocity = reader.data()?;
+                        let notes = open.entry((channel, data)).or_default();
+                        if status & 0xF0 == 0x90 && velocity > 0 {
+                            notes.push(tick);
+                        } else if !notes.is_empty() {
+                            chunk.notes.push(Played {
+                                channel,
+                                key: data,
+                                start: notes.remove(0),
+                                end: tick,
+                            });
+                        }
+                    }
+                    0xC0 => {
+                        chunk.programs.entry(channel).or_insert(data);
+                    }
+                    0xD0 => {}
+                    _ => {
+                        reader.data()?;
+                    }
+                }
+            }
+        }
+    }
+
+    // The notes still sounding at the end of the track.
+    for ((channel, key), starts) in open {
+        chunk.notes.extend(starts.into_iter().map(|start| Played {
+            channel,
+            key,
+            start,
+            end: tick,
+        }));
+    }
+    chunk.notes.sort_by_key(|n| (n.start, n.key));
+
+    Ok(chunk)
+}
+
+/// Lays the notes (start, end, note) in the bars, with as many voices as the overlapping
+/// notes need. The notes starting together and ending together make a cluster, and the
+/// notes going over a bar line are cut.
+fn bars(
+    notes: impl Iterator<Item = (Duration, Duration, Note)>,
+    layout: &[(Duration, TimeSignature)],
+) -> Vec<Bar> {
+    let mut chords: BTreeMap<(Duration, Duration), Vec<Note>> = BTreeMap::new();
+    for (start, end, note) in notes {
+        chords.entry((start, end)).or_default().push(note);
+    }
+
+    let mut voices: Vec<Vec<(Duration, Duration, Vec<Note>)>> = vec![];
+    for ((start, end), mut notes) in chords {
+        notes.sort();
+        notes.dedup();
+        match voices
+            .iter_mut()
+            .find(|v| v.last().is_some_and(|(_, e, _)| *e <= start))
+        {
+            Some(voice) => voice.push((start, end, notes)),
+            None => voices.push(vec![(start, end, notes)]),
+        }
+    }
+
+    layout
+        .iter()
+        .map(|(bar_start, time_signature)| {
+            let bar_end = *bar_start + time_signature.duration();
+            let mut bar = Bar::new().with_time_signature(time_signature.clone());
+
+            for (index, voice) in voices.iter().enumerate() {
+                let mut elements = vec![];
+                let mut cursor = *bar_start;
+                for (start, end, notes) in voice {
+                    if *end <= *bar_start || *start >= bar_end {
+                        continue;
+                    }
+                    let (start, end) = ((*start).max(*bar_start), (*end).min(bar_end));
+                    if start > cursor {
+                        elements.extend(silences(start - cursor));
+                    }
+                    elements.push(match &notes[..] {
+                        [note] => BarElement::Note(*note, end - start),
+                        notes => BarElement::Cluster(notes.to_vec(), end - start),
+                    });
+                    cursor = end;
+                }
+
+                if elements.is_empty() && index > 0 {
+                    continue;
+                }
+                elements.extend(silences(bar_end - cursor));
+
+                bar = match index {
+                    0 => elements.into_iter().fold(bar, Bar::with_element),
+                    _ => {
+                        bar.with_voice(elements.into_iter().fold(Voice::new(), Voice::with_element))
+                    }
+                };
+            }
+            bar
+        })
+        .collect()
+}
+
+fn silences(duration: Duration) -> impl Iterator<Item = BarElement> {
+    let parts = match duration.is_zero() {
+        true => vec![],
+        false => duration.split(),
+    };
+    parts.into_iter().map(BarElement::Silence)
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], MidiError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or(MidiError::Truncated)?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, MidiError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// A data byte of an event, from 0 to 127.
+    fn data(&mut self) -> Result<u8, MidiError> {
+        match self.byte()? {
+            byte if byte & 0x80 != 0 => Err(MidiError::InvalidData(byte)),
+            byte => Ok(byte),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, MidiError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A variable-length quantity, of up to 4 bytes.
+    fn variable(&mut self) -> Result<u64, MidiError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = value << 7 | (byte & 0x7F) as u64;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl Display for MidiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidiError::Io(e) => write!(f, "{e}"),
+            MidiError::NotMidi => write!(f, "not a MIDI file"),
+            MidiError::Truncated => write!(f, "the MIDI file is truncated"),
+            MidiError::UnsupportedFormat(format) => {
+                write!(f, "the MIDI format {format} is not supported")
+            }
+            MidiError::Timecode => write!(f, "the SMPTE time division is not supported"),
+            MidiError::RunningStatus => write!(f, "a MIDI event has no status"),
+            MidiError::InvalidData(byte) => write!(f, "invalid MIDI data byte {byte:#04X}"),
+            MidiError::TooLong => write!(f, "the MIDI file is longer than {LONGEST} bars"),
+        }
+    }
+}
+
+impl std::error::Error for MidiError {}
+
+impl From<std::io::Error> for MidiError {
+    fn from(e: std::io::Error) -> Self {
+        MidiError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mode, C, D, E, EIGHTH, G, SIXTEENTH};
+
+    /// A format 0 file with a single track chunk.
+    fn single(events: &[u8]) -> Vec<u8> {
+        let mut bytes = b"MThd\0\0\0\x06\0\0\0\x01\x01\xE0MTrk".to_vec();
+        bytes.extend((events.len() as u32).to_be_bytes());
+        bytes.extend(events);
+        bytes
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(file(b"RIFF", EIGHTH), Err(MidiError::NotMidi)));
+        let header = b"MThd\0\0\0\x06\0\x02\0\x01\x01\xE0";
+        assert!(matches!(
+            file(header, EIGHTH),
+            Err(MidiError::UnsupportedFormat(2))
+        ));
+        let header = b"MThd\0\0\0\x06\0\x01\0\x01\x01\xE0";
+        assert!(matches!(file(header, EIGHTH), Err(MidiError::Truncated)));
+        let header = b"MThd\0\0\0\x06\0\x01\0\x01\xE7\x28";
+        assert!(matches!(file(header, EIGHTH), Err(MidiError::Timecode)));
+        let bytes = single(&[0x00, 0x3C, 0x40]);
+        assert!(matches!(
+            file(&bytes, EIGHTH),
+            Err(MidiError::RunningStatus)
+        ));
+        // A program change to 200.
+        let bytes = single(&[0x00, 0xC0, 0xC8, 0x00, 0xFF, 0x2F, 0x00]);
+        let error = file(&bytes, EIGHTH).err().unwrap();
+        assert!(matches!(error, MidiError::InvalidData(0xC8)));
+        assert_eq!(error.to_string(), "invalid MIDI data byte 0xC8");
+        // A note held during 2^28 ticks (about 140 000 bars), then during twice as long.
+        let bytes = single(&[
+            0x00, 0x90, 0x3C, 0x40, 0xFF, 0xFF, 0xFF, 0x7F, 0x80, 0x3C, 0x00, 0x00, 0xFF, 0x2F,
+            0x00,
+        ]);
+        assert!(matches!(file(&bytes, EIGHTH), Err(MidiError::TooLong)));
+        let bytes = single(&[
+            0x00, 0x90, 0x3C, 0x40, 0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x00, 0xFF, 0xFF, 0xFF,
+            0x7F, 0x80, 0x3C, 0x00, 0x00, 0xFF, 0x2F, 0x00,
+        ]);
+        assert!(matches!(file(&bytes, EIGHTH), Err(MidiError::TooLong)));
+    }
+
+    #[test]
+    fn running_status() {
+        // C4 and E4 on, with the running status, then off with velocity 0.
+        let bytes = single(&[
+            0x00, 0x90, 0x3C, 0x50, 0x00, 0x40, 0x50, 0x83, 0x60, 0x3C, 0x00, 0x00, 0x40, 0x00,
+            0x00, 0xFF, 0x2F, 0x00,
+        ]);
+        let midi = file(&bytes, SIXTEENTH).unwrap();
+        assert_eq!(midi.format(), Format::Single);
+        let track = midi.tracks().next().unwrap();
+        let bar = track.bars().next().unwrap();
+        assert_eq!(bar.to_string(), "<C E> _");
+    }
+
+    #[test]
+    fn meta_events() {
+        let bytes = single(&[
+            0x00, 0xFF, 0x03, 0x04, b'B', b'a', b's', b's', // name
+            0x00, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // 60 bpm
+            0x00, 0xFF, 0x58, 0x04, 0x03, 0x02, 0x18, 0x08, // 3/4
+            0x00, 0xFF, 0x59, 0x02, 0xFD, 0x01, // C minor
+            0x00, 0xC3, 0x20, // program 32, channel 3
+            0x00, 0x93, 0x30, 0x60, 0x8B, 0x20, 0x83, 0x30, 0x40, // C3, a dotted half
+            0x00, 0xFF, 0x2F, 0x00,
+        ]);
+        let midi = file(&bytes, SIXTEENTH).unwrap();
+        assert_eq!(midi.tempo().bpm_at(Duration::new(0, 1)), 60.0);
+        assert_eq!(
+            midi.key().map(|k| (k.tonic(), k.mode())),
+            Some((C, Mode::Minor))
+        );
+
+        let track = midi.tracks().next().unwrap();
+        assert_eq!(track.name(), "Bass");
+        assert_eq!((track.channel(), track.program()), (3, 32));
+        let bar = track.bars().next().unwrap();
+        assert_eq!(bar.time_signature(), &TimeSignature::new(3, 4));
+        assert!(bar.is_complete());
+    }
+
+    #[test]
+    fn round_trip() {
+        let track = Track::new("Piano")
+            .with_note(C, 8)
+            .with_note(D, 8)
+            .with_note(E, 4)
+            .with_chord(C.maj(), 2)
+            .with_bar(
+                Bar::new()
+                    .with_note(G, 2)
+                    .with_silence(2)
+                    .with_voice(Voice::new().with_note(C, 1)),
+            );
+        let bytes = Midi::new().with_track(track).to_bytes();
+
+        let midi = file(&bytes, SIXTEENTH).unwrap();
+        let bars = midi.tracks().next().unwrap().bars().collect::<Vec<_>>();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].to_string(), "C D E <C E G>");
+        assert_eq!(bars[1].to_string(), "G _ / C");
+    }
+
+    #[test]
+    fn quantization() {
+        // A C4 late by 10 ticks and 470 ticks long, then a D4 early by 20 ticks.
+        let bytes = single(&[
+            0x0A, 0x90, 0x3C, 0x50, 0x83, 0x56, 0x80, 0x3C, 0x00, 0x83, 0x4C, 0x90, 0x3E, 0x50,
+            0x83, 0x60, 0x80, 0x3E, 0x00, 0x00, 0xFF, 0x2F, 0x00,
+        ]);
+        let midi = file(&bytes, EIGHTH).unwrap();
+        let bar = midi.tracks().next().unwrap().bars().next().unwrap();
+        assert_eq!(bar.to_string(), "C _ D _");
+    }
+}
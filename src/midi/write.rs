@@ -0,0 +1,291 @@
+use crate::{timeline::Timeline, Duration, Mode, SIXTEENTH};
+
+use super::{Format, Midi, Track};
+
+/// A message of a track, at a given tick.
+struct Event {
+    tick: u64,
+    /// The order of the messages at the same tick: the meta events, the program changes,
+    /// the notes off then the notes on.
+    order: u8,
+    bytes: Vec<u8>,
+}
+
+/// The bytes of a Standard MIDI File.
+pub(super) fn file(midi: &Midi) -> Vec<u8> {
+    let conductor = conductor(midi);
+    let parts = midi.tracks.iter().map(|track| part(midi, track));
+
+    let chunks = match midi.format {
+        Format::Single => {
+            let events = conductor.into_iter().chain(parts.flatten()).collect();
+            vec![chunk(events)]
+        }
+        Format::Multiple => std::iter::once(conductor)
+            .chain(parts)
+            .map(chunk)
+            .collect::<Vec<_>>(),
+    };
+
+    let format: u16 = match midi.format {
+        Format::Single => 0,
+        Format::Multiple => 1,
+    };
+
+    let mut bytes = b"MThd".to_vec();
+    bytes.extend(6u32.to_be_bytes());
+    bytes.extend(format.to_be_bytes());
+    bytes.extend((chunks.len() as u16).to_be_bytes());
+    bytes.extend(midi.ticks_per_quarter.to_be_bytes());
+    for chunk in chunks {
+        bytes.extend(chunk);
+    }
+    bytes
+}
+
+/// The tempo, the time signatures (of the first track) and the key signature.
+fn conductor(midi: &Midi) -> Vec<Event> {
+    let mut events = tempos(midi);
+
+    let mut start = Duration::new(0, 1);
+    let mut previous = None;
+    for bar in midi.tracks.first().into_iter().flat_map(|t| t.bars()) {
+        let time_signature = bar.time_signature();
+        if previous != Some(time_signature) {
+            // The MIDI clocks (24 per quarter note) of a beat.
+            let beat = time_signature.beats()[0];
+            let clocks = beat.numerator() * 96 / beat.denominator();
+            let data = [
+                time_signature.numerator(),
+                time_signature.denominator().trailing_zeros() as u8,
+                clocks.min(255) as u8,
+                8,
+            ];
+            events.push(meta(tick(midi, start), 0x58, &data));
+        }
+        previous = Some(time_signature);
+        start += time_signature.duration();
+    }
+
+    if let Some(key) = midi.key {
+        let mode = match key.mode() {
+            Mode::Major => 0,
+            Mode::Minor => 1,
+        };
+        events.push(meta(0, 0x59, &[key.signature() as u8, mode]));
+    }
+
+    events
+}
+
+/// The tempo changes, the ramps being played in steps of a sixteenth note.
+fn tempos(midi: &Midi) -> Vec<Event> {
+    let tempo = &midi.tempo;
+    let points = tempo.points().collect::<Vec<_>>();
+
+    let mut changes: Vec<(Duration, f64)> = vec![];
+    let mut change = |at: Duration, bpm: f64| match changes.last_mut() {
+        Some(last) if last.0 == at => last.1 = bpm,
+        _ => changes.push((at, bpm)),
+    };
+
+    for (index, point) in points.iter().enumerate() {
+        if point.is_ramp() && index > 0 {
+            let mut at = points[index - 1].at();
+            while at < point.at() {
+                let middle = (at + SIXTEENTH / 2).min(point.at());
+                change(at, tempo.bpm_at(middle));
+                at += SIXTEENTH;
+            }
+        }
+        change(point.at(), point.bpm());
+    }
+
+    changes
+        .into_iter()
+        .map(|(at, bpm)| {
+            let quarters_per_minute = bpm * tempo.unit().as_f64() * 4.0;
+            let micros = (60_000_000.0 / quarters_per_minute).round() as u32;
+            let micros = micros.clamp(1, 0xFF_FFFF).to_be_bytes();
+            meta(tick(midi, at), 0x51, &micros[1..])
+        })
+        .collect()
+}
+
+/// The name, the program and the notes of a track.
+fn part(midi: &Midi, track: &Track) -> Vec<Event> {
+    let channel = track.channel;
+    let mut events = vec![Event {
+        tick: 0,
+        order: 1,
+        bytes: vec![0xC0 | channel, track.program],
+    }];
+    if midi.format == Format::Multiple {
+        events.push(meta(0, 0x03, track.name.as_bytes()));
+    }
+
+    let timeline = Timeline::new(&track.bars, &midi.tempo);
+    for played in track.groove.apply(&timeline) {
+        if played.velocity() == 0 {
+            continue;
+        }
+
+        let start = seconds_tick(midi, played.start());
+        let end = seconds_tick(midi, played.start() + played.duration()).max(start + 1);
+        for note in played.element().notes() {
+            let key = note.midi();
+            events.push(Event {
+                tick: start,
+                order: 3,
+                bytes: vec![0x90 | channel, key, played.velocity()],
+            });
+            events.push(Event {
+                tick: end,
+                order: 2,
+                bytes: vec![0x80 | channel, key, 0],
+            });
+        }
+    }
+    events
+}
+
+fn meta(tick: u64, kind: u8, data: &[u8]) -> Event {
+    let mut bytes = vec![0xFF, kind];
+    bytes.extend(variable(data.len() as u64));
+    bytes.extend(data);
+    Event {
+        tick,
+        order: 0,
+        bytes,
+    }
+}
+
+/// The tick of a time from the start of the piece.
+fn tick(midi: &Midi, time: Duration) -> u64 {
+    let ticks = time.numerator() as u64 * 4 * midi.ticks_per_quarter as u64;
+    (ticks + time.denominator() as u64 / 2) / time.denominator() as u64
+}
+
+/// The tick of a number of seconds from the start of the piece.
+fn seconds_tick(midi: &Midi, seconds: f64) -> u64 {
+    let tempo = &midi.tempo;
+    let quarters = tempo.beats_at(seconds) * tempo.unit().as_f64() * 4.0;
+    (quarters * midi.ticks_per_quarter as f64).round() as u64
+}
+
+/// A track chunk, with its events ordered in time.
+fn chunk(mut events: Vec<Event>) -> Vec<u8> {
+    events.sort_by_key(|e| (e.tick, e.order));
+
+    let mut data = vec![];
+    let mut previous = 0;
+    for event in events {
+        data.extend(variable(event.tick - previous));
+        data.extend(event.bytes);
+        previous = event.tick;
+    }
+    data.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut bytes = b"MTrk".to_vec();
+    bytes.extend((data.len() as u32).to_be_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+/// A variable-length quantity: 7 bits per byte, the high bit set on all the bytes but the
+/// last one.
+fn variable(value: u64) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.reverse();
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        midi::Track, timeline::TempoMap, Bar, Key, TimeSignature, A, C, HALF, QUARTER, WHOLE,
+    };
+
+    #[test]
+    fn variable_length() {
+        assert_eq!(variable(0), [0x00]);
+        assert_eq!(variable(0x40), [0x40]);
+        assert_eq!(variable(0x80), [0x81, 0x00]);
+        assert_eq!(variable(0x3FFF), [0xFF, 0x7F]);
+        assert_eq!(variable(0x0FFF_FFFF), [0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn header() {
+        let midi = Midi::new().with_track(Track::new("Piano").with_note(C, 1));
+        let bytes = file(&midi);
+        assert_eq!(&bytes[..14], b"MThd\0\0\0\x06\0\x01\0\x02\x01\xE0");
+        assert_eq!(&bytes[14..18], b"MTrk");
+
+        let bytes = file(&midi.with_format(Format::Single));
+        assert_eq!(&bytes[8..12], [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn conductor_track() {
+        let track = Track::new("Piano")
+            .with_bar(Bar::new().with_note(C, 1))
+            .with_bar(Bar::new().with_time_signature(TimeSignature::new(6, 8)));
+        let midi = Midi::new()
+            .with_tempo(TempoMap::new(120.0))
+            .with_key(Key::minor(A))
+            .with_track(track);
+
+        let events = conductor(&midi);
+        let bytes = events.iter().map(|e| e.bytes.clone()).collect::<Vec<_>>();
+        assert_eq!(bytes[0], [0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]);
+        assert_eq!(bytes[1], [0xFF, 0x58, 0x04, 4, 2, 24, 8]);
+        assert_eq!(bytes[2], [0xFF, 0x58, 0x04, 6, 3, 36, 8]);
+        assert_eq!(events[2].tick, 1920);
+        assert_eq!(bytes[3], [0xFF, 0x59, 0x02, 0, 1]);
+    }
+
+    #[test]
+    fn ramps() {
+        let midi = Midi::new().with_tempo(TempoMap::new(120.0).with_ramp(WHOLE, 60.0));
+        let events = tempos(&midi);
+        assert_eq!(events.len(), 17);
+        assert_eq!(events[1].tick, 120);
+        assert_eq!(events[16].bytes, [0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40]);
+    }
+
+    #[test]
+    fn notes() {
+        let track = Track::new("Piano")
+            .with_channel(2)
+            .with_program(4)
+            .with_note(C, 2)
+            .with_silence(4)
+            .with_chord(C.maj(), 4);
+        let midi = Midi::new().with_track(track);
+        let events = part(&midi, &midi.tracks[0]);
+        assert_eq!(events[0].bytes, [0xC2, 4]);
+        assert_eq!(events[2].bytes, [0x92, 60, 80]);
+        assert_eq!(events[3].bytes, [0x82, 60, 0]);
+        assert_eq!(events[3].tick, 960);
+        assert_eq!(events.len(), 2 + 2 + 3 * 2);
+        assert_eq!(events[4].tick, 1440);
+    }
+
+    #[test]
+    fn bars_of_track() {
+        let track = Track::new("Bass")
+            .with_note(C, 2)
+            .with_note(C, 4)
+            .with_note(C, 2)
+            .with_note(C, 4);
+        assert_eq!(track.bars().count(), 2);
+        assert!(track.bars().all(|b| b.duration() == HALF + QUARTER));
+    }
+}
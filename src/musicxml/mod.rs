@@ -0,0 +1,368 @@
+//! MusicXML documents, to exchange scores with the notation software.
+//!
+//! A [`MusicXml`] document holds [`Part`]s, each one made of one or more staves of bars (eg.
+//! the right hand and the left hand of a piano part), and the chord symbols written above the
+//! part. A document can be built from a [`Score`], with its form (repeats, endings, segno,
+//! coda) and its sections as rehearsal marks.
+//!
+//! An [`Import`] reads a MusicXML document (partwise or timewise) back into bars: the
+//! notes of each staff, and the chord symbols. What cannot be imported is skipped with a
+//! warning.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{musicxml::*, *};
+//!
+//! let rh = [Bar::new().with_note(E, 2).with_note(G, 2)];
+//! let lh = [Bar::new().with_chord(C.maj7(), 1)];
+//! let piano = Part::new("Piano")
+//!     .with_staff(Clef::Treble, &rh)
+//!     .with_staff(Clef::Bass, &lh);
+//! let xml = MusicXml::new("Exercise").with_part(piano).to_xml();
+//!
+//! assert!(xml.contains("<staves>2</staves>"));
+//! assert!(xml.contains(r#"<kind text="maj7">major-seventh</kind>"#));
+//! ```
+
+mod read;
+mod write;
+mod xml;
+
+pub use read::MusicXmlError;
+
+use crate::{
+    score::{Mark, Score, Staff},
+    Bar, Key,
+};
+
+/// The alterations of the degrees of a chord, eg. (5, -1) for a flat fifth.
+type Degrees = &'static [(u8, i8)];
+
+/// The suffix of each chord, its MusicXML kind and the alterations of its degrees.
+const KINDS: [(&str, &str, Degrees); 20] = [
+    ("", "major", &[]),
+    ("maj7", "major-seventh", &[]),
+    ("maj9", "major-ninth", &[]),
+    ("maj11", "major-11th", &[]),
+    ("maj13", "major-13th", &[]),
+    ("m", "minor", &[]),
+    ("m7", "minor-seventh", &[]),
+    ("m7(b5)", "half-diminished", &[]),
+    ("m9", "minor-ninth", &[]),
+    ("m11", "minor-11th", &[]),
+    ("m13", "minor-13th", &[]),
+    ("7", "dominant", &[]),
+    ("7b5", "dominant", &[(5, -1)]),
+    ("7#5", "augmented-seventh", &[]),
+    ("9", "dominant-ninth", &[]),
+    ("11", "dominant-11th", &[]),
+    ("13", "dominant-13th", &[]),
+    ("13b9b13", "dominant-13th", &[(9, -1), (13, -1)]),
+    ("dim", "diminished", &[]),
+    ("dim7", "diminished-seventh", &[]),
+];
+
+/// The clef of a staff.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Clef {
+    #[default]
+    Treble,
+    Bass,
+}
+
+/// A part of a document: an instrument, with its staves and its chord symbols.
+pub struct Part<'a> {
+    name: String,
+    staves: Vec<(Clef, Vec<Option<&'a Bar>>)>,
+    chords: Vec<Option<&'a Bar>>,
+}
+
+/// A MusicXML document.
+pub struct MusicXml<'a> {
+    title: String,
+    key: Option<Key>,
+    parts: Vec<Part<'a>>,
+    marks: Vec<Vec<Mark>>,
+    labels: Vec<Option<String>>,
+}
+
+/// A part of an imported document.
+pub struct ImportedPart {
+    name: String,
+    staves: Vec<(Clef, Vec<Bar>)>,
+    chords: Vec<Bar>,
+}
+
+/// A MusicXML document read into bars, with the warnings about what was skipped.
+pub struct Import {
+    title: String,
+    key: Option<Key>,
+    parts: Vec<ImportedPart>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Part<'a> {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            staves: vec![],
+            chords: vec![],
+        }
+    }
+
+    /// Adds a staff, below the previous ones.
+    pub fn with_staff(self, clef: Clef, bars: impl IntoIterator<Item = &'a Bar>) -> Self {
+        self.with_measures(clef, bars.into_iter().map(Some))
+    }
+
+    /// Adds a staff, `None` being a measure where the staff does not play (a measure rest).
+    pub fn with_measures(
+        self,
+        clef: Clef,
+        bars: impl IntoIterator<Item = Option<&'a Bar>>,
+    ) -> Self {
+        let mut staves = self.staves;
+        staves.push((clef, bars.into_iter().collect()));
+        Self { staves, ..self }
+    }
+
+    /// Writes the chords of the bars as chord symbols above the first staff.
+    pub fn with_chords(self, bars: impl IntoIterator<Item = Option<&'a Bar>>) -> Self {
+        Self {
+            chords: bars.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of measures of the longest staff.
+    fn measures(&self) -> usize {
+        self.staves
+            .iter()
+            .map(|(_, bars)| bars.len())
+            .chain(std::iter::once(self.chords.len()))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<'a> MusicXml<'a> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            key: None,
+            parts: vec![],
+            marks: vec![],
+            labels: vec![],
+        }
+    }
+
+    /// Sets the key signature, which also gives the spelling of the notes (sharps by
+    /// default).
+    pub fn with_key(self, key: Key) -> Self {
+        Self {
+            key: Some(key),
+            ..self
+        }
+    }
+
+    pub fn with_part(self, part: Part<'a>) -> Self {
+        let mut parts = self.parts;
+        parts.push(part);
+        Self { parts, ..self }
+    }
+
+    /// A document with the staves of the score, as written (the form is kept with repeat
+    /// signs, endings and jumps):
+    /// - the melody, with the chords as chord symbols above it,
+    /// - the chords, when there is no melody,
+    /// - the right hand and the left hand in a piano part,
+    /// - the bass.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{musicxml::*, score::*, *};
+    ///
+    /// let bb = Measure::chords(Bar::new().with_chord(A_SHARP.maj7(), 1));
+    /// let eb = Measure::chords(Bar::new().with_chord(D_SHARP.dom7(), 1));
+    /// let section = Section::new("A")
+    ///     .with_measure(bb.with_mark(Mark::RepeatStart))
+    ///     .with_measure(eb.with_mark(Mark::RepeatEnd(2)));
+    /// let score = Score::new("Vamp").with_section(section);
+    ///
+    /// let xml = MusicXml::from_score(&score).with_key(Key::major(A_SHARP)).to_xml();
+    /// assert!(xml.contains(r#"<repeat direction="backward"/>"#));
+    /// assert!(xml.contains("<root-step>B</root-step>"));
+    /// ```
+    pub fn from_score(score: &'a Score) -> Self {
+        let measures = score.measures().collect::<Vec<_>>();
+        let staves = score.staves();
+        let bars = |staff: Staff| measures.iter().map(move |m| m.bar(staff));
+
+        let mut document = Self::new(score.title());
+        if staves.contains(&Staff::Melody) {
+            let mut melody = Part::new("Melody").with_measures(Clef::Treble, bars(Staff::Melody));
+            if staves.contains(&Staff::Chords) {
+                melody = melody.with_chords(bars(Staff::Chords));
+            }
+            document = document.with_part(melody);
+        } else if staves.contains(&Staff::Chords) {
+            document = document
+                .with_part(Part::new("Chords").with_measures(Clef::Treble, bars(Staff::Chords)));
+        }
+        if staves.contains(&Staff::RightHand) || staves.contains(&Staff::LeftHand) {
+            let piano = Part::new("Piano")
+                .with_measures(Clef::Treble, bars(Staff::RightHand))
+                .with_measures(Clef::Bass, bars(Staff::LeftHand));
+            document = document.with_part(piano);
+        }
+        if staves.contains(&Staff::Bass) {
+            document =
+                document.with_part(Part::new("Bass").with_measures(Clef::Bass, bars(Staff::Bass)));
+        }
+
+        let mut labels = vec![];
+        for section in score.sections() {
+            let label = Some(section.label().to_string());
+            labels.extend(std::iter::once(label).chain(section.measures().skip(1).map(|_| None)));
+        }
+
+        Self {
+            marks: measures
+                .iter()
+                .map(|m| m.marks().copied().collect())
+                .collect(),
+            labels,
+            ..document
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn parts(&self) -> impl Iterator<Item = &Part<'a>> {
+        self.parts.iter()
+    }
+
+    /// The content of the `.musicxml` file.
+    pub fn to_xml(&self) -> String {
+        write::document(self)
+    }
+
+    /// Writes the `.musicxml` file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_xml())
+    }
+}
+
+impl ImportedPart {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the staves of the part, from the top one, with a bar per measure.
+    pub fn staves(&self) -> impl Iterator<Item = (Clef, &[Bar])> {
+        self.staves
+            .iter()
+            .map(|(clef, bars)| (*clef, bars.as_slice()))
+    }
+
+    /// Returns the chord symbols of the part, with a bar per measure (a measure without
+    /// chord symbol being a silence), or nothing if the part has no chord symbol.
+    pub fn chords(&self) -> &[Bar] {
+        &self.chords
+    }
+}
+
+impl Import {
+    /// Reads a MusicXML document, partwise or timewise.
+    ///
+    /// The notes of a voice are laid in the bar one after the other, the notes of a chord
+    /// making a cluster, and the gaps between them being silences. The voices of a staff
+    /// are the voices of its bars, in the order they appear. The grace notes, the elements
+    /// which are not notes nor chord symbols, and the chord kinds without a matching chord
+    /// are skipped, with a warning. The directions, the barlines and the layout are
+    /// skipped without any warning.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{musicxml::*, *};
+    ///
+    /// let xml = r#"<score-partwise version="4.0">
+    ///   <part-list><score-part id="P1"><part-name>Flute</part-name></score-part></part-list>
+    ///   <part id="P1">
+    ///     <measure number="1">
+    ///       <attributes><divisions>2</divisions><time><beats>2</beats><beat-type>4</beat-type></time></attributes>
+    ///       <harmony><root><root-step>G</root-step></root><kind>dominant-ninth</kind></harmony>
+    ///       <note><pitch><step>B</step><octave>4</octave></pitch><duration>3</duration></note>
+    ///       <note><pitch><step>A</step><octave>4</octave></pitch><duration>1</duration></note>
+    ///       <direction><direction-type><dynamics><p/></dynamics></direction-type></direction>
+    ///       <figure-sketch/>
+    ///     </measure>
+    ///   </part>
+    /// </score-partwise>"#;
+    ///
+    /// let import = Import::from_xml(xml).unwrap();
+    /// let flute = import.parts().next().unwrap();
+    /// let (_, bars) = flute.staves().next().unwrap();
+    /// assert_eq!(flute.name(), "Flute");
+    /// assert_eq!(bars[0].time_signature(), &TimeSignature::new(2, 4));
+    /// assert_eq!(format!("{:X}", bars[0]), "B A");
+    /// assert_eq!(flute.chords()[0].to_string(), "G9");
+    /// assert_eq!(
+    ///     import.warnings().collect::<Vec<_>>(),
+    ///     ["measure 1: skipped <figure-sketch>"]
+    /// );
+    /// ```
+    pub fn from_xml(text: &str) -> Result<Self, MusicXmlError> {
+        read::document(text)
+    }
+
+    /// Reads a `.musicxml` (or `.xml`) file.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, MusicXmlError> {
+        read::file(std::fs::read(path)?)
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The key of the first key signature, if any.
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+
+    pub fn parts(&self) -> impl Iterator<Item = &ImportedPart> {
+        self.parts.iter()
+    }
+
+    /// Returns what was skipped, eg. "measure 3: skipped a grace note".
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// A document with the imported parts, to write them back.
+    pub fn to_musicxml(&self) -> MusicXml<'_> {
+        let mut document = MusicXml::new(&self.title);
+        if let Some(key) = self.key {
+            document = document.with_key(key);
+        }
+        for part in &self.parts {
+            let mut written = part
+                .staves
+                .iter()
+                .fold(Part::new(&part.name), |written, (clef, bars)| {
+                    written.with_staff(*clef, bars)
+                });
+            if !part.chords.is_empty() {
+                written = written.with_chords(part.chords.iter().map(Some));
+            }
+            document = document.with_part(written);
+        }
+        document
+    }
+}
@@ -0,0 +1,881 @@
+use std::fmt::Display;
+
+use super::{
+    xml::{self, Element},
+    Clef, Import, ImportedPart, KINDS,
+};
+use crate::{chords::Chords, Bar, BarElement, Duration, Key, Note, TimeSignature, Voice};
+
+/// The errors of reading a MusicXML file.
+#[derive(Debug)]
+pub enum MusicXmlError {
+    Io(std::io::Error),
+    /// The file is a compressed MusicXML archive (`.mxl`), which is not supported.
+    Compressed,
+    /// The text is not well-formed XML (or not UTF-8).
+    Syntax(String),
+    /// The root element is neither `<score-partwise>` nor `<score-timewise>`.
+    NotMusicXml(String),
+}
+
+/// The elements of a measure which are known, but not imported.
+const IGNORED: [&str; 9] = [
+    "barline",
+    "bookmark",
+    "direction",
+    "figured-bass",
+    "grouping",
+    "link",
+    "listening",
+    "print",
+    "sound",
+];
+
+/// The most divisions of a quarter note, and the longest note or measure in whole notes, for
+/// the durations to stay far from overflowing.
+const MAX_DIVISIONS: u32 = 16_384;
+const LONGEST: u32 = 64;
+
+/// The attributes of a part, kept from a measure to the next one.
+struct Attributes {
+    divisions: u32,
+    time: TimeSignature,
+    clefs: Vec<Clef>,
+}
+
+/// Notes (or a rest, without any note) starting together in a voice.
+struct Event {
+    start: Duration,
+    duration: Duration,
+    notes: Vec<Note>,
+}
+
+/// The events of a voice of a staff, in a measure.
+struct VoiceEvents<'a> {
+    staff: usize,
+    voice: &'a str,
+    events: Vec<Event>,
+}
+
+/// Reads the bytes of a MusicXML file.
+pub(super) fn file(data: Vec<u8>) -> Result<Import, MusicXmlError> {
+    if data.starts_with(b"PK") {
+        return Err(MusicXmlError::Compressed);
+    }
+    let text = String::from_utf8(data)
+        .map_err(|_| MusicXmlError::Syntax("the file is not UTF-8".to_string()))?;
+    document(&text)
+}
+
+/// Reads a MusicXML document, partwise or timewise.
+pub(super) fn document(text: &str) -> Result<Import, MusicXmlError> {
+    let root = xml::parse(text).map_err(MusicXmlError::Syntax)?;
+
+    // The measures of each part, with their number.
+    let mut parts: Vec<(&str, Vec<(&str, &Element)>)> = vec![];
+    match root.name.as_str() {
+        "score-partwise" => {
+            for part in root.children("part") {
+                let measures = part
+                    .children("measure")
+                    .map(|m| (m.attribute("number").unwrap_or("?"), m));
+                parts.push((part.attribute("id").unwrap_or(""), measures.collect()));
+            }
+        }
+        "score-timewise" => {
+            for measure in root.children("measure") {
+                let number = measure.attribute("number").unwrap_or("?");
+                for part in measure.children("part") {
+                    let id = part.attribute("id").unwrap_or("");
+                    match parts.iter_mut().find(|(i, _)| *i == id) {
+                        Some((_, measures)) => measures.push((number, part)),
+                        None => parts.push((id, vec![(number, part)])),
+                    }
+                }
+            }
+        }
+        name => return Err(MusicXmlError::NotMusicXml(name.to_string())),
+    }
+
+    let title = root
+        .child("work")
+        .and_then(|w| w.child_text("work-title"))
+        .or(root.child_text("movement-title"))
+        .unwrap_or("");
+    let names = root
+        .child("part-list")
+        .into_iter()
+        .flat_map(|list| list.children("score-part"))
+        .map(|p| (p.attribute("id").unwrap_or(""), p.child_text("part-name")))
+        .collect::<Vec<_>>();
+
+    let mut import = Import {
+        title: title.to_string(),
+        key: None,
+        parts: vec![],
+        warnings: vec![],
+    };
+    for (id, measures) in parts {
+        let name = names
+            .iter()
+            .find(|(i, _)| *i == id)
+            .and_then(|(_, name)| *name)
+            .unwrap_or(id);
+        let part = part(&mut import, name, &measures);
+        import.parts.push(part);
+    }
+    Ok(import)
+}
+
+/// Reads the measures of a part.
+fn part(import: &mut Import, name: &str, measures: &[(&str, &Element)]) -> ImportedPart {
+    let mut attributes = Attributes {
+        divisions: 1,
+        time: TimeSignature::default(),
+        clefs: vec![Clef::Treble],
+    };
+    let mut staves: Vec<Vec<Bar>> = vec![];
+    let mut chords = vec![];
+    let mut has_chords = false;
+
+    for (number, measure) in measures {
+        let mut warn = |warning: String| {
+            let warning = format!("measure {number}: {warning}");
+            if !import.warnings.contains(&warning) {
+                import.warnings.push(warning);
+            }
+        };
+
+        let mut voices: Vec<VoiceEvents> = vec![];
+        let mut harmonies: Vec<(Duration, Option<Chords>)> = vec![];
+        let mut position = Duration::ZERO;
+        let mut last: Option<usize> = None;
+
+        for element in &measure.children {
+            match element.name.as_str() {
+                "attributes" => {
+                    read_attributes(element, &mut attributes, &mut import.key, &mut warn);
+                }
+                "note" => {
+                    if element.child("grace").is_some() {
+                        warn("skipped a grace note".to_string());
+                        continue;
+                    }
+                    if element.child("cue").is_some() {
+                        warn("skipped a cue note".to_string());
+                        continue;
+                    }
+                    let Some(duration) = duration(element, &attributes) else {
+                        warn("skipped a note without a valid duration".to_string());
+                        continue;
+                    };
+                    if position > Duration::new(LONGEST, 1) {
+                        warn(format!("skipped the notes after {LONGEST} whole notes"));
+                        continue;
+                    }
+                    let note = match element.child("pitch") {
+                        Some(pitch) => {
+                            let note = pitch_note(pitch);
+                            if note.is_none() {
+                                warn("read an invalid pitch as a rest".to_string());
+                            }
+                            note
+                        }
+                        None => {
+                            if element.child("unpitched").is_some() {
+                                warn("skipped an unpitched note".to_string());
+                            }
+                            None
+                        }
+                    };
+
+                    // A note of a chord starts with the previous note.
+                    if let (Some(index), Some(_)) = (last, element.child("chord")) {
+                        let events = &mut voices[index].events;
+                        events.last_mut().unwrap().notes.extend(note);
+                        continue;
+                    }
+
+                    let staff = element
+                        .child_text("staff")
+                        .and_then(|s| s.parse::<usize>().ok())
+                        .unwrap_or(1)
+                        .max(1);
+                    let voice = element.child_text("voice").unwrap_or("1");
+                    let index = match voices
+                        .iter()
+                        .position(|v| v.staff == staff && v.voice == voice)
+                    {
+                        Some(index) => index,
+                        None => {
+                            voices.push(VoiceEvents {
+                                staff,
+                                voice,
+                                events: vec![],
+                            });
+                            voices.len() - 1
+                        }
+                    };
+                    voices[index].events.push(Event {
+                        start: position,
+                        duration,
+                        notes: note.into_iter().collect(),
+                    });
+                    last = Some(index);
+                    position += duration;
+                }
+                "backup" => {
+                    let duration = duration(element, &attributes).unwrap_or(Duration::ZERO);
+                    position = position.checked_sub(duration).unwrap_or(Duration::ZERO);
+                }
+                "forward" if position > Duration::new(LONGEST, 1) => {
+                    warn(format!("skipped the notes after {LONGEST} whole notes"));
+                }
+                "forward" => position += duration(element, &attributes).unwrap_or(Duration::ZERO),
+                "harmony" => match harmony(element) {
+                    Ok(chord) => {
+                        // The offset, in divisions, may be negative.
+                        let longest = (attributes.divisions * 4 * LONGEST) as f64;
+                        let offset = element
+                            .child_text("offset")
+                            .and_then(|o| o.parse::<f64>().ok())
+                            .map_or(0, |o| o.round().clamp(-longest, longest) as i64);
+                        let shift =
+                            Duration::new(offset.unsigned_abs() as u32, attributes.divisions * 4);
+                        let start = match offset < 0 {
+                            true => position.checked_sub(shift).unwrap_or(Duration::ZERO),
+                            false => position + shift,
+                        };
+                        harmonies.push((start, chord));
+                        has_chords = true;
+                    }
+                    Err(warning) => warn(warning),
+                },
+                name if IGNORED.contains(&name) => {}
+                name => warn(format!("skipped <{name}>")),
+            }
+        }
+
+        // A pickup measure lasts as long as its notes.
+        let length = match measure.attribute("implicit") {
+            Some("yes") => voices
+                .iter()
+                .flat_map(|v| v.events.iter())
+                .map(|e| e.start + e.duration)
+                .max()
+                .unwrap_or(Duration::ZERO),
+            _ => attributes.time.duration(),
+        };
+
+        let count = voices
+            .iter()
+            .map(|v| v.staff)
+            .chain(std::iter::once(attributes.clefs.len()))
+            .max()
+            .unwrap_or(1);
+        while staves.len() < count {
+            let silent = staves.first().map_or(vec![], |bars| {
+                bars.iter()
+                    .map(|b| silent_bar(b.time_signature(), b.time_signature().duration()))
+                    .collect()
+            });
+            staves.push(silent);
+        }
+        for (index, bars) in staves.iter_mut().enumerate() {
+            let staff_voices = voices.iter_mut().filter(|v| v.staff == index + 1);
+            bars.push(bar(staff_voices, &attributes.time, length, &mut warn));
+        }
+        chords.push(chord_bar(harmonies, &attributes.time, length));
+    }
+
+    let mut clefs = attributes.clefs;
+    clefs.resize(staves.len(), Clef::Treble);
+    ImportedPart {
+        name: name.to_string(),
+        staves: clefs.into_iter().zip(staves).collect(),
+        chords: match has_chords {
+            true => chords,
+            false => vec![],
+        },
+    }
+}
+
+/// Reads the divisions, the key, the time signature and the clefs.
+fn read_attributes(
+    element: &Element,
+    attributes: &mut Attributes,
+    key: &mut Option<Key>,
+    warn: &mut impl FnMut(String),
+) {
+    if let Some(divisions) = element
+        .child_text("divisions")
+        .and_then(|d| d.parse::<u32>().ok())
+    {
+        match divisions <= MAX_DIVISIONS {
+            true => attributes.divisions = u32::max(divisions, 1),
+            false => warn(format!("skipped {divisions} divisions of a quarter note")),
+        }
+    }
+
+    if let Some(fifths) = element
+        .child("key")
+        .and_then(|k| k.child_text("fifths"))
+        .and_then(|f| f.parse::<i16>().ok())
+    {
+        if (-7..=7).contains(&fifths) {
+            let tonic = Key::major(Note::from((fifths * 7).rem_euclid(12) as i8));
+            let mode = element.child("key").and_then(|k| k.child_text("mode"));
+            key.get_or_insert(match mode {
+                Some("minor") => tonic.relative(),
+                _ => tonic,
+            });
+        } else {
+            warn(format!("skipped a key with {fifths} fifths"));
+        }
+    }
+
+    if let Some(time) = element.child("time") {
+        match time_signature(time) {
+            Some(time) => attributes.time = time,
+            None => warn("skipped an unsupported time signature".to_string()),
+        }
+    }
+
+    if let Some(staves) = element.child_text("staves").and_then(|s| s.parse().ok()) {
+        attributes.clefs.resize(usize::max(staves, 1), Clef::Treble);
+    }
+    for clef in element.children("clef") {
+        let number = clef
+            .attribute("number")
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1);
+        if attributes.clefs.len() < number {
+            attributes.clefs.resize(number, Clef::Treble);
+        }
+        attributes.clefs[number - 1] = match clef.child_text("sign") {
+            Some("F") => Clef::Bass,
+            _ => Clef::Treble,
+        };
+    }
+}
+
+/// The time signature, simple (eg. 3/4) or additive (eg. 3+3+2/8).
+fn time_signature(time: &Element) -> Option<TimeSignature> {
+    let denominator = time.child_text("beat-type")?.parse::<u8>().ok()?;
+    let groups = time
+        .child_text("beats")?
+        .split('+')
+        .map(|g| g.trim().parse::<u8>().ok().filter(|g| *g > 0))
+        .collect::<Option<Vec<_>>>()?;
+    if !denominator.is_power_of_two() || groups.iter().map(|g| *g as u32).sum::<u32>() > 255 {
+        return None;
+    }
+    match &groups[..] {
+        [numerator] => Some(TimeSignature::new(*numerator, denominator)),
+        groups => Some(TimeSignature::additive(groups, denominator)),
+    }
+}
+
+/// The duration of a note, a backup or a forward, from its divisions of a quarter note, if
+/// it is not longer than [`LONGEST`] whole notes.
+fn duration(element: &Element, attributes: &Attributes) -> Option<Duration> {
+    let divisions = element.child_text("duration")?.parse::<f64>().ok()?;
+    let whole = attributes.divisions * 4;
+    let divisions = divisions.round().max(0.0);
+    (divisions <= (whole * LONGEST) as f64).then(|| Duration::new(divisions as u32, whole))
+}
+
+/// The pitch class of a step (C being 0).
+fn step(step: &str) -> Option<i8> {
+    match step {
+        "C" => Some(0),
+        "D" => Some(2),
+        "E" => Some(4),
+        "F" => Some(5),
+        "G" => Some(7),
+        "A" => Some(9),
+        "B" => Some(11),
+        _ => None,
+    }
+}
+
+/// The semitones of an alteration, the microtones being rounded, within an octave.
+fn alter(alter: Option<&str>) -> i8 {
+    alter
+        .and_then(|a| a.parse::<f64>().ok())
+        .map_or(0, |a| a.round().clamp(-12.0, 12.0) as i8)
+}
+
+/// The note of a pitch, if it is in the MIDI range.
+fn pitch_note(pitch: &Element) -> Option<Note> {
+    let step = step(pitch.child_text("step")?)?;
+    let octave = pitch.child_text("octave")?.parse::<i32>().ok()?;
+    let alter = alter(pitch.child_text("alter"));
+    let note = octave.checked_sub(4)?.checked_mul(12)? + (step + alter) as i32;
+    // From C-1 to G9.
+    (-60..=67).contains(&note).then(|| Note::from(note as i8))
+}
+
+/// The chord of a chord symbol, `None` meaning no chord (N.C.), or a warning if the chord
+/// is not known.
+fn harmony(element: &Element) -> Result<Option<Chords>, String> {
+    let kind = element.child_text("kind").unwrap_or("");
+    if kind == "none" {
+        return Ok(None);
+    }
+    let root = element
+        .child("root")
+        .and_then(|r| {
+            Some((
+                step(r.child_text("root-step")?)?,
+                r.child_text("root-alter"),
+            ))
+        })
+        .map(|(step, root_alter)| Note::from(step + alter(root_alter)))
+        .ok_or_else(|| "skipped a chord symbol without root".to_string())?;
+
+    let degrees = element
+        .children("degree")
+        .filter_map(|d| {
+            let value = d.child_text("degree-value")?.parse::<u8>().ok()?;
+            Some((value, alter(d.child_text("degree-alter"))))
+        })
+        .collect::<Vec<_>>();
+    let candidates = KINDS.iter().filter(|(_, k, _)| *k == kind);
+    let suffix = candidates
+        .clone()
+        .find(|(_, _, d)| *d == degrees)
+        .or(candidates.clone().next())
+        .map(|(suffix, _, _)| *suffix)
+        .ok_or_else(|| format!("skipped the unknown chord kind {kind:?}"))?;
+
+    Chords::all_chords(root)
+        .find(|chord| chord.suffix() == suffix)
+        .map(Some)
+        .ok_or_else(|| format!("skipped the unknown chord kind {kind:?}"))
+}
+
+/// Lays the events of the voices of a staff in a bar, the gaps being silences.
+fn bar<'a, 'b: 'a>(
+    voices: impl Iterator<Item = &'a mut VoiceEvents<'b>>,
+    time: &TimeSignature,
+    length: Duration,
+    warn: &mut impl FnMut(String),
+) -> Bar {
+    let mut bar = Bar::new().with_time_signature(time.clone());
+    let mut empty = true;
+
+    for (index, voice) in voices.enumerate() {
+        voice.events.sort_by_key(|e| e.start);
+        let mut elements = vec![];
+        let mut cursor = Duration::ZERO;
+        for event in &voice.events {
+            if event.start < cursor {
+                warn(format!(
+                    "skipped overlapping notes in the voice {}",
+                    voice.voice
+                ));
+                continue;
+            }
+            elements.extend(silences(event.start - cursor));
+            let mut notes = event.notes.clone();
+            notes.sort();
+            notes.dedup();
+            elements.push(match &notes[..] {
+                [] => BarElement::Silence(event.duration),
+                [note] => BarElement::Note(*note, event.duration),
+                notes => BarElement::Cluster(notes.to_vec(), event.duration),
+            });
+            cursor = event.start + event.duration;
+        }
+        elements.extend(silences(
+            length.checked_sub(cursor).unwrap_or(Duration::ZERO),
+        ));
+
+        bar = match index {
+            0 => elements.into_iter().fold(bar, Bar::with_element),
+            _ => bar.with_voice(elements.into_iter().fold(Voice::new(), Voice::with_element)),
+        };
+        empty = false;
+    }
+
+    match empty {
+        true => silent_bar(time, length),
+        false => bar,
+    }
+}
+
+/// A bar of the chord symbols, each chord lasting until the next one.
+fn chord_bar(
+    mut harmonies: Vec<(Duration, Option<Chords>)>,
+    time: &TimeSignature,
+    length: Duration,
+) -> Bar {
+    harmonies.sort_by_key(|(start, _)| *start);
+    let ends = harmonies
+        .iter()
+        .skip(1)
+        .map(|(start, _)| *start)
+        .chain(std::iter::once(length))
+        .collect::<Vec<_>>();
+
+    let first = harmonies.first().map_or(length, |(start, _)| *start);
+    let mut bar = silences(first).fold(
+        Bar::new().with_time_signature(time.clone()),
+        Bar::with_element,
+    );
+    for ((start, chord), end) in harmonies.into_iter().zip(ends) {
+        let Some(duration) = end.checked_sub(start).filter(|d| !d.is_zero()) else {
+            continue;
+        };
+        bar = match chord {
+            Some(chord) => bar.with_chord(chord, duration),
+            None => silences(duration).fold(bar, Bar::with_element),
+        };
+    }
+    bar
+}
+
+fn silent_bar(time: &TimeSignature, length: Duration) -> Bar {
+    silences(length).fold(
+        Bar::new().with_time_signature(time.clone()),
+        Bar::with_element,
+    )
+}
+
+fn silences(duration: Duration) -> impl Iterator<Item = BarElement> {
+    let parts = match duration.is_zero() {
+        true => vec![],
+        false => duration.split(),
+    };
+    parts.into_iter().map(BarElement::Silence)
+}
+
+impl Display for MusicXmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MusicXmlError::Io(e) => write!(f, "{e}"),
+            MusicXmlError::Compressed => {
+                write!(f, "the compressed MusicXML files (.mxl) are not supported")
+            }
+            MusicXmlError::Syntax(message) => write!(f, "invalid XML: {message}"),
+            MusicXmlError::NotMusicXml(root) => {
+                write!(f, "not a MusicXML document (the root element is <{root}>)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MusicXmlError {}
+
+impl From<std::io::Error> for MusicXmlError {
+    fn from(e: std::io::Error) -> Self {
+        MusicXmlError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        musicxml::{MusicXml, Part},
+        Chart, Spelling, A, B, C, D, E, EIGHTH, F, G, HALF, OCTAVE, QUARTER,
+    };
+
+    fn measures(content: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="4.0">
+  <part-list><score-part id="P1"><part-name>Piano</part-name></score-part></part-list>
+  <part id="P1">{content}</part>
+</score-partwise>"#
+        )
+    }
+
+    fn staff(import: &Import, index: usize) -> Vec<String> {
+        let part = import.parts().next().unwrap();
+        let (_, bars) = part.staves().nth(index).unwrap();
+        bars.iter().map(|b| format!("{b:X}")).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let rh = [
+            Bar::new()
+                .with_note(E + OCTAVE, QUARTER.dotted())
+                .with_note(D + OCTAVE, EIGHTH)
+                .with_cluster(&[C, E, G], HALF),
+            Bar::new()
+                .with_time_signature(TimeSignature::new(3, 4))
+                .with_note(B, QUARTER.triplet())
+                .with_note(A, QUARTER.triplet())
+                .with_note(G, QUARTER.triplet())
+                .with_silence(QUARTER),
+        ];
+        let lh = [
+            Bar::new().with_note(C - OCTAVE, 1),
+            Bar::new()
+                .with_time_signature(TimeSignature::new(3, 4))
+                .with_note(G - OCTAVE, HALF.dotted())
+                .with_voice(Voice::new().with_note(B - OCTAVE, HALF.dotted())),
+        ];
+        let chords = [
+            Bar::new().with_chord(C.maj7(), 1),
+            Bar::new()
+                .with_time_signature(TimeSignature::new(3, 4))
+                .with_chord(G.dom7(), QUARTER)
+                .with_chord(D.min7(), HALF),
+        ];
+        let piano = Part::new("Piano")
+            .with_staff(Clef::Treble, &rh)
+            .with_staff(Clef::Bass, &lh)
+            .with_chords(chords.iter().map(Some));
+        let xml = MusicXml::new("Étude <1>")
+            .with_key(Key::minor(A))
+            .with_part(piano)
+            .to_xml();
+
+        let import = Import::from_xml(&xml).unwrap();
+        assert_eq!(import.title(), "Étude <1>");
+        assert_eq!(import.key(), Some(Key::minor(A)));
+        assert_eq!(import.warnings().count(), 0);
+
+        let part = import.parts().next().unwrap();
+        let clefs = part.staves().map(|(clef, _)| clef).collect::<Vec<_>>();
+        assert_eq!(clefs, [Clef::Treble, Clef::Bass]);
+        for (index, bars) in [&rh, &lh].into_iter().enumerate() {
+            let expected = bars.iter().map(|b| format!("{b:X}")).collect::<Vec<_>>();
+            assert_eq!(staff(&import, index), expected);
+        }
+        assert_eq!(Chart::new().render(part.chords()), "| Cmaj7 | G7 Dm7 |");
+
+        // The imported document is written as it was.
+        assert_eq!(import.to_musicxml().to_xml(), xml);
+    }
+
+    #[test]
+    fn timewise() {
+        let xml = r#"<score-timewise>
+  <part-list>
+    <score-part id="P1"><part-name>Voice</part-name></score-part>
+    <score-part id="P2"><part-name>Bass</part-name></score-part>
+  </part-list>
+  <measure number="1">
+    <part id="P1">
+      <attributes><divisions>1</divisions><key><fifths>-1</fifths></key></attributes>
+      <note><pitch><step>F</step><octave>4</octave></pitch><duration>4</duration></note>
+    </part>
+    <part id="P2">
+      <note><pitch><step>B</step><alter>-1</alter><octave>2</octave></pitch><duration>4</duration></note>
+    </part>
+  </measure>
+  <measure number="2">
+    <part id="P1"><note><rest measure="yes"/><duration>4</duration></note></part>
+    <part id="P2"><note><pitch><step>C</step><octave>3</octave></pitch><duration>4</duration></note></part>
+  </measure>
+</score-timewise>"#;
+        let import = Import::from_xml(xml).unwrap();
+        assert_eq!(import.key(), Some(Key::major(F)));
+
+        let parts = import
+            .parts()
+            .map(|part| {
+                let (_, bars) = part.staves().next().unwrap();
+                let notes = bars
+                    .iter()
+                    .flat_map(|b| b.elements().flat_map(|e| e.notes()));
+                let notes = notes.map(|n| Spelling::Flats.note(n)).collect::<Vec<_>>();
+                (part.name().to_string(), notes.join(" "), bars.len())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            [
+                ("Voice".to_string(), "F".to_string(), 2),
+                ("Bass".to_string(), "Bb C".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn voices() {
+        // Two voices on the first staff, with a backup, and a gap filled by a forward.
+        let xml = measures(
+            r#"<measure number="1">
+  <attributes><divisions>2</divisions><staves>2</staves>
+    <clef number="1"><sign>G</sign></clef><clef number="2"><sign>F</sign></clef></attributes>
+  <note><pitch><step>C</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><staff>1</staff></note>
+  <note><pitch><step>D</step><octave>5</octave></pitch><duration>4</duration><voice>1</voice><staff>1</staff></note>
+  <backup><duration>8</duration></backup>
+  <forward><duration>4</duration><voice>2</voice><staff>1</staff></forward>
+  <note><pitch><step>G</step><octave>4</octave></pitch><duration>2</duration><voice>2</voice><staff>1</staff></note>
+  <note><chord/><pitch><step>B</step><octave>4</octave></pitch><duration>2</duration><voice>2</voice><staff>1</staff></note>
+  <backup><duration>6</duration></backup>
+  <note><pitch><step>C</step><octave>3</octave></pitch><duration>8</duration><voice>5</voice><staff>2</staff></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        assert_eq!(staff(&import, 0), ["C D / _ <G B> _"]);
+        assert_eq!(staff(&import, 1), ["C"]);
+        let part = import.parts().next().unwrap();
+        assert_eq!(part.staves().nth(1).unwrap().0, Clef::Bass);
+        assert!(part.chords().is_empty());
+    }
+
+    #[test]
+    fn overflows() {
+        // An overfull measure is not padded with a rest.
+        let xml = measures(
+            r#"<measure number="1"><attributes><divisions>1</divisions></attributes>
+  <note><pitch><step>C</step><octave>4</octave></pitch><duration>5</duration></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        let part = import.parts().next().unwrap();
+        let (_, bars) = part.staves().next().unwrap();
+        let durations = bars[0].elements().map(|e| e.duration()).collect::<Vec<_>>();
+        assert_eq!(durations, [Duration::new(5, 4)]);
+
+        // A backup past the start of the measure goes back to its start.
+        let xml = measures(
+            r#"<measure number="1"><attributes><divisions>1</divisions></attributes>
+  <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration></note>
+  <backup><duration>3</duration></backup>
+  <note><pitch><step>D</step><octave>4</octave></pitch><duration>1</duration><voice>2</voice></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        assert_eq!(staff(&import, 0), ["C _ / D _"]);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let xml = measures(
+            r#"<measure number="1">
+  <attributes><divisions>2000000000</divisions><key><fifths>5000</fifths></key>
+    <time><beats>3</beats><beat-type>4</beat-type></time></attributes>
+  <note><pitch><step>C</step><octave>20</octave></pitch><duration>2</duration></note>
+  <note><pitch><step>D</step><alter>127</alter><octave>4</octave></pitch><duration>1</duration></note>
+  <note><pitch><step>E</step><octave>4</octave></pitch><duration>99999999999</duration></note>
+  <forward><duration>256</duration></forward>
+  <note><pitch><step>F</step><octave>4</octave></pitch><duration>1</duration></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        assert_eq!(
+            import.warnings().collect::<Vec<_>>(),
+            [
+                "measure 1: skipped 2000000000 divisions of a quarter note",
+                "measure 1: skipped a key with 5000 fifths",
+                "measure 1: read an invalid pitch as a rest",
+                "measure 1: skipped a note without a valid duration",
+                "measure 1: skipped the notes after 64 whole notes",
+            ]
+        );
+        assert_eq!(import.key(), None);
+        // The D is raised by an octave at most, the note after the forward is skipped.
+        assert_eq!(staff(&import, 0)[0], "_ D");
+        let part = import.parts().next().unwrap();
+        let bar = &part.staves().next().unwrap().1[0];
+        assert_eq!(bar.elements().nth(1).unwrap().notes(), [D + OCTAVE]);
+    }
+
+    #[test]
+    fn kinds() {
+        let harmony = |root: &str, kind: &str, degrees: &str| {
+            let xml = measures(&format!(
+                r#"<measure number="1"><harmony><root><root-step>{root}</root-step></root><kind>{kind}</kind>{degrees}</harmony>
+                <note><rest/><duration>4</duration></note></measure>"#
+            ));
+            let import = Import::from_xml(&xml).unwrap();
+            let part = import.parts().next().unwrap();
+            let chords = part.chords().first().map(|bar| bar.to_string());
+            (
+                chords,
+                import.warnings().map(str::to_string).collect::<Vec<_>>(),
+            )
+        };
+        let chord = |root, kind| harmony(root, kind, "").0.unwrap();
+
+        assert_eq!(chord("G", "dominant-ninth"), "G9");
+        assert_eq!(chord("D", "minor-seventh"), "Dm7");
+        assert_eq!(chord("B", "half-diminished"), "Bm7(b5)");
+        assert_eq!(chord("C", "major"), "C");
+        assert_eq!(chord("C", "none"), "_");
+        let altered = harmony(
+            "G",
+            "dominant",
+            "<degree><degree-value>5</degree-value><degree-alter>-1</degree-alter><degree-type>alter</degree-type></degree>",
+        );
+        assert_eq!(altered.0.as_deref(), Some("G7b5"));
+
+        let (chords, warnings) = harmony("C", "Tristan", "");
+        assert_eq!(chords, None);
+        assert_eq!(
+            warnings,
+            [r#"measure 1: skipped the unknown chord kind "Tristan""#]
+        );
+    }
+
+    #[test]
+    fn harmony_offsets() {
+        let xml = measures(
+            r#"<measure number="1">
+  <attributes><divisions>1</divisions></attributes>
+  <note><pitch><step>C</step><octave>4</octave></pitch><duration>2</duration></note>
+  <harmony><root><root-step>F</root-step></root><kind>major</kind><offset>1</offset></harmony>
+  <note><pitch><step>A</step><octave>4</octave></pitch><duration>2</duration></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        let chords = import.parts().next().unwrap().chords();
+        assert_eq!(chords[0].to_string(), "_ F");
+        assert_eq!(chords[0].elements().last().unwrap().duration(), QUARTER);
+    }
+
+    #[test]
+    fn warnings() {
+        let xml = measures(
+            r#"<measure number="1" implicit="yes">
+  <note><grace/><pitch><step>D</step><octave>4</octave></pitch></note>
+  <note><pitch><step>C</step><octave>4</octave></pitch><duration>1</duration></note>
+  <note><unpitched><display-step>E</display-step><display-octave>4</display-octave></unpitched><duration>1</duration></note>
+  <lyric-sheet/>
+</measure>
+<measure number="2">
+  <attributes><time><senza-misura/></time></attributes>
+  <note><pitch><step>E</step><octave>4</octave></pitch><duration>4</duration></note>
+</measure>"#,
+        );
+        let import = Import::from_xml(&xml).unwrap();
+        assert_eq!(
+            import.warnings().collect::<Vec<_>>(),
+            [
+                "measure 1: skipped a grace note",
+                "measure 1: skipped an unpitched note",
+                "measure 1: skipped <lyric-sheet>",
+                "measure 2: skipped an unsupported time signature",
+            ]
+        );
+        // The pickup measure lasts as long as its notes.
+        assert_eq!(staff(&import, 0), ["C _", "E"]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| Import::from_xml(text).err().unwrap().to_string();
+        assert_eq!(
+            error("<html></html>"),
+            "not a MusicXML document (the root element is <html>)"
+        );
+        assert_eq!(
+            error("<score-partwise>"),
+            "invalid XML: line 1: unclosed <score-partwise>"
+        );
+        assert!(matches!(
+            file(b"PK\x03\x04".to_vec()),
+            Err(MusicXmlError::Compressed)
+        ));
+    }
+}
@@ -0,0 +1,693 @@
+use std::fmt::Display;
+
+use super::{Clef, MusicXml, Part, KINDS};
+use crate::{
+    chords::{Chord, Chords},
+    score::Mark,
+    Bar, BarElement, Duration, Note, Spelling, TimeSignature,
+};
+
+/// An XML text being written, indented by two spaces.
+struct Xml {
+    text: String,
+    depth: usize,
+}
+
+/// What is written in all the measures of a document.
+struct Context<'a> {
+    document: &'a MusicXml<'a>,
+    spelling: Spelling,
+    divisions: u32,
+    times: Vec<TimeSignature>,
+}
+
+/// The MusicXML text of a document.
+pub(super) fn document(document: &MusicXml) -> String {
+    let measures = document
+        .parts
+        .iter()
+        .map(Part::measures)
+        .chain(std::iter::once(document.marks.len()))
+        .max()
+        .unwrap_or(0);
+
+    let context = Context {
+        document,
+        spelling: document.key.map_or(Spelling::Sharps, Spelling::Key),
+        divisions: divisions(document),
+        times: times(document, measures),
+    };
+
+    let mut xml = Xml::new();
+    xml.line(r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#);
+    xml.line(concat!(
+        r#"<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN" "#,
+        r#""http://www.musicxml.org/dtds/partwise.dtd">"#
+    ));
+    xml.open("score-partwise", &[("version", "4.0")]);
+    xml.open("work", &[]);
+    xml.leaf("work-title", &[], escape(&document.title));
+    xml.close("work");
+
+    xml.open("part-list", &[]);
+    for (index, part) in document.parts.iter().enumerate() {
+        xml.open("score-part", &[("id", &format!("P{}", index + 1))]);
+        xml.leaf("part-name", &[], escape(&part.name));
+        xml.close("score-part");
+    }
+    xml.close("part-list");
+
+    for (index, part) in document.parts.iter().enumerate() {
+        xml.open("part", &[("id", &format!("P{}", index + 1))]);
+        for measure in 0..measures {
+            context.measure(&mut xml, part, index == 0, measure);
+        }
+        xml.close("part");
+    }
+    xml.close("score-partwise");
+    xml.text
+}
+
+impl Context<'_> {
+    fn measure(&self, xml: &mut Xml, part: &Part, first_part: bool, index: usize) {
+        let no_marks = vec![];
+        let marks = self.document.marks.get(index).unwrap_or(&no_marks);
+        let has = |index: usize, mark: Mark| {
+            self.document
+                .marks
+                .get(index)
+                .is_some_and(|marks| marks.contains(&mark))
+        };
+
+        xml.open("measure", &[("number", &(index + 1).to_string())]);
+        self.attributes(xml, part, index);
+
+        // The left bar line: the start of an ending and of a repeat.
+        let ending = marks.iter().find_map(|m| match m {
+            Mark::Ending(n) => Some(*n),
+            _ => None,
+        });
+        let ending_start = ending.filter(|n| index == 0 || !has(index - 1, Mark::Ending(*n)));
+        let repeat_start = marks.contains(&Mark::RepeatStart);
+        if ending_start.is_some() || repeat_start {
+            xml.open("barline", &[("location", "left")]);
+            if repeat_start {
+                xml.leaf("bar-style", &[], "heavy-light");
+            }
+            if let Some(n) = ending_start {
+                let number = n.to_string();
+                xml.leaf("ending", &[("number", &number), ("type", "start")], &number);
+            }
+            if repeat_start {
+                xml.empty("repeat", &[("direction", "forward")]);
+            }
+            xml.close("barline");
+        }
+
+        if first_part {
+            if let Some(Some(label)) = self.document.labels.get(index) {
+                direction(xml, |xml| xml.leaf("rehearsal", &[], escape(label)));
+            }
+            for mark in marks.iter().filter(|m| m.is_before()) {
+                match mark {
+                    Mark::Segno => direction(xml, |xml| xml.empty("segno", &[])),
+                    Mark::Coda => direction(xml, |xml| xml.empty("coda", &[])),
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(Some(bar)) = part.chords.get(index) {
+            let mut offset = Duration::new(0, 1);
+            for element in bar.elements() {
+                if let BarElement::Chord(chord, _) = element {
+                    self.harmony(xml, chord, offset);
+                }
+                offset += element.duration();
+            }
+        }
+
+        let staves = part.staves.len();
+        let mut position = 0;
+        for (staff, (_, bars)) in part.staves.iter().enumerate() {
+            let staff_number = (staves > 1).then_some(staff + 1);
+            let voices = match bars.get(index).copied().flatten() {
+                Some(bar) => bar.voices().map(|v| v.elements().collect()).collect(),
+                None => vec![vec![]],
+            };
+
+            for (voice, elements) in voices.into_iter().enumerate() {
+                if position > 0 {
+                    xml.open("backup", &[]);
+                    xml.leaf("duration", &[], position);
+                    xml.close("backup");
+                }
+                let number = staff * 4 + voice + 1;
+                position = match elements.is_empty() {
+                    true => self.measure_rest(xml, index, number, staff_number),
+                    false => elements
+                        .into_iter()
+                        .map(|e| self.element(xml, e, number, staff_number))
+                        .sum(),
+                };
+            }
+        }
+
+        if first_part {
+            for mark in marks.iter().filter(|m| m.is_after()) {
+                direction(xml, |xml| xml.leaf("words", &[], mark));
+            }
+        }
+
+        // The right bar line: the end of an ending and of a repeat.
+        let repeat_end = marks.iter().find_map(|m| match m {
+            Mark::RepeatEnd(times) => Some(*times),
+            _ => None,
+        });
+        let ending_stop = ending.filter(|n| !has(index + 1, Mark::Ending(*n)));
+        if ending_stop.is_some() || repeat_end.is_some() {
+            xml.open("barline", &[("location", "right")]);
+            if repeat_end.is_some() {
+                xml.leaf("bar-style", &[], "light-heavy");
+            }
+            if let Some(n) = ending_stop {
+                let kind = match repeat_end {
+                    Some(_) => "stop",
+                    None => "discontinue",
+                };
+                xml.empty("ending", &[("number", &n.to_string()), ("type", kind)]);
+            }
+            match repeat_end {
+                Some(times) if times != 2 => xml.empty(
+                    "repeat",
+                    &[("direction", "backward"), ("times", &times.to_string())],
+                ),
+                Some(_) => xml.empty("repeat", &[("direction", "backward")]),
+                None => {}
+            }
+            xml.close("barline");
+        }
+
+        xml.close("measure");
+    }
+
+    /// The divisions, the key, the time, the staves and the clefs on the first measure, and
+    /// the time when it changes.
+    fn attributes(&self, xml: &mut Xml, part: &Part, index: usize) {
+        let time = &self.times[index];
+        let time_changes = index == 0 || self.times[index - 1] != *time;
+        if !time_changes {
+            return;
+        }
+
+        xml.open("attributes", &[]);
+        if index == 0 {
+            xml.leaf("divisions", &[], self.divisions);
+            if let Some(key) = self.document.key {
+                xml.open("key", &[]);
+                xml.leaf("fifths", &[], key.signature());
+                xml.leaf("mode", &[], key.mode());
+                xml.close("key");
+            }
+        }
+
+        xml.open("time", &[]);
+        let beats = match time.is_additive() {
+            true => {
+                let groups = time.groups().map(|g| g.to_string());
+                groups.collect::<Vec<_>>().join("+")
+            }
+            false => time.numerator().to_string(),
+        };
+        xml.leaf("beats", &[], beats);
+        xml.leaf("beat-type", &[], time.denominator());
+        xml.close("time");
+
+        if index == 0 {
+            let staves = part.staves.len();
+            if staves > 1 {
+                xml.leaf("staves", &[], staves);
+            }
+            for (number, (clef, _)) in part.staves.iter().enumerate() {
+                let number = (number + 1).to_string();
+                let attributes: &[(&str, &str)] = match staves > 1 {
+                    true => &[("number", &number)],
+                    false => &[],
+                };
+                let (sign, line) = match clef {
+                    Clef::Treble => ("G", 2),
+                    Clef::Bass => ("F", 4),
+                };
+                xml.open("clef", attributes);
+                xml.leaf("sign", &[], sign);
+                xml.leaf("line", &[], line);
+                xml.close("clef");
+            }
+        }
+        xml.close("attributes");
+    }
+
+    /// Writes an element of a bar, as notes tied together when it has more than one note
+    /// value, and returns its duration in divisions.
+    fn element(
+        &self,
+        xml: &mut Xml,
+        element: &BarElement,
+        voice: usize,
+        staff: Option<usize>,
+    ) -> u32 {
+        let duration = element.duration();
+        if let BarElement::Chord(chord, _) = element {
+            self.harmony(xml, chord, Duration::ZERO);
+        }
+        let pieces = match duration.denominator().is_power_of_two() && !duration.is_simple() {
+            true => duration.split(),
+            false => vec![duration],
+        };
+        let notes = element.notes();
+        for (index, piece) in pieces.iter().enumerate() {
+            // The rests are not tied.
+            let mut ties = vec![];
+            if index > 0 && !notes.is_empty() {
+                ties.push("stop");
+            }
+            if index + 1 < pieces.len() && !notes.is_empty() {
+                ties.push("start");
+            }
+            self.notes(xml, &notes, *piece, &ties, voice, staff);
+        }
+        self.divisions(duration)
+    }
+
+    /// Writes the notes played together, or a rest without any note.
+    fn notes(
+        &self,
+        xml: &mut Xml,
+        notes: &[Note],
+        duration: Duration,
+        ties: &[&str],
+        voice: usize,
+        staff: Option<usize>,
+    ) {
+        let notes = match notes.is_empty() {
+            true => vec![None],
+            false => notes.iter().copied().map(Some).collect(),
+        };
+        for (index, note) in notes.into_iter().enumerate() {
+            xml.open("note", &[]);
+            if index > 0 {
+                xml.empty("chord", &[]);
+            }
+            match note {
+                Some(note) => {
+                    let (step, alter) = self.pitch(note);
+                    xml.open("pitch", &[]);
+                    xml.leaf("step", &[], step);
+                    if alter != 0 {
+                        xml.leaf("alter", &[], alter);
+                    }
+                    xml.leaf("octave", &[], note.scientific_octave());
+                    xml.close("pitch");
+                }
+                None => xml.empty("rest", &[]),
+            }
+            xml.leaf("duration", &[], self.divisions(duration));
+            for tie in ties {
+                xml.empty("tie", &[("type", tie)]);
+            }
+            xml.leaf("voice", &[], voice);
+            if let Some((kind, dots, tuplet)) = note_type(duration) {
+                xml.leaf("type", &[], kind);
+                for _ in 0..dots {
+                    xml.empty("dot", &[]);
+                }
+                if let Some((actual, normal)) = tuplet {
+                    xml.open("time-modification", &[]);
+                    xml.leaf("actual-notes", &[], actual);
+                    xml.leaf("normal-notes", &[], normal);
+                    xml.close("time-modification");
+                }
+            }
+            if let Some(staff) = staff {
+                xml.leaf("staff", &[], staff);
+            }
+            if !ties.is_empty() {
+                xml.open("notations", &[]);
+                for tie in ties {
+                    xml.empty("tied", &[("type", tie)]);
+                }
+                xml.close("notations");
+            }
+            xml.close("note");
+        }
+    }
+
+    /// Writes a rest lasting the whole measure, and returns its duration in divisions.
+    fn measure_rest(&self, xml: &mut Xml, index: usize, voice: usize, staff: Option<usize>) -> u32 {
+        let duration = self.divisions(self.times[index].duration());
+        xml.open("note", &[]);
+        xml.empty("rest", &[("measure", "yes")]);
+        xml.leaf("duration", &[], duration);
+        xml.leaf("voice", &[], voice);
+        if let Some(staff) = staff {
+            xml.leaf("staff", &[], staff);
+        }
+        xml.close("note");
+        duration
+    }
+
+    /// Writes a chord symbol, at the given offset from the current position.
+    fn harmony(&self, xml: &mut Xml, chord: &Chords, offset: Duration) {
+        let (step, alter) = self.pitch(chord.root());
+        let (kind, degrees) = kind(chord);
+
+        xml.open("harmony", &[]);
+        xml.open("root", &[]);
+        xml.leaf("root-step", &[], step);
+        if alter != 0 {
+            xml.leaf("root-alter", &[], alter);
+        }
+        xml.close("root");
+        xml.leaf("kind", &[("text", chord.suffix())], kind);
+        for (value, alter) in degrees {
+            xml.open("degree", &[]);
+            xml.leaf("degree-value", &[], value);
+            xml.leaf("degree-alter", &[], alter);
+            xml.leaf("degree-type", &[], "alter");
+            xml.close("degree");
+        }
+        if !offset.is_zero() {
+            xml.leaf("offset", &[], self.divisions(offset));
+        }
+        xml.close("harmony");
+    }
+
+    /// The step and the alteration of the note, spelled with the key.
+    fn pitch(&self, note: Note) -> (char, i8) {
+        let name = self.spelling.note(note);
+        let mut chars = name.chars();
+        let step = chars.next().unwrap_or('C');
+        let alter = chars
+            .map(|c| match c {
+                '#' => 1,
+                'b' => -1,
+                _ => 0,
+            })
+            .sum();
+        (step, alter)
+    }
+
+    fn divisions(&self, duration: Duration) -> u32 {
+        duration.numerator() * 4 * self.divisions / duration.denominator()
+    }
+}
+
+/// A direction above the staff.
+fn direction(xml: &mut Xml, content: impl FnOnce(&mut Xml)) {
+    xml.open("direction", &[("placement", "above")]);
+    xml.open("direction-type", &[]);
+    content(xml);
+    xml.close("direction-type");
+    xml.close("direction");
+}
+
+/// The divisions of a quarter note, for all the durations of the document to be whole
+/// numbers.
+fn divisions(document: &MusicXml) -> u32 {
+    let bars = document.parts.iter().flat_map(|part| {
+        let staves = part.staves.iter().flat_map(|(_, bars)| bars.iter());
+        staves.chain(part.chords.iter()).flatten()
+    });
+    let durations = bars.flat_map(|bar: &&Bar| {
+        let elements = bar
+            .voices()
+            .flat_map(|v| v.elements().map(|e| e.duration()));
+        elements.chain(std::iter::once(bar.time_signature().duration()))
+    });
+
+    durations.fold(1, |divisions, duration| {
+        let quarters = duration.numerator() * 4;
+        let needed = duration.denominator() / gcd(duration.denominator(), quarters);
+        divisions / gcd(divisions, needed) * needed
+    })
+}
+
+/// The time signature of each measure: the one of the first bar of the measure, or the
+/// previous one.
+fn times(document: &MusicXml, measures: usize) -> Vec<TimeSignature> {
+    let mut times: Vec<TimeSignature> = vec![];
+    for index in 0..measures {
+        let time = document
+            .parts
+            .iter()
+            .flat_map(|part| {
+                let staves = part.staves.iter().map(|(_, bars)| bars);
+                staves.chain(std::iter::once(&part.chords))
+            })
+            .find_map(|bars| bars.get(index).copied().flatten())
+            .map(|bar| bar.time_signature().clone())
+            .or_else(|| times.last().cloned())
+            .unwrap_or_default();
+        times.push(time);
+    }
+    times
+}
+
+/// The type of a note, its dots and its tuplet (actual and normal notes).
+type NoteType = (&'static str, u32, Option<(u32, u32)>);
+
+/// The type of the note, if the duration can be written as a single note (possibly in a
+/// tuplet).
+fn note_type(duration: Duration) -> Option<NoteType> {
+    [(1, 1), (3, 2), (5, 4), (6, 4), (7, 4)]
+        .into_iter()
+        .find_map(|(actual, normal)| {
+            let written = duration * actual / normal;
+            let tuplet = actual > 1;
+            if !written.is_simple() || tuplet && duration.denominator().is_power_of_two() {
+                return None;
+            }
+            let dots = (written.numerator() + 1).trailing_zeros() - 1;
+            let kind = match written.denominator() >> dots {
+                1 => "whole",
+                2 => "half",
+                4 => "quarter",
+                8 => "eighth",
+                16 => "16th",
+                32 => "32nd",
+                64 => "64th",
+                128 => "128th",
+                _ => return None,
+            };
+            Some((kind, dots, tuplet.then_some((actual, normal))))
+        })
+}
+
+/// The MusicXML kind of the chord, and the alterations of its degrees.
+fn kind(chord: &Chords) -> (&'static str, &'static [(u8, i8)]) {
+    let kind = KINDS
+        .iter()
+        .find(|(suffix, _, _)| *suffix == chord.suffix());
+    match (kind, chord) {
+        (Some((_, kind, degrees)), _) => (kind, degrees),
+        (None, Chords::Major(_, _)) => ("major", &[]),
+        (None, Chords::Minor(_, _)) => ("minor", &[]),
+        (None, Chords::Dominant(_, _)) => ("dominant", &[]),
+        (None, Chords::Diminished(_, _)) => ("diminished", &[]),
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    match b {
+        0 => a.max(1),
+        _ => gcd(b, a % b),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Xml {
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            depth: 0,
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        self.text.push_str(&"  ".repeat(self.depth));
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn tag(name: &str, attributes: &[(&str, &str)]) -> String {
+        let attributes = attributes
+            .iter()
+            .map(|(key, value)| format!(" {key}=\"{}\"", escape(value)));
+        format!("{name}{}", attributes.collect::<String>())
+    }
+
+    fn open(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.line(&format!("<{}>", Self::tag(name, attributes)));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, name: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{name}>"));
+    }
+
+    fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.line(&format!("<{}/>", Self::tag(name, attributes)));
+    }
+
+    fn leaf(&mut self, name: &str, attributes: &[(&str, &str)], text: impl Display) {
+        self.line(&format!("<{}>{text}</{name}>", Self::tag(name, attributes)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        score::{Jump, Measure, Score, Section, Staff},
+        Key, A_SHARP, C, D, E, EIGHTH, F, G, QUARTER, WHOLE,
+    };
+
+    #[test]
+    fn types() {
+        assert_eq!(note_type(QUARTER), Some(("quarter", 0, None)));
+        assert_eq!(note_type(QUARTER.dotted()), Some(("quarter", 1, None)));
+        assert_eq!(note_type(WHOLE.double_dotted()), Some(("whole", 2, None)));
+        assert_eq!(
+            note_type(EIGHTH.triplet()),
+            Some(("eighth", 0, Some((3, 2))))
+        );
+        assert_eq!(note_type(Duration::new(5, 8)), None);
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(kind(&C.maj()).0, "major");
+        assert_eq!(kind(&C.min7b5()).0, "half-diminished");
+        assert_eq!(kind(&C.dom7b5()), ("dominant", &[(5, -1)][..]));
+        assert_eq!(kind(&C.dim7()).0, "diminished-seventh");
+    }
+
+    #[test]
+    fn notes() {
+        let bars = [Bar::new()
+            .with_note(C, 4)
+            .with_note(A_SHARP, EIGHTH.triplet())
+            .with_note(C, EIGHTH.triplet())
+            .with_note(D, EIGHTH.triplet())
+            .with_silence(2)];
+        let xml = MusicXml::new("Notes")
+            .with_key(Key::major(F))
+            .with_part(Part::new("Piano").with_staff(Clef::Treble, &bars))
+            .to_xml();
+
+        assert!(xml.contains("<divisions>3</divisions>"));
+        assert!(xml.contains("<fifths>-1</fifths>"));
+        assert!(xml.contains("<step>B</step>"));
+        assert!(xml.contains("<alter>-1</alter>"));
+        assert!(xml.contains("<octave>3</octave>"));
+        assert!(xml.contains("<actual-notes>3</actual-notes>"));
+        assert!(xml.contains("<duration>6</duration>"));
+        assert!(!xml.contains("<staff>"));
+    }
+
+    #[test]
+    fn ties() {
+        // A 5/8 note is a half tied to an eighth, the dotted quarter rest is not split.
+        let bars = [Bar::new()
+            .with_cluster(&[C, E], Duration::new(5, 8))
+            .with_silence(Duration::new(3, 8))];
+        let xml = MusicXml::new("Ties")
+            .with_part(Part::new("Piano").with_staff(Clef::Treble, &bars))
+            .to_xml();
+
+        let types = xml
+            .split("<type>")
+            .skip(1)
+            .map(|s| &s[..s.find('<').unwrap()])
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["half", "half", "eighth", "eighth", "quarter"]);
+        assert_eq!(xml.matches(r#"<tie type="start"/>"#).count(), 2);
+        assert_eq!(xml.matches(r#"<tied type="stop"/>"#).count(), 2);
+        assert_eq!(xml.matches("<tie ").count(), 4);
+        assert!(xml.contains(
+            r#"<duration>4</duration>
+        <tie type="start"/>
+        <voice>1</voice>
+        <type>half</type>
+        <notations>
+          <tied type="start"/>
+        </notations>"#
+        ));
+    }
+
+    #[test]
+    fn staves() {
+        let rh = [Bar::new().with_note(E, 2).with_note(G, 2)];
+        let lh = [Bar::new()
+            .with_chord(C.maj(), 1)
+            .with_voice(crate::Voice::new().with_note(C, 1))];
+        let piano = Part::new("Piano")
+            .with_staff(Clef::Treble, &rh)
+            .with_staff(Clef::Bass, &lh);
+        let xml = MusicXml::new("Staves").with_part(piano).to_xml();
+
+        assert!(xml.contains(r#"<clef number="2">"#));
+        assert_eq!(xml.matches("<backup>").count(), 2);
+        assert_eq!(xml.matches("<chord/>").count(), 2);
+        assert!(xml.contains("<voice>6</voice>"));
+        assert!(xml.contains("<staff>2</staff>"));
+    }
+
+    #[test]
+    fn score() {
+        let chords = |chord| Measure::chords(Bar::new().with_chord(chord, 1));
+        let melody = |bar| Measure::new().with_bar(Staff::Melody, bar);
+        let section = Section::new("A & B")
+            .with_measure(
+                melody(Bar::new().with_note(C, 1))
+                    .with_bar(
+                        Staff::Chords,
+                        Bar::new()
+                            .with_chord(C.maj7(), 2)
+                            .with_chord(A_SHARP.dom7(), 2),
+                    )
+                    .with_mark(Mark::RepeatStart)
+                    .with_mark(Mark::Segno),
+            )
+            .with_measure(
+                chords(F.maj7())
+                    .with_mark(Mark::Ending(1))
+                    .with_mark(Mark::RepeatEnd(3)),
+            )
+            .with_measure(
+                melody(Bar::new().with_note(C, 1))
+                    .with_mark(Mark::Ending(2))
+                    .with_mark(Mark::DalSegno(Jump::End)),
+            );
+        let score = Score::new("Form").with_section(section);
+        let xml = MusicXml::from_score(&score).to_xml();
+
+        assert_eq!(xml.matches("<part id=").count(), 1);
+        assert!(xml.contains("<rehearsal>A &amp; B</rehearsal>"));
+        assert!(xml.contains("<segno/>"));
+        assert!(xml.contains(r#"<repeat direction="forward"/>"#));
+        assert!(xml.contains(r#"<repeat direction="backward" times="3"/>"#));
+        assert!(xml.contains(r#"<ending number="1" type="stop"/>"#));
+        assert!(xml.contains(r#"<ending number="2" type="discontinue"/>"#));
+        assert!(xml.contains("<words>D.S.</words>"));
+        assert!(xml.contains(r#"<rest measure="yes"/>"#));
+        assert!(xml.contains("<offset>2</offset>"));
+    }
+}
@@ -0,0 +1,273 @@
+/// An element of an XML document, with its attributes, its child elements and its text.
+#[derive(Debug, Default)]
+pub(super) struct Element {
+    pub(super) name: String,
+    attributes: Vec<(String, String)>,
+    pub(super) children: Vec<Element>,
+    text: String,
+}
+
+/// An XML text being read.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+/// Reads the root element of an XML document. The declaration, the processing
+/// instructions, the comments and the document type are skipped.
+pub(super) fn parse(text: &str) -> Result<Element, String> {
+    let mut parser = Parser { text, position: 0 };
+    parser.misc()?;
+    if !parser.rest().starts_with('<') {
+        return Err(parser.error("no root element"));
+    }
+    let root = parser.element()?;
+    parser.misc()?;
+    match parser.rest().is_empty() {
+        true => Ok(root),
+        false => Err(parser.error("content after the root element")),
+    }
+}
+
+impl Element {
+    pub(super) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(super) fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub(super) fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The text of the element, without the spaces around it.
+    pub(super) fn text(&self) -> &str {
+        self.text.trim()
+    }
+
+    /// The text of a child element.
+    pub(super) fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(Element::text)
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.position].matches('\n').count() + 1;
+        format!("line {line}: {message}")
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips the text up to the end of the delimiter.
+    fn skip_past(&mut self, delimiter: &str) -> Result<(), String> {
+        match self.rest().find(delimiter) {
+            Some(index) => {
+                self.position += index + delimiter.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("missing {delimiter}"))),
+        }
+    }
+
+    /// Skips the spaces, the comments, the processing instructions and the document type.
+    fn misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_spaces();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Skips the document type, with its internal subset between brackets.
+    fn doctype(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        for (index, c) in self.rest().char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => {
+                    self.position += index + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unclosed document type"))
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error("missing name"));
+        }
+        self.position += end;
+        Ok(rest[..end].to_string())
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        self.position += 1;
+        let mut element = Element {
+            name: self.name()?,
+            ..Element::default()
+        };
+
+        loop {
+            self.skip_spaces();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            } else if rest.starts_with('>') {
+                self.position += 1;
+                break;
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("unclosed <{}>", element.name)));
+            }
+            let attribute = self.name()?;
+            self.skip_spaces();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(&format!("missing value of {attribute}")));
+            }
+            self.position += 1;
+            self.skip_spaces();
+            let value = self.quoted()?;
+            element.attributes.push((attribute, value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.position += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{name}> closes <{}>", element.name)));
+                }
+                self.skip_spaces();
+                self.skip_past(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+                let delimiter = if rest.starts_with("<?") { "?>" } else { "-->" };
+                self.skip_past(delimiter)?;
+            } else if let Some(data) = rest.strip_prefix("<![CDATA[") {
+                let end = data
+                    .find("]]>")
+                    .ok_or_else(|| self.error("unclosed CDATA section"))?;
+                element.text.push_str(&data[..end]);
+                self.position += "<![CDATA[".len() + end + "]]>".len();
+            } else if rest.starts_with('<') {
+                element.children.push(self.element()?);
+            } else if rest.is_empty() {
+                return Err(self.error(&format!("unclosed <{}>", element.name)));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let text = unescape(&rest[..end]).map_err(|e| self.error(&e))?;
+                element.text.push_str(&text);
+                self.position += end;
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let quote = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error("missing quote")),
+        };
+        let end = rest[1..]
+            .find(quote)
+            .ok_or_else(|| self.error("unclosed quote"))?;
+        let value = unescape(&rest[1..1 + end]).map_err(|e| self.error(&e))?;
+        self.position += end + 2;
+        Ok(value)
+    }
+}
+
+/// Replaces the entities and the character references.
+fn unescape(text: &str) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        result.push_str(&rest[..index]);
+        let end = rest[index..]
+            .find(';')
+            .ok_or_else(|| format!("unclosed entity in {text:?}"))?;
+        let entity = &rest[index + 1..index + end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|decimal| decimal.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        result.push(c.ok_or_else(|| format!("unknown entity &{entity};"))?);
+        rest = &rest[index + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elements() {
+        let text = r#"<?xml version="1.0"?>
+<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD" "partwise.dtd" [ <!ENTITY x "y"> ]>
+<!-- a comment -->
+<root version='4.0'>
+  <a id="1">Rock &amp; roll</a>
+  <b/><a id="2"><![CDATA[<raw>]]> &#233;&#x41;</a>
+</root>"#;
+        let root = parse(text).unwrap();
+        assert_eq!(root.name, "root");
+        assert_eq!(root.attribute("version"), Some("4.0"));
+        assert_eq!(root.children.len(), 3);
+        assert_eq!(root.child_text("a"), Some("Rock & roll"));
+        let texts = root.children("a").map(|a| a.text()).collect::<Vec<_>>();
+        assert_eq!(texts, ["Rock & roll", "<raw> éA"]);
+        assert!(root.child("b").is_some_and(|b| b.children.is_empty()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("<a><b></a>").unwrap_err(), "line 1: </a> closes <b>");
+        assert_eq!(parse("<a>\n<b>").unwrap_err(), "line 2: unclosed <b>");
+        assert_eq!(
+            parse("<a>&nbsp;</a>").unwrap_err(),
+            "line 1: unknown entity &nbsp;"
+        );
+        assert_eq!(parse("text").unwrap_err(), "line 1: no root element");
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fmt::{Debug, Display, LowerHex, UpperHex},
     ops::{Add, Sub},
+    str::FromStr,
 };
 
 use crate::{
@@ -13,6 +14,10 @@ use super::{Tone, OCTAVE};
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Note(i8);
 
+/// The error of reading a note name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseNoteError(String);
+
 impl Note {
     const TRANSLATE: i8 = 3;
     const OCTAVE_SIZE: i8 = OCTAVE.inner() as i8;
@@ -50,6 +55,32 @@ impl Note {
         }
     }
 
+    /// Returns the octave in scientific pitch notation, where the octaves start at C
+    /// (eg. the B just below C4 is in the octave 3).
+    pub fn scientific_octave(&self) -> i8 {
+        self.0.div_euclid(Self::OCTAVE_SIZE) + 4
+    }
+
+    /// Returns the pitch class of the note, from 0 (C) to 11 (B), regardless of the octave.
+    pub fn pitch_class(&self) -> u8 {
+        self.0.rem_euclid(Self::OCTAVE_SIZE) as u8
+    }
+
+    /// Returns the MIDI number of the note (60 for C4), kept inside the MIDI range.
+    pub fn midi(&self) -> u8 {
+        (self.0 as i16 + 60).clamp(0, 127) as u8
+    }
+
+    /// Returns the frequency of the note in Hz, in equal temperament with A4 at 440 Hz.
+    pub fn frequency(&self) -> f64 {
+        440.0 * 2f64.powf((self.0 as f64 - 9.0) / 12.0)
+    }
+
+    /// Returns the note of a MIDI number (C4 for 60).
+    pub fn from_midi(number: u8) -> Self {
+        Self(number.min(127) as i8 - 60)
+    }
+
     pub fn perfect_fifth(&self) -> Self {
         self + PERFECT_5TH
     }
@@ -295,6 +326,50 @@ impl From<Note> for i8 {
     }
 }
 
+/// Reads a note name: a letter and its accidentals, eg. "C", "F#" or "Bb". The note is the
+/// one of the constants, from [`A`] to [`G_SHARP`] (eg. [`B`] for "Cb").
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// assert_eq!("Bb".parse(), Ok(A_SHARP));
+/// assert_eq!("F##".parse(), Ok(G));
+/// assert!("H".parse::<Note>().is_err());
+/// ```
+impl FromStr for Note {
+    type Err = ParseNoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseNoteError(s.to_string());
+        let mut chars = s.chars();
+        let note = match chars.next().ok_or_else(error)? {
+            'A' => A,
+            'B' => B,
+            'C' => C,
+            'D' => D,
+            'E' => E,
+            'F' => F,
+            'G' => G,
+            _ => return Err(error()),
+        };
+        let note = chars.try_fold(note, |note, c| match c {
+            '#' | '♯' => note.0.checked_add(1).map(Note).ok_or_else(error),
+            'b' | '♭' => note.0.checked_sub(1).map(Note).ok_or_else(error),
+            _ => Err(error()),
+        })?;
+        Ok(note.base())
+    }
+}
+
+impl Display for ParseNoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid note name {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseNoteError {}
+
 impl Add<Tone> for Note {
     type Output = Self;
 
@@ -354,10 +429,35 @@ pub const G: Note = Note(7);
 pub const G_SHARP: Note = Note(8);
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
     use crate::{SEMI_TONE, TONE};
 
+    #[test]
+    fn from_str() {
+        assert_eq!("C".parse(), Ok(C));
+        assert_eq!("C#".parse(), Ok(C_SHARP));
+        assert_eq!("Eb".parse(), Ok(D_SHARP));
+        assert_eq!("Cb".parse(), Ok(B));
+        assert_eq!("B#".parse(), Ok(C));
+        assert_eq!("A♭".parse(), Ok(G_SHARP));
+        for error in ["", "c", "Cx", "C #"] {
+            assert_eq!(
+                error.parse::<Note>(),
+                Err(ParseNoteError(error.to_string()))
+            );
+        }
+        // Too many accidentals to stay a note.
+        let sharps = format!("C{}", "#".repeat(130));
+        assert_eq!(sharps.parse::<Note>(), Err(ParseNoteError(sharps.clone())));
+        assert_eq!(format!("C{}", "b".repeat(12)).parse(), Ok(C));
+        assert_eq!(
+            "H".parse::<Note>().unwrap_err().to_string(),
+            r#"invalid note name "H""#
+        );
+    }
+
     #[test]
     fn display() {
         assert_eq!(format!("{C}"), "C");
@@ -524,6 +624,41 @@ mod tests {
         assert_eq!(C - B, SEMI_TONE);
     }
 
+    #[test]
+    fn scientific_octave() {
+        assert_eq!(C.scientific_octave(), 4);
+        assert_eq!(B.scientific_octave(), 3);
+        assert_eq!(A.scientific_octave(), 3);
+        assert_eq!(Note::from(11).scientific_octave(), 4);
+        assert_eq!(Note::from(-13).scientific_octave(), 2);
+    }
+
+    #[test]
+    fn pitch_class() {
+        assert_eq!(C.pitch_class(), 0);
+        assert_eq!(A.pitch_class(), 9);
+        assert_eq!(B.pitch_class(), 11);
+        assert_eq!((C - OCTAVE).pitch_class(), 0);
+        assert_eq!(Note::from(-15).pitch_class(), 9);
+    }
+
+    #[test]
+    fn midi() {
+        assert_eq!(C.midi(), 60);
+        assert_eq!(A.midi(), 57);
+        assert_eq!((C - OCTAVE).midi(), 48);
+        assert_eq!(Note::from(100).midi(), 127);
+        assert_eq!(Note::from_midi(57), A);
+        assert_eq!(Note::from_midi(C.midi()), C);
+    }
+
+    #[test]
+    fn frequency() {
+        assert_eq!((A + OCTAVE).frequency(), 440.0);
+        assert_eq!(A.frequency(), 220.0);
+        assert!((C.frequency() - 261.626).abs() < 0.001);
+    }
+
     #[test]
     fn perfect_fifth() {
         assert_eq!(D.perfect_fifth().base(), A.base());
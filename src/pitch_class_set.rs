@@ -0,0 +1,505 @@
+use std::{
+    fmt::Display,
+    ops::{BitAnd, BitOr, Not},
+};
+
+use crate::Note;
+
+/// A set of pitch classes (C being 0, B being 11), whatever the octave of the notes,
+/// held in a 12 bits mask.
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let triad: PitchClassSet = [E, G, B].into_iter().collect();
+/// assert!(triad.contains(G + OCTAVE));
+/// assert_eq!(triad.to_string(), "[4, 7, 11]");
+/// assert_eq!(triad.prime_form().to_string(), "[0, 3, 7]");
+/// assert_eq!(triad.forte_number(), "3-11");
+/// assert_eq!(triad.interval_vector(), [0, 0, 1, 1, 1, 0]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PitchClassSet(u16);
+
+/// The Forte numbers of the set classes from 3 to 6 pitch classes, with a set of each class
+/// (`A` and `B` being 10 and 11). The classes of 7 to 9 pitch classes have the number of
+/// their complement.
+const FORTE: [(&str, &str); 129] = [
+    ("3-1", "012"),
+    ("3-2", "013"),
+    ("3-3", "014"),
+    ("3-4", "015"),
+    ("3-5", "016"),
+    ("3-6", "024"),
+    ("3-7", "025"),
+    ("3-8", "026"),
+    ("3-9", "027"),
+    ("3-10", "036"),
+    ("3-11", "037"),
+    ("3-12", "048"),
+    ("4-1", "0123"),
+    ("4-2", "0124"),
+    ("4-3", "0134"),
+    ("4-4", "0125"),
+    ("4-5", "0126"),
+    ("4-6", "0127"),
+    ("4-7", "0145"),
+    ("4-8", "0156"),
+    ("4-9", "0167"),
+    ("4-10", "0235"),
+    ("4-11", "0135"),
+    ("4-12", "0236"),
+    ("4-13", "0136"),
+    ("4-14", "0237"),
+    ("4-Z15", "0146"),
+    ("4-16", "0157"),
+    ("4-17", "0347"),
+    ("4-18", "0147"),
+    ("4-19", "0148"),
+    ("4-20", "0158"),
+    ("4-21", "0246"),
+    ("4-22", "0247"),
+    ("4-23", "0257"),
+    ("4-24", "0248"),
+    ("4-25", "0268"),
+    ("4-26", "0358"),
+    ("4-27", "0258"),
+    ("4-28", "0369"),
+    ("4-Z29", "0137"),
+    ("5-1", "01234"),
+    ("5-2", "01235"),
+    ("5-3", "01245"),
+    ("5-4", "01236"),
+    ("5-5", "01237"),
+    ("5-6", "01256"),
+    ("5-7", "01267"),
+    ("5-8", "02346"),
+    ("5-9", "01246"),
+    ("5-10", "01346"),
+    ("5-11", "02347"),
+    ("5-Z12", "01356"),
+    ("5-13", "01248"),
+    ("5-14", "01257"),
+    ("5-15", "01268"),
+    ("5-16", "01347"),
+    ("5-Z17", "01348"),
+    ("5-Z18", "01457"),
+    ("5-19", "01367"),
+    ("5-20", "01378"),
+    ("5-21", "01458"),
+    ("5-22", "01478"),
+    ("5-23", "02357"),
+    ("5-24", "01357"),
+    ("5-25", "02358"),
+    ("5-26", "02458"),
+    ("5-27", "01358"),
+    ("5-28", "02368"),
+    ("5-29", "01368"),
+    ("5-30", "01468"),
+    ("5-31", "01369"),
+    ("5-32", "01469"),
+    ("5-33", "02468"),
+    ("5-34", "02469"),
+    ("5-35", "02479"),
+    ("5-Z36", "01247"),
+    ("5-Z37", "03458"),
+    ("5-Z38", "01258"),
+    ("6-1", "012345"),
+    ("6-2", "012346"),
+    ("6-Z3", "012356"),
+    ("6-Z4", "012456"),
+    ("6-5", "012367"),
+    ("6-Z6", "012567"),
+    ("6-7", "012678"),
+    ("6-8", "023457"),
+    ("6-9", "012357"),
+    ("6-Z10", "013457"),
+    ("6-Z11", "012457"),
+    ("6-Z12", "012467"),
+    ("6-Z13", "013467"),
+    ("6-14", "013458"),
+    ("6-15", "012458"),
+    ("6-16", "014568"),
+    ("6-Z17", "012478"),
+    ("6-18", "012578"),
+    ("6-Z19", "013478"),
+    ("6-20", "014589"),
+    ("6-21", "023468"),
+    ("6-22", "012468"),
+    ("6-Z23", "023568"),
+    ("6-Z24", "013468"),
+    ("6-Z25", "013568"),
+    ("6-Z26", "013578"),
+    ("6-27", "013469"),
+    ("6-Z28", "013569"),
+    ("6-Z29", "013689"),
+    ("6-30", "013679"),
+    ("6-31", "013589"),
+    ("6-32", "024579"),
+    ("6-33", "023579"),
+    ("6-34", "013579"),
+    ("6-35", "02468A"),
+    ("6-Z36", "012347"),
+    ("6-Z37", "012348"),
+    ("6-Z38", "012378"),
+    ("6-Z39", "023458"),
+    ("6-Z40", "012358"),
+    ("6-Z41", "012368"),
+    ("6-Z42", "012369"),
+    ("6-Z43", "012568"),
+    ("6-Z44", "012569"),
+    ("6-Z45", "023469"),
+    ("6-Z46", "012469"),
+    ("6-Z47", "012479"),
+    ("6-Z48", "012579"),
+    ("6-Z49", "013479"),
+    ("6-Z50", "014679"),
+];
+
+impl PitchClassSet {
+    const ALL: u16 = 0xFFF;
+
+    /// The empty set.
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// The set of a mask, the bit `n` being the pitch class `n` (the bits above 11 are
+    /// ignored).
+    pub fn from_mask(mask: u16) -> Self {
+        Self(mask & Self::ALL)
+    }
+
+    pub fn mask(&self) -> u16 {
+        self.0
+    }
+
+    /// Adds the pitch class of the note.
+    pub fn with(self, note: Note) -> Self {
+        Self(self.0 | 1 << note.pitch_class())
+    }
+
+    /// Whether the pitch class of the note is in the set.
+    pub fn contains(&self, note: Note) -> bool {
+        self.0 & 1 << note.pitch_class() != 0
+    }
+
+    /// Whether all the pitch classes of the set are in the other one.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the pitch classes, from 0 to 11.
+    pub fn pitch_classes(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..12).filter(|pc| self.0 & 1 << pc != 0)
+    }
+
+    /// Returns the notes of the pitch classes, from C4 to B4.
+    pub fn notes(&self) -> impl Iterator<Item = Note> + '_ {
+        self.pitch_classes().map(|pc| Note::from(pc as i8))
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// The pitch classes which are not in the set.
+    pub fn complement(&self) -> Self {
+        Self(!self.0 & Self::ALL)
+    }
+
+    /// Transposes the set up by the semitones (Tn), down for negative semitones.
+    pub fn transpose(&self, semitones: i8) -> Self {
+        let n = semitones.rem_euclid(12) as u32;
+        Self(((self.0 << n) | (self.0 >> (12 - n))) & Self::ALL)
+    }
+
+    /// Inverts the set around C (I), the pitch class `n` becoming `12 - n`.
+    pub fn invert(&self) -> Self {
+        self.pitch_classes()
+            .fold(Self::new(), |set, pc| Self(set.0 | 1 << ((12 - pc) % 12)))
+    }
+
+    /// Returns the normal form of the set: its most compact ordering, and between equally
+    /// compact ones the most packed to the left (Forte's convention).
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// let set: PitchClassSet = [B, D, F, G_SHARP, E].into_iter().collect();
+    /// assert_eq!(set.normal_form(), [2, 4, 5, 8, 11]);
+    /// ```
+    pub fn normal_form(&self) -> Vec<u8> {
+        let pcs = self.pitch_classes().collect::<Vec<_>>();
+        (0..pcs.len())
+            .map(|start| {
+                let mut rotation = pcs[start..].to_vec();
+                rotation.extend(&pcs[..start]);
+                rotation
+            })
+            .min_by_key(|rotation| packing(rotation))
+            .unwrap_or_default()
+    }
+
+    /// Returns the prime form of the set: its normal form or the one of its inversion,
+    /// transposed to 0, whichever is the most packed to the left.
+    pub fn prime_form(&self) -> Self {
+        let zero = |set: Self| {
+            let normal = set.normal_form();
+            let first = normal.first().copied().unwrap_or_default();
+            (packing(&normal), set.transpose(-(first as i8)))
+        };
+        let (original, inverted) = (zero(*self), zero(self.invert()));
+        match inverted.0 < original.0 {
+            true => inverted.1,
+            false => original.1,
+        }
+    }
+
+    /// Returns the number of pairs of pitch classes at each interval class, from the minor
+    /// second (or major seventh) to the tritone.
+    pub fn interval_vector(&self) -> [u8; 6] {
+        let pcs = self.pitch_classes().collect::<Vec<_>>();
+        let mut vector = [0; 6];
+        for (index, low) in pcs.iter().enumerate() {
+            for high in &pcs[index + 1..] {
+                let interval = high - low;
+                vector[interval.min(12 - interval) as usize - 1] += 1;
+            }
+        }
+        vector
+    }
+
+    /// Returns the Forte number of the set class, eg. "4-Z15". The sets of less than 3 or
+    /// more than 9 pitch classes are numbered by their interval class, eg. "2-5" for a
+    /// fourth and "10-5" for its complement.
+    pub fn forte_number(&self) -> String {
+        let prime = self.prime_form();
+        classes(self.len())
+            .into_iter()
+            .find(|(_, set)| *set == prime)
+            .map(|(number, _)| number)
+            .expect("PitchClassSet: every set class has a Forte number")
+    }
+
+    /// Returns the prime form of the set class which has the same interval vector, but is
+    /// not related by transposition or inversion, if any (eg. 4-Z29 for 4-Z15).
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::*;
+    ///
+    /// let set: PitchClassSet = [C, C_SHARP, E, F_SHARP].into_iter().collect();
+    /// let other = set.z_relation().unwrap();
+    /// assert_eq!(other.forte_number(), "4-Z29");
+    /// assert_eq!(other.interval_vector(), set.interval_vector());
+    /// ```
+    pub fn z_relation(&self) -> Option<Self> {
+        let prime = self.prime_form();
+        let vector = self.interval_vector();
+        classes(self.len())
+            .into_iter()
+            .map(|(_, set)| set)
+            .find(|set| *set != prime && set.interval_vector() == vector)
+    }
+
+    /// Whether the sets have the same interval vector, without being related by
+    /// transposition or inversion.
+    pub fn is_z_related(&self, other: &Self) -> bool {
+        self.interval_vector() == other.interval_vector() && self.prime_form() != other.prime_form()
+    }
+}
+
+/// How compact and packed to the left an ordering is: its span, then the intervals from
+/// its first pitch class to the others.
+fn packing(ordering: &[u8]) -> (u8, Vec<u8>) {
+    let first = ordering.first().copied().unwrap_or_default();
+    let interval = |pc: &u8| (pc + 12 - first) % 12;
+    let span = ordering.last().map_or(0, interval);
+    (span, ordering.iter().map(interval).collect())
+}
+
+/// The Forte numbers and the prime forms of the set classes of the given number of pitch
+/// classes.
+fn classes(len: usize) -> Vec<(String, PitchClassSet)> {
+    let set = |pcs: &[u32]| {
+        pcs.iter().fold(PitchClassSet::new(), |set, pc| {
+            PitchClassSet(set.0 | 1 << pc)
+        })
+    };
+    match len {
+        0 => vec![("0-1".to_string(), PitchClassSet::new())],
+        1 => vec![("1-1".to_string(), set(&[0]))],
+        2 => (1..=6)
+            .map(|interval| (format!("2-{interval}"), set(&[0, interval])))
+            .collect(),
+        3..=6 => FORTE
+            .iter()
+            .filter(|(_, pcs)| pcs.len() == len)
+            .map(|(number, pcs)| {
+                let pcs = pcs
+                    .chars()
+                    .filter_map(|c| c.to_digit(12))
+                    .collect::<Vec<_>>();
+                (number.to_string(), set(&pcs).prime_form())
+            })
+            .collect(),
+        _ => classes(12 - len)
+            .into_iter()
+            .map(|(number, set)| {
+                let (_, ordinal) = number.split_once('-').unwrap_or_default();
+                (format!("{len}-{ordinal}"), set.complement().prime_form())
+            })
+            .collect(),
+    }
+}
+
+impl FromIterator<Note> for PitchClassSet {
+    fn from_iter<T: IntoIterator<Item = Note>>(notes: T) -> Self {
+        notes
+            .into_iter()
+            .fold(Self::new(), |set, note| set.with(note))
+    }
+}
+
+impl BitOr for PitchClassSet {
+    type Output = PitchClassSet;
+
+    fn bitor(self, other: Self) -> Self::Output {
+        self.union(&other)
+    }
+}
+
+impl BitAnd for PitchClassSet {
+    type Output = PitchClassSet;
+
+    fn bitand(self, other: Self) -> Self::Output {
+        self.intersection(&other)
+    }
+}
+
+impl Not for PitchClassSet {
+    type Output = PitchClassSet;
+
+    fn not(self) -> Self::Output {
+        self.complement()
+    }
+}
+
+impl Display for PitchClassSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pcs = self
+            .pitch_classes()
+            .map(|pc| pc.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "[{pcs}]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, C, D, E, F_SHARP, G, OCTAVE};
+
+    fn set(pcs: &[u8]) -> PitchClassSet {
+        pcs.iter().fold(PitchClassSet::new(), |set, pc| {
+            set.with(Note::from(*pc as i8))
+        })
+    }
+
+    #[test]
+    fn operations() {
+        let c = set(&[0, 4, 7]);
+        let a = set(&[9, 0, 4]);
+        assert_eq!(c | a, set(&[0, 4, 7, 9]));
+        assert_eq!(c & a, set(&[0, 4]));
+        assert_eq!((!c).len(), 9);
+        assert!(set(&[0, 4]).is_subset(&c));
+        assert!(!a.is_subset(&c));
+        assert!(c.contains(C - OCTAVE));
+        assert_eq!(PitchClassSet::from_mask(0xF001).mask(), 1);
+        assert_eq!(c.notes().collect::<Vec<_>>(), [C, E, G]);
+        assert!(PitchClassSet::default().is_empty());
+    }
+
+    #[test]
+    fn transpositions_and_inversions() {
+        let c = set(&[0, 4, 7]);
+        assert_eq!(c.transpose(2), [D, F_SHARP, A].into_iter().collect());
+        assert_eq!(c.transpose(-1), set(&[11, 3, 6]));
+        assert_eq!(c.transpose(12), c);
+        assert_eq!(c.invert(), set(&[0, 8, 5]));
+        assert_eq!(c.invert().invert(), c);
+    }
+
+    #[test]
+    fn forms() {
+        assert_eq!(set(&[0, 4, 7]).normal_form(), [0, 4, 7]);
+        assert_eq!(set(&[11, 0, 4]).normal_form(), [11, 0, 4]);
+        assert_eq!(set(&[0, 4, 7]).prime_form(), set(&[0, 3, 7]));
+        // Forte's packing to the left, where Rahn's gives [0, 1, 5, 6, 8].
+        assert_eq!(set(&[0, 1, 5, 6, 8]).prime_form(), set(&[0, 1, 3, 7, 8]));
+        assert_eq!(set(&[]).normal_form(), []);
+        assert_eq!(set(&[3]).prime_form(), set(&[0]));
+    }
+
+    #[test]
+    fn forte_numbers() {
+        assert_eq!(set(&[0, 4, 7, 10]).forte_number(), "4-27");
+        assert_eq!(set(&[0, 2, 4, 5, 7, 9, 11]).forte_number(), "7-35");
+        assert_eq!(set(&[0, 1, 4, 7, 8]).complement().forte_number(), "7-22");
+        assert_eq!(set(&[0, 2, 4, 6, 8, 10]).forte_number(), "6-35");
+        assert_eq!(set(&[0, 5]).forte_number(), "2-5");
+        assert_eq!(set(&[]).forte_number(), "0-1");
+        assert_eq!(PitchClassSet::from_mask(0xFFF).forte_number(), "12-1");
+        assert_eq!(set(&[0, 1, 3, 4, 6, 7, 9, 10]).forte_number(), "8-28");
+    }
+
+    #[test]
+    fn table() {
+        // The table holds each set class once.
+        let counts = [1, 1, 6, 12, 29, 38, 50, 38, 29, 12, 6, 1, 1];
+        for (len, count) in counts.into_iter().enumerate() {
+            let primes = (0..4096u16)
+                .map(PitchClassSet)
+                .filter(|set| set.len() == len)
+                .map(|set| set.prime_form())
+                .collect::<std::collections::HashSet<_>>();
+            let classes = classes(len);
+            assert_eq!(primes.len(), count, "{len}");
+            assert_eq!(classes.len(), count, "{len}");
+            assert!(classes.iter().all(|(_, set)| primes.contains(set)), "{len}");
+        }
+    }
+
+    #[test]
+    fn z_relations() {
+        // Only the classes numbered with a Z have a Z-related class.
+        for len in 0..=12 {
+            for (number, set) in classes(len) {
+                assert_eq!(set.z_relation().is_some(), number.contains('Z'), "{number}");
+            }
+        }
+        let z17 = set(&[0, 1, 2, 4, 7, 8]);
+        let z43 = set(&[0, 1, 2, 5, 6, 8]);
+        assert!(z17.is_z_related(&z43));
+        assert_eq!(z17.z_relation(), Some(z43));
+        assert!(!z17.is_z_related(&z17.transpose(3).invert()));
+        assert_eq!(set(&[0, 1, 4, 6]).interval_vector(), [1, 1, 1, 1, 1, 1]);
+    }
+}
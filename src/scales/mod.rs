@@ -1,4 +1,4 @@
-use crate::{Note, NoteStepperIterator, Tone};
+use crate::{Note, NoteStepperIterator, PitchClassSet, Tone};
 use std::fmt::{Display, LowerHex, UpperHex};
 
 mod major;
@@ -52,6 +52,11 @@ impl Scales {
         Self::minor(name, notes)
     }
 
+    /// Returns the pitch classes of the scale.
+    pub fn pitch_classes(&self) -> PitchClassSet {
+        self.inner_notes().iter().copied().collect()
+    }
+
     fn inner_notes(&self) -> &Vec<Note> {
         match self {
             Scales::Major(_, notes) => notes,
@@ -106,6 +111,12 @@ impl Scale for Scales {
     }
 }
 
+impl From<&Scales> for PitchClassSet {
+    fn from(scale: &Scales) -> Self {
+        scale.pitch_classes()
+    }
+}
+
 impl Display for Scales {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.tonic(), self.inner_name())
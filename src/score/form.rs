@@ -0,0 +1,289 @@
+use super::Measure;
+use std::{collections::HashMap, fmt::Display};
+
+/// A mark of the form of a piece, set on a measure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mark {
+    /// The repeat starts at the beginning of the measure.
+    RepeatStart,
+    /// The repeat ends at the end of the measure, and the repeated measures are played the
+    /// given number of times (usually 2).
+    RepeatEnd(u8),
+    /// The measure belongs to the n-th ending (volta): it is played on the n-th pass only.
+    Ending(u8),
+    /// The target of a D.S.
+    Segno,
+    /// The measure starts the coda.
+    Coda,
+    /// After a D.C. or a D.S. al Coda, the piece jumps to the coda at the end of the measure.
+    ToCoda,
+    /// After a D.C. or a D.S. al Fine, the piece ends with the measure.
+    Fine,
+    /// Da Capo: at the end of the measure, the piece goes back to its beginning.
+    DaCapo(Jump),
+    /// Dal Segno: at the end of the measure, the piece goes back to the segno.
+    DalSegno(Jump),
+}
+
+/// How the piece continues after a D.C. or a D.S.: to its end, to the Fine, or to the coda.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Jump {
+    End,
+    Fine,
+    Coda,
+}
+
+impl Mark {
+    /// Returns true if the mark is written at the beginning of the measure.
+    pub fn is_before(&self) -> bool {
+        matches!(self, Mark::Ending(_) | Mark::Segno | Mark::Coda)
+    }
+
+    /// Returns true if the mark is written at the end of the measure.
+    pub fn is_after(&self) -> bool {
+        matches!(
+            self,
+            Mark::ToCoda | Mark::Fine | Mark::DaCapo(_) | Mark::DalSegno(_)
+        )
+    }
+}
+
+/// The indexes of the measures in the order they are played.
+///
+/// Repeats are played the number of times of their end mark; an end repeat without a start
+/// repeat goes back to the beginning of the piece, or to the end of the previous repeat. The
+/// endings are played on their pass. After a D.C. or a D.S. the repeats are not taken again
+/// and only the last ending of each repeat is played.
+pub(super) fn unroll(measures: &[&Measure]) -> Vec<usize> {
+    let mut played = vec![];
+    let mut jumps_back: HashMap<usize, u8> = HashMap::new();
+    let mut repeat_start = 0;
+    let mut pass = 1;
+    let mut in_endings = false;
+    let mut returning = false;
+    let mut jump: Option<Jump> = None;
+
+    let mut i = 0;
+    while i < measures.len() {
+        let measure = measures[i];
+
+        let back = std::mem::take(&mut returning);
+        if measure.has_mark(Mark::RepeatStart) && !back {
+            repeat_start = i;
+            pass = 1;
+        }
+
+        match ending(measure) {
+            Some(n) => {
+                in_endings = true;
+                let skip = match jump {
+                    None => n != pass,
+                    Some(_) => n != last_ending(measures, i),
+                };
+                if skip {
+                    i += 1;
+                    continue;
+                }
+            }
+            None if in_endings && !back => {
+                in_endings = false;
+                repeat_start = i;
+                pass = 1;
+            }
+            None => in_endings = false,
+        }
+
+        played.push(i);
+
+        if jump.is_some() {
+            if jump == Some(Jump::Fine) && measure.has_mark(Mark::Fine) {
+                break;
+            }
+            if jump == Some(Jump::Coda) && measure.has_mark(Mark::ToCoda) {
+                if let Some(coda) = find(measures, i + 1, Mark::Coda) {
+                    i = coda;
+                    continue;
+                }
+            }
+        }
+
+        if let Some(times) = repeat_end(measure) {
+            let count = jumps_back.entry(i).or_insert(0);
+            if jump.is_none() && *count + 1 < times {
+                *count += 1;
+                pass += 1;
+                i = repeat_start;
+                returning = true;
+                continue;
+            }
+            if ending(measure).is_none() {
+                repeat_start = i + 1;
+                pass = 1;
+            }
+        }
+
+        if jump.is_none() {
+            let target = measure.marks().find_map(|m| match m {
+                Mark::DaCapo(j) => Some((*j, 0)),
+                Mark::DalSegno(j) => Some((*j, find(measures, 0, Mark::Segno).unwrap_or(0))),
+                _ => None,
+            });
+            if let Some((j, target)) = target {
+                jump = Some(j);
+                i = target;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    played
+}
+
+fn ending(measure: &Measure) -> Option<u8> {
+    measure.marks().find_map(|m| match m {
+        Mark::Ending(n) => Some(*n),
+        _ => None,
+    })
+}
+
+fn repeat_end(measure: &Measure) -> Option<u8> {
+    measure.marks().find_map(|m| match m {
+        Mark::RepeatEnd(times) => Some(*times),
+        _ => None,
+    })
+}
+
+/// The number of the last ending of the group of endings which contains the measure.
+fn last_ending(measures: &[&Measure], index: usize) -> u8 {
+    measures[index..]
+        .iter()
+        .map_while(|m| ending(m))
+        .max()
+        .unwrap_or(1)
+}
+
+/// The index of the first measure with the mark, from the given index.
+fn find(measures: &[&Measure], from: usize, mark: Mark) -> Option<usize> {
+    (from..measures.len()).find(|i| measures[*i].has_mark(mark))
+}
+
+impl Display for Jump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Jump::End => Ok(()),
+            Jump::Fine => write!(f, " al Fine"),
+            Jump::Coda => write!(f, " al Coda"),
+        }
+    }
+}
+
+impl Display for Mark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mark::RepeatStart => write!(f, "|:"),
+            Mark::RepeatEnd(2) => write!(f, ":|"),
+            Mark::RepeatEnd(times) => write!(f, ":| x{times}"),
+            Mark::Ending(n) => write!(f, "{n}."),
+            Mark::Segno => write!(f, "Segno"),
+            Mark::Coda => write!(f, "Coda"),
+            Mark::ToCoda => write!(f, "To Coda"),
+            Mark::Fine => write!(f, "Fine"),
+            Mark::DaCapo(jump) => write!(f, "D.C.{jump}"),
+            Mark::DalSegno(jump) => write!(f, "D.S.{jump}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Measures with the given marks, and the order they are played in.
+    fn unrolled(marks: &[&[Mark]]) -> Vec<usize> {
+        let measures = marks
+            .iter()
+            .map(|marks| {
+                marks
+                    .iter()
+                    .fold(Measure::new(), |m, mark| m.with_mark(*mark))
+            })
+            .collect::<Vec<_>>();
+        unroll(&measures.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn linear() {
+        assert_eq!(unrolled(&[&[], &[], &[]]), [0, 1, 2]);
+        assert!(unrolled(&[]).is_empty());
+    }
+
+    #[test]
+    fn repeats() {
+        let order = unrolled(&[&[], &[Mark::RepeatStart], &[Mark::RepeatEnd(2)], &[]]);
+        assert_eq!(order, [0, 1, 2, 1, 2, 3]);
+
+        let order = unrolled(&[&[], &[Mark::RepeatEnd(3)], &[]]);
+        assert_eq!(order, [0, 1, 0, 1, 0, 1, 2]);
+
+        // The second end repeat goes back to the end of the first one.
+        let order = unrolled(&[&[Mark::RepeatEnd(2)], &[], &[Mark::RepeatEnd(2)]]);
+        assert_eq!(order, [0, 0, 1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn endings() {
+        let order = unrolled(&[
+            &[Mark::RepeatStart],
+            &[],
+            &[Mark::Ending(1), Mark::RepeatEnd(2)],
+            &[Mark::Ending(2)],
+            &[],
+        ]);
+        assert_eq!(order, [0, 1, 2, 0, 1, 3, 4]);
+
+        let order = unrolled(&[
+            &[],
+            &[Mark::Ending(1), Mark::RepeatEnd(2)],
+            &[Mark::Ending(2), Mark::RepeatEnd(2)],
+            &[Mark::Ending(3)],
+        ]);
+        assert_eq!(order, [0, 1, 0, 2, 0, 3]);
+    }
+
+    #[test]
+    fn da_capo() {
+        let order = unrolled(&[&[], &[Mark::Fine], &[], &[Mark::DaCapo(Jump::Fine)]]);
+        assert_eq!(order, [0, 1, 2, 3, 0, 1]);
+
+        // The repeats are not taken after the D.C., and only the last ending is played.
+        let order = unrolled(&[
+            &[],
+            &[Mark::Ending(1), Mark::RepeatEnd(2)],
+            &[Mark::Ending(2), Mark::DaCapo(Jump::End)],
+        ]);
+        assert_eq!(order, [0, 1, 0, 2, 0, 2]);
+    }
+
+    #[test]
+    fn dal_segno_al_coda() {
+        let order = unrolled(&[
+            &[],
+            &[Mark::Segno],
+            &[Mark::ToCoda],
+            &[Mark::DalSegno(Jump::Coda)],
+            &[Mark::Coda],
+            &[],
+        ]);
+        assert_eq!(order, [0, 1, 2, 3, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Mark::DalSegno(Jump::Coda).to_string(), "D.S. al Coda");
+        assert_eq!(Mark::DaCapo(Jump::End).to_string(), "D.C.");
+        assert_eq!(Mark::RepeatEnd(3).to_string(), ":| x3");
+        assert_eq!(Mark::Ending(1).to_string(), "1.");
+    }
+}
@@ -0,0 +1,347 @@
+//! Scores and lead sheets: bars organized in measures, staves and sections, with the form of
+//! the piece (repeats, endings, D.C., D.S. and coda).
+//!
+//! A [`Score`] is an ordered list of [`Section`]s (A, B, bridge...), each one made of
+//! [`Measure`]s. A measure holds one bar per [`Staff`] (right hand, left hand, bass, chords...)
+//! and the [`Mark`]s of the form. [`Score::unroll`] follows the form to give the measures in
+//! the order they are played.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{score::*, *};
+//!
+//! let c7 = Measure::chords(Bar::new().with_chord(C.dom7(), 1)).with_mark(Mark::RepeatStart);
+//! let f7 = Measure::chords(Bar::new().with_chord(F.dom7(), 1)).with_mark(Mark::RepeatEnd(2));
+//! let score = Score::new("Vamp").with_section(Section::new("A").with_measure(c7).with_measure(f7));
+//! assert_eq!(score.unroll().len(), 4);
+//! println!("{score}");
+//! ```
+
+use crate::Bar;
+use std::fmt::{Display, LowerHex, UpperHex};
+
+mod form;
+
+pub use form::*;
+
+/// A staff of a score, played by one hand or one instrument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Staff {
+    Melody,
+    Chords,
+    RightHand,
+    LeftHand,
+    Bass,
+}
+
+/// A measure of a score: the bar played on each staff and the marks of the form.
+#[derive(Default)]
+pub struct Measure {
+    bars: Vec<(Staff, Bar)>,
+    marks: Vec<Mark>,
+}
+
+/// A section of a score (eg. A, B, bridge), made of measures.
+pub struct Section {
+    label: String,
+    measures: Vec<Measure>,
+}
+
+/// A score: a title and the sections of the piece, in order.
+pub struct Score {
+    title: String,
+    sections: Vec<Section>,
+}
+
+impl Measure {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A measure of a lead sheet, with only the chords staff.
+    pub fn chords(bar: Bar) -> Self {
+        Self::new().with_bar(Staff::Chords, bar)
+    }
+
+    /// Sets the bar of the staff, replacing the previous one if any.
+    pub fn with_bar(self, staff: Staff, bar: Bar) -> Self {
+        let mut bars = self.bars;
+        match bars.iter_mut().find(|(s, _)| *s == staff) {
+            Some(existing) => existing.1 = bar,
+            None => bars.push((staff, bar)),
+        }
+        Self { bars, ..self }
+    }
+
+    pub fn with_mark(self, mark: Mark) -> Self {
+        let mut marks = self.marks;
+        marks.push(mark);
+        Self { marks, ..self }
+    }
+
+    /// Returns the bar of the staff, if the staff plays in this measure.
+    pub fn bar(&self, staff: Staff) -> Option<&Bar> {
+        self.bars.iter().find(|(s, _)| *s == staff).map(|(_, b)| b)
+    }
+
+    /// Returns the staves with their bar, in the order they were added.
+    pub fn bars(&self) -> impl Iterator<Item = &(Staff, Bar)> {
+        self.bars.iter()
+    }
+
+    pub fn marks(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter()
+    }
+
+    pub fn has_mark(&self, mark: Mark) -> bool {
+        self.marks.contains(&mark)
+    }
+}
+
+impl Section {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            measures: vec![],
+        }
+    }
+
+    pub fn with_measure(self, measure: Measure) -> Self {
+        let mut measures = self.measures;
+        measures.push(measure);
+        Self { measures, ..self }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn measures(&self) -> impl Iterator<Item = &Measure> {
+        self.measures.iter()
+    }
+
+    /// Returns the staves played in the section, in the order they first appear.
+    pub fn staves(&self) -> Vec<Staff> {
+        staves(self.measures())
+    }
+}
+
+impl Score {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            sections: vec![],
+        }
+    }
+
+    pub fn with_section(self, section: Section) -> Self {
+        let mut sections = self.sections;
+        sections.push(section);
+        Self { sections, ..self }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    /// Returns all the measures as written, section after section.
+    pub fn measures(&self) -> impl Iterator<Item = &Measure> {
+        self.sections.iter().flat_map(|s| s.measures())
+    }
+
+    /// Returns the staves played in the score, in the order they first appear.
+    pub fn staves(&self) -> Vec<Staff> {
+        staves(self.measures())
+    }
+
+    /// Returns the measures in the order they are played, following the repeats, the
+    /// endings and the jumps (D.C., D.S., coda) of the form.
+    pub fn unroll(&self) -> Vec<&Measure> {
+        let measures = self.measures().collect::<Vec<_>>();
+        form::unroll(&measures)
+            .into_iter()
+            .map(|i| measures[i])
+            .collect()
+    }
+
+    /// Returns the bars of the staff in the order they are played, `None` for the measures
+    /// where the staff does not play.
+    pub fn unroll_staff(&self, staff: Staff) -> Vec<Option<&Bar>> {
+        self.unroll().into_iter().map(|m| m.bar(staff)).collect()
+    }
+}
+
+fn staves<'a>(measures: impl Iterator<Item = &'a Measure>) -> Vec<Staff> {
+    let mut staves = vec![];
+    for (staff, _) in measures.flat_map(|m| m.bars()) {
+        if !staves.contains(staff) {
+            staves.push(*staff);
+        }
+    }
+    staves
+}
+
+impl Display for Staff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Staff::Melody => write!(f, "Melody"),
+            Staff::Chords => write!(f, "Chords"),
+            Staff::RightHand => write!(f, "RH"),
+            Staff::LeftHand => write!(f, "LH"),
+            Staff::Bass => write!(f, "Bass"),
+        }
+    }
+}
+
+impl Section {
+    /// One line per staff, with the marks of the form around the bars, eg.
+    /// `Chords: |: C7 | F7 :|`.
+    fn render(&self, bar_format: impl Fn(&Bar) -> String) -> String {
+        let mut lines = vec![format!("[{}]", self.label)];
+        for staff in self.staves() {
+            let mut line = String::from("|");
+            for measure in self.measures() {
+                if measure.has_mark(Mark::RepeatStart) {
+                    line.push(':');
+                }
+                for mark in measure.marks().filter(|m| m.is_before()) {
+                    line.push_str(&format!(" {mark}"));
+                }
+
+                // A staff without a bar rests, `%` would repeat the previous bar.
+                let bar = measure.bar(staff).map_or("_".to_string(), &bar_format);
+                line.push_str(&format!(" {bar} "));
+
+                for mark in measure.marks().filter(|m| m.is_after()) {
+                    line.push_str(&format!("{mark} "));
+                }
+                if let Some(Mark::RepeatEnd(times)) =
+                    measure.marks().find(|m| matches!(m, Mark::RepeatEnd(_)))
+                {
+                    if *times > 2 {
+                        line.push_str(&format!("x{times} "));
+                    }
+                    line.push(':');
+                }
+                line.push('|');
+            }
+            lines.push(format!("{staff}: {line}"));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(|b| format!("{b}")))
+    }
+}
+
+impl UpperHex for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(|b| format!("{b:X}")))
+    }
+}
+
+impl LowerHex for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(|b| format!("{b:x}")))
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)?;
+        for section in self.sections() {
+            write!(f, "\n{section}")?;
+        }
+        Ok(())
+    }
+}
+
+impl UpperHex for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)?;
+        for section in self.sections() {
+            write!(f, "\n{section:X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl LowerHex for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)?;
+        for section in self.sections() {
+            write!(f, "\n{section:x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C, D, G};
+
+    #[test]
+    fn staves() {
+        let measure = Measure::new()
+            .with_bar(Staff::RightHand, Bar::new().with_note(C, 1))
+            .with_bar(Staff::LeftHand, Bar::new().with_chord(C.maj7(), 1))
+            .with_bar(Staff::RightHand, Bar::new().with_note(G, 1));
+        assert_eq!(measure.bars().count(), 2);
+        assert_eq!(measure.bar(Staff::RightHand).unwrap().to_string(), "G");
+        assert!(measure.bar(Staff::Bass).is_none());
+
+        let score = Score::new("Test").with_section(
+            Section::new("A")
+                .with_measure(measure)
+                .with_measure(Measure::new().with_bar(Staff::Bass, Bar::new().with_note(C, 1))),
+        );
+        assert_eq!(
+            score.staves(),
+            [Staff::RightHand, Staff::LeftHand, Staff::Bass]
+        );
+        let bass = score.unroll_staff(Staff::Bass);
+        assert!(bass[0].is_none() && bass[1].is_some());
+    }
+
+    #[test]
+    fn display() {
+        let score = Score::new("II-V-I").with_section(
+            Section::new("A")
+                .with_measure(
+                    Measure::chords(Bar::new().with_chord(D.min7(), 1))
+                        .with_mark(Mark::RepeatStart),
+                )
+                .with_measure(Measure::chords(Bar::new().with_chord(G.dom7(), 1)))
+                .with_measure(
+                    Measure::chords(Bar::new().with_chord(C.maj7(), 1))
+                        .with_mark(Mark::RepeatEnd(2)),
+                ),
+        );
+        assert_eq!(
+            score.to_string(),
+            "II-V-I\n[A]\nChords: |: Dm7 | G7 | Cmaj7 :|"
+        );
+
+        // A staff without a bar rests for the measure.
+        let score = Score::new("Walk").with_section(
+            Section::new("A")
+                .with_measure(Measure::chords(Bar::new().with_chord(C.maj7(), 1)))
+                .with_measure(
+                    Measure::chords(Bar::new().with_chord(G.dom7(), 1))
+                        .with_bar(Staff::Bass, Bar::new().with_note(G, 1)),
+                ),
+        );
+        assert_eq!(
+            score.to_string(),
+            "Walk\n[A]\nChords: | Cmaj7 | G7 |\nBass: | _ | G |"
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Offline audio rendering: bars played by simple synthesized instruments, written to a
+//! WAV file.
+//!
+//! A [`Synth`] holds [`Part`]s of bars, each played by an [`Instrument`]: an oscillator
+//! ([`Waveform`]) or a sum of partials, shaped by an ADSR [`Envelope`], with a gain. The
+//! parts are mixed into mono samples, written as 16 bits PCM or 32 bits float. No audio
+//! device is needed, the file is only bytes.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{synth::*, timeline::TempoMap, *};
+//!
+//! let piano = Part::new(Instrument::piano())
+//!     .with_chord(D.min7(), 1)
+//!     .with_chord(G.dom7(), 1)
+//!     .with_chord(C.maj7(), 1);
+//! let synth = Synth::new()
+//!     .with_sample_rate(8000)
+//!     .with_tempo(TempoMap::new(120.0))
+//!     .with_part(piano);
+//!
+//! let bytes = synth.to_wav();
+//! assert_eq!(&bytes[..4], b"RIFF");
+//! assert_eq!(&bytes[8..12], b"WAVE");
+//! ```
+
+mod render;
+mod write;
+
+use crate::{
+    chords::Chords, groove::Groove, timeline::TempoMap, tuning::Tuning, Bar, BarElement, Duration,
+    Note, TimeSignature,
+};
+
+/// The shape of an oscillator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+/// An ADSR envelope: the attack, decay and release times in seconds, and the sustain level
+/// (from 0 to 1) held until the end of the note.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Envelope {
+    attack: f64,
+    decay: f64,
+    sustain: f64,
+    release: f64,
+}
+
+/// A synthesized instrument: partials of an oscillator, shaped by an envelope.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instrument {
+    waveform: Waveform,
+    /// The partials, as the ratio of their frequency to the one of the note and their gain.
+    partials: Vec<(f64, f64)>,
+    envelope: Envelope,
+    gain: f64,
+}
+
+/// The layout of the samples of a WAV file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 16 bits signed integers (PCM).
+    #[default]
+    Int16,
+    /// 32 bits floats (IEEE float).
+    Float32,
+}
+
+/// A sequence of bars played by an instrument.
+pub struct Part {
+    instrument: Instrument,
+    groove: Groove,
+    time_signature: TimeSignature,
+    bars: Vec<Bar>,
+}
+
+/// The parts of a piece, rendered to audio.
+pub struct Synth {
+    sample_rate: u32,
+    format: SampleFormat,
+    tempo: TempoMap,
+    tuning: Tuning,
+    parts: Vec<Part>,
+}
+
+impl Waveform {
+    /// The value of the waveform, from -1 to 1, at a phase from 0 to 1 (the waveforms start
+    /// at 0, rising, except the square one).
+    pub fn value(&self, phase: f64) -> f64 {
+        let phase = phase.rem_euclid(1.0);
+        match self {
+            Waveform::Sine => (phase * std::f64::consts::TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).rem_euclid(1.0) - 0.5).abs(),
+            Waveform::Saw => 2.0 * (phase + 0.5).rem_euclid(1.0) - 1.0,
+            Waveform::Square => match phase < 0.5 {
+                true => 1.0,
+                false => -1.0,
+            },
+        }
+    }
+}
+
+impl Envelope {
+    pub fn new(attack: f64, decay: f64, sustain: f64, release: f64) -> Self {
+        assert!(
+            attack >= 0.0 && decay >= 0.0 && release >= 0.0,
+            "Envelope: the times must not be negative"
+        );
+        assert!(
+            (0.0..=1.0).contains(&sustain),
+            "Envelope: the sustain {sustain} is not in 0..=1"
+        );
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+
+    pub fn attack(&self) -> f64 {
+        self.attack
+    }
+
+    pub fn decay(&self) -> f64 {
+        self.decay
+    }
+
+    pub fn sustain(&self) -> f64 {
+        self.sustain
+    }
+
+    pub fn release(&self) -> f64 {
+        self.release
+    }
+
+    /// The level of the envelope, from 0 to 1, at a time (in seconds) from the start of a
+    /// note held during the given seconds.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::synth::Envelope;
+    ///
+    /// let envelope = Envelope::new(0.1, 0.2, 0.5, 0.5);
+    /// assert_eq!(envelope.level(0.05, 1.0), 0.5);
+    /// assert_eq!(envelope.level(0.1, 1.0), 1.0);
+    /// assert_eq!(envelope.level(0.5, 1.0), 0.5);
+    /// assert_eq!(envelope.level(1.25, 1.0), 0.25);
+    /// assert_eq!(envelope.level(1.5, 1.0), 0.0);
+    /// ```
+    pub fn level(&self, time: f64, held: f64) -> f64 {
+        if time < 0.0 {
+            return 0.0;
+        }
+        if time <= held {
+            return self.held(time);
+        }
+        match self.release > 0.0 {
+            true => self.held(held) * (1.0 - (time - held) / self.release).max(0.0),
+            false => 0.0,
+        }
+    }
+
+    /// The level while the note is held.
+    fn held(&self, time: f64) -> f64 {
+        if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        }
+    }
+}
+
+impl Default for Envelope {
+    /// A short attack and release, the note being held at its full level.
+    fn default() -> Self {
+        Self::new(0.01, 0.0, 1.0, 0.05)
+    }
+}
+
+impl Instrument {
+    /// An oscillator with the default envelope and a gain of 0.25, to mix a few notes
+    /// without clipping.
+    pub fn new(waveform: Waveform) -> Self {
+        Self {
+            waveform,
+            partials: vec![(1.0, 1.0)],
+            envelope: Envelope::default(),
+            gain: 0.25,
+        }
+    }
+
+    /// A piano-ish additive instrument: six decreasing harmonics of a sine, with a quick
+    /// attack and a long decay.
+    pub fn piano() -> Self {
+        Self::new(Waveform::Sine)
+            .with_partials(&[
+                (1.0, 1.0),
+                (2.0, 0.5),
+                (3.0, 0.3),
+                (4.0, 0.15),
+                (5.0, 0.08),
+                (6.0, 0.04),
+            ])
+            .with_envelope(Envelope::new(0.005, 1.2, 0.2, 0.3))
+    }
+
+    pub fn with_envelope(self, envelope: Envelope) -> Self {
+        Self { envelope, ..self }
+    }
+
+    /// Sets the gain of each note of the instrument, from 0 to 1.
+    pub fn with_gain(self, gain: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&gain),
+            "Instrument: the gain {gain} is not in 0..=1"
+        );
+        Self { gain, ..self }
+    }
+
+    /// Sets the partials of the instrument, as the ratio of their frequency to the one of
+    /// the note and their relative gain, eg. `(2.0, 0.5)` for the octave at half the gain of
+    /// `(1.0, 1.0)`. The sum of the partials is scaled back to the gain of the instrument.
+    pub fn with_partials(self, partials: &[(f64, f64)]) -> Self {
+        assert!(
+            !partials.is_empty(),
+            "Instrument: an instrument needs a partial"
+        );
+        Self {
+            partials: partials.to_vec(),
+            ..self
+        }
+    }
+
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    pub fn partials(&self) -> &[(f64, f64)] {
+        &self.partials
+    }
+
+    pub fn envelope(&self) -> Envelope {
+        self.envelope
+    }
+
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+impl Part {
+    /// An empty part in 4/4, played straight at the full velocity of the groove.
+    pub fn new(instrument: Instrument) -> Self {
+        Self {
+            instrument,
+            groove: Groove::new(),
+            time_signature: TimeSignature::common(),
+            bars: vec![],
+        }
+    }
+
+    /// Sets the groove the bars are played with (the velocities, the swing...).
+    pub fn with_groove(self, groove: Groove) -> Self {
+        Self { groove, ..self }
+    }
+
+    /// Sets the time signature of the bars created by the elements added to the part.
+    pub fn with_time_signature(self, time_signature: TimeSignature) -> Self {
+        Self {
+            time_signature,
+            ..self
+        }
+    }
+
+    pub fn with_bar(self, bar: Bar) -> Self {
+        let mut bars = self.bars;
+        bars.push(bar);
+        Self { bars, ..self }
+    }
+
+    pub fn with_bars(self, bars: impl IntoIterator<Item = Bar>) -> Self {
+        bars.into_iter().fold(self, |part, bar| part.with_bar(bar))
+    }
+
+    pub fn with_note(self, note: Note, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Note(note, duration.into()))
+    }
+
+    pub fn with_chord(self, chord: Chords, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Chord(chord, duration.into()))
+    }
+
+    pub fn with_cluster(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Cluster(notes.to_vec(), duration.into()))
+    }
+
+    pub fn with_notes(self, notes: &[Note], duration: impl Into<Duration>) -> Self {
+        let duration = duration.into();
+        notes
+            .iter()
+            .fold(self, |part, note| part.with_note(*note, duration))
+    }
+
+    pub fn with_silence(self, duration: impl Into<Duration>) -> Self {
+        self.with_element(BarElement::Silence(duration.into()))
+    }
+
+    /// Adds an element to the last bar, or to a new bar if it does not fit in the last one.
+    pub fn with_element(self, element: BarElement) -> Self {
+        let mut bars = self.bars;
+        let bar = match bars.pop() {
+            Some(bar)
+                if bar.time_signature() == &self.time_signature
+                    && bar.duration() + element.duration() <= self.time_signature.duration() =>
+            {
+                bar
+            }
+            Some(bar) => {
+                bars.push(bar);
+                Bar::new().with_time_signature(self.time_signature.clone())
+            }
+            None => Bar::new().with_time_signature(self.time_signature.clone()),
+        };
+        bars.push(bar.with_element(element));
+        Self { bars, ..self }
+    }
+
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    pub fn bars(&self) -> impl Iterator<Item = &Bar> {
+        self.bars.iter()
+    }
+}
+
+impl Synth {
+    /// An empty piece rendered at 44100 Hz in 16 bits, with a tempo of 120, in the equal
+    /// temperament.
+    pub fn new() -> Self {
+        Self {
+            sample_rate: 44100,
+            format: SampleFormat::Int16,
+            tempo: TempoMap::default(),
+            tuning: Tuning::equal(),
+            parts: vec![],
+        }
+    }
+
+    pub fn with_sample_rate(self, sample_rate: u32) -> Self {
+        assert!(sample_rate > 0, "Synth: the sample rate must not be 0");
+        Self {
+            sample_rate,
+            ..self
+        }
+    }
+
+    pub fn with_format(self, format: SampleFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    pub fn with_tempo(self, tempo: TempoMap) -> Self {
+        Self { tempo, ..self }
+    }
+
+    /// Sets the tuning giving the frequencies of the notes.
+    pub fn with_tuning(self, tuning: Tuning) -> Self {
+        Self { tuning, ..self }
+    }
+
+    pub fn with_part(self, part: Part) -> Self {
+        let mut parts = self.parts;
+        parts.push(part);
+        Self { parts, ..self }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn format(&self) -> SampleFormat {
+        self.format
+    }
+
+    pub fn tempo(&self) -> &TempoMap {
+        &self.tempo
+    }
+
+    pub fn tuning(&self) -> Tuning {
+        self.tuning
+    }
+
+    pub fn parts(&self) -> impl Iterator<Item = &Part> {
+        self.parts.iter()
+    }
+
+    /// Renders the parts into mono samples, from -1 to 1 (the mix being clipped). The
+    /// samples last until the end of the release of the last note.
+    pub fn samples(&self) -> Vec<f32> {
+        render::samples(self)
+    }
+
+    /// The bytes of the WAV file.
+    pub fn to_wav(&self) -> Vec<u8> {
+        write::wav(self.sample_rate, self.format, &self.samples())
+    }
+
+    /// Writes the WAV file.
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_wav())
+    }
+}
+
+impl Default for Synth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waveforms() {
+        for waveform in [Waveform::Sine, Waveform::Triangle, Waveform::Saw] {
+            assert!(waveform.value(0.0).abs() < 1e-9, "{waveform:?}");
+        }
+        assert!((Waveform::Sine.value(0.25) - 1.0).abs() < 1e-9);
+        assert_eq!(Waveform::Triangle.value(0.25), 1.0);
+        assert_eq!(Waveform::Triangle.value(0.75), -1.0);
+        assert_eq!(Waveform::Saw.value(0.25), 0.5);
+        assert_eq!(Waveform::Saw.value(0.75), -0.5);
+        assert_eq!(Waveform::Square.value(0.25), 1.0);
+        assert_eq!(Waveform::Square.value(1.75), -1.0);
+    }
+
+    #[test]
+    fn envelopes() {
+        let envelope = Envelope::new(0.0, 0.0, 1.0, 0.0);
+        assert_eq!(envelope.level(0.0, 1.0), 1.0);
+        assert_eq!(envelope.level(1.0, 1.0), 1.0);
+        assert_eq!(envelope.level(1.01, 1.0), 0.0);
+        assert_eq!(envelope.level(-0.1, 1.0), 0.0);
+
+        // A note released during its attack fades from the level it reached.
+        let envelope = Envelope::new(1.0, 0.0, 1.0, 1.0);
+        assert_eq!(envelope.level(0.5, 0.5), 0.5);
+        assert_eq!(envelope.level(1.0, 0.5), 0.25);
+    }
+
+    #[test]
+    fn parts() {
+        let part = Part::new(Instrument::new(Waveform::Square).with_gain(0.5))
+            .with_note(crate::C, 2)
+            .with_note(crate::D, 2)
+            .with_note(crate::E, 1);
+        assert_eq!(part.bars().count(), 2);
+        assert_eq!(part.instrument().gain(), 0.5);
+        assert_eq!(Instrument::piano().partials().len(), 6);
+    }
+}
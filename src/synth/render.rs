@@ -0,0 +1,131 @@
+use crate::timeline::Timeline;
+
+use super::{Instrument, Synth};
+
+/// A note played by an instrument.
+struct Voice<'a> {
+    instrument: &'a Instrument,
+    /// The start of the note, in seconds.
+    start: f64,
+    /// How long the note is held before its release, in seconds.
+    held: f64,
+    frequency: f64,
+    /// The gain of the instrument, scaled by the velocity of the note.
+    gain: f64,
+}
+
+/// Mixes the notes of all the parts. The velocity of each note (127 being the loudest)
+/// scales the gain of its instrument.
+pub(super) fn samples(synth: &Synth) -> Vec<f32> {
+    let mut voices = vec![];
+    for part in &synth.parts {
+        let timeline = Timeline::new(&part.bars, &synth.tempo);
+        for played in part.groove.apply(&timeline) {
+            for note in played.element().notes() {
+                voices.push(Voice {
+                    instrument: &part.instrument,
+                    start: played.start().max(0.0),
+                    held: played.duration(),
+                    frequency: synth.tuning.frequency(note),
+                    gain: part.instrument.gain * played.velocity() as f64 / 127.0,
+                });
+            }
+        }
+    }
+
+    let rate = synth.sample_rate as f64;
+    let end = voices
+        .iter()
+        .map(|voice| voice.start + voice.held + voice.instrument.envelope.release)
+        .fold(0.0, f64::max);
+    let mut mix = vec![0.0; (end * rate).ceil() as usize];
+    for voice in &voices {
+        voice.render(&mut mix, rate);
+    }
+    mix.into_iter()
+        .map(|sample| sample.clamp(-1.0, 1.0) as f32)
+        .collect()
+}
+
+impl Voice<'_> {
+    /// Adds the samples of the note to the mix.
+    fn render(&self, mix: &mut [f64], rate: f64) {
+        let instrument = self.instrument;
+        let envelope = instrument.envelope;
+        let total: f64 = instrument.partials.iter().map(|(_, gain)| gain).sum();
+        let first = (self.start * rate).ceil() as usize;
+        let last = ((self.start + self.held + envelope.release) * rate).ceil() as usize;
+
+        for (index, sample) in mix.iter_mut().enumerate().take(last).skip(first) {
+            let time = index as f64 / rate - self.start;
+            let level = envelope.level(time, self.held);
+            if level == 0.0 {
+                continue;
+            }
+            let value: f64 = instrument
+                .partials
+                .iter()
+                .map(|(ratio, gain)| {
+                    gain * instrument.waveform.value(time * self.frequency * ratio)
+                })
+                .sum();
+            *sample += self.gain * level * value / total;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{synth::*, timeline::TempoMap, A, C, E, G, OCTAVE};
+
+    fn synth(part: Part) -> Synth {
+        Synth::new()
+            .with_sample_rate(8000)
+            .with_tempo(TempoMap::new(60.0))
+            .with_part(part)
+    }
+
+    #[test]
+    fn length() {
+        let instrument =
+            Instrument::new(Waveform::Sine).with_envelope(Envelope::new(0.0, 0.0, 1.0, 0.5));
+        let samples = synth(Part::new(instrument).with_note(C, 4)).samples();
+        // A quarter note at 60, then the release.
+        assert_eq!(samples.len(), 12000);
+        assert!(samples[..8000].iter().any(|s| s.abs() > 0.1));
+        assert!(samples.last().unwrap().abs() < 0.01);
+        assert!(Synth::new().samples().is_empty());
+    }
+
+    #[test]
+    fn frequency() {
+        // A4 played with a square wave during 4 s: two changes of sign per period.
+        let instrument =
+            Instrument::new(Waveform::Square).with_envelope(Envelope::new(0.0, 0.0, 1.0, 0.0));
+        let samples = synth(Part::new(instrument).with_note(A + OCTAVE, 1)).samples();
+        let changes = samples
+            .windows(2)
+            .filter(|pair| pair[0].signum() != pair[1].signum())
+            .count();
+        assert!((changes as i32 - 4 * 440 * 2).abs() <= 2, "{changes}");
+    }
+
+    #[test]
+    fn mix() {
+        let loud = Instrument::new(Waveform::Square)
+            .with_gain(1.0)
+            .with_envelope(Envelope::new(0.0, 0.0, 1.0, 0.0));
+        let part = Part::new(loud)
+            .with_groove(crate::groove::Groove::new().with_velocity(127))
+            .with_cluster(&[C, E, G], 4);
+        let samples = synth(part).samples();
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        assert!(samples.contains(&1.0));
+
+        // The velocity (80 by default) scales the gain.
+        let soft =
+            Instrument::new(Waveform::Square).with_envelope(Envelope::new(0.0, 0.0, 1.0, 0.0));
+        let samples = synth(Part::new(soft).with_note(C, 4)).samples();
+        assert_eq!(samples[1], (0.25 * 80.0 / 127.0) as f32);
+    }
+}
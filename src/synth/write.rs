@@ -0,0 +1,94 @@
+use super::SampleFormat;
+
+/// The bytes of a mono WAV file: a RIFF header, the format chunk (with a fact chunk for
+/// the floats), then the samples in little endian.
+pub(super) fn wav(sample_rate: u32, format: SampleFormat, samples: &[f32]) -> Vec<u8> {
+    let (tag, bits): (u16, u16) = match format {
+        SampleFormat::Int16 => (1, 16),
+        SampleFormat::Float32 => (3, 32),
+    };
+    let block = bits / 8;
+
+    let mut data = Vec::with_capacity(samples.len() * block as usize);
+    for sample in samples {
+        match format {
+            SampleFormat::Int16 => {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                data.extend(value.to_le_bytes());
+            }
+            SampleFormat::Float32 => data.extend(sample.to_le_bytes()),
+        }
+    }
+
+    let mut fmt = vec![];
+    fmt.extend(tag.to_le_bytes());
+    fmt.extend(1u16.to_le_bytes());
+    fmt.extend(sample_rate.to_le_bytes());
+    fmt.extend((sample_rate * block as u32).to_le_bytes());
+    fmt.extend(block.to_le_bytes());
+    fmt.extend(bits.to_le_bytes());
+
+    let mut chunks = chunk(b"fmt ", &fmt);
+    if format == SampleFormat::Float32 {
+        // The formats other than PCM have an extension size, and the number of samples.
+        chunks = chunk(b"fmt ", &[fmt, 0u16.to_le_bytes().to_vec()].concat());
+        chunks.extend(chunk(b"fact", &(samples.len() as u32).to_le_bytes()));
+    }
+    chunks.extend(chunk(b"data", &data));
+
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((4 + chunks.len() as u32).to_le_bytes());
+    bytes.extend(b"WAVE");
+    bytes.extend(chunks);
+    bytes
+}
+
+/// A chunk with its id and its size, padded to an even size.
+fn chunk(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut bytes = id.to_vec();
+    bytes.extend((content.len() as u32).to_le_bytes());
+    bytes.extend(content);
+    if content.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn pcm() {
+        let bytes = wav(8000, SampleFormat::Int16, &[0.0, 1.0, -1.0, 0.5]);
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(&bytes, 16), 16);
+        assert_eq!(&bytes[20..24], [1, 0, 1, 0]);
+        assert_eq!(u32_at(&bytes, 24), 8000);
+        assert_eq!(u32_at(&bytes, 28), 16000);
+        assert_eq!(&bytes[32..36], [2, 0, 16, 0]);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32_at(&bytes, 40), 8);
+        assert_eq!(&bytes[44..], [0, 0, 0xFF, 0x7F, 0x01, 0x80, 0x00, 0x40]);
+    }
+
+    #[test]
+    fn float() {
+        let bytes = wav(44100, SampleFormat::Float32, &[0.5, -0.25]);
+        assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+        assert_eq!(u32_at(&bytes, 16), 18);
+        assert_eq!(&bytes[20..22], [3, 0]);
+        assert_eq!(&bytes[34..36], [32, 0]);
+        assert_eq!(&bytes[38..42], b"fact");
+        assert_eq!(u32_at(&bytes, 46), 2);
+        assert_eq!(&bytes[50..54], b"data");
+        assert_eq!(u32_at(&bytes, 54), 8);
+        assert_eq!(&bytes[58..62], 0.5f32.to_le_bytes());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::Duration;
+use std::fmt::Display;
+
+/// A time signature: the number of units of a bar (the numerator), the unit (the
+/// denominator), and how the units are grouped into beats.
+///
+/// - simple meters (2/4, 3/4, 4/4) have one beat per unit,
+/// - compound meters (6/8, 9/8, 12/8) group the units by three,
+/// - odd meters (5/8, 7/8) group the units by two with a last group of three,
+/// - additive meters (2+2+3/8, 3+3+2/8) give their own grouping.
+///
+/// # Example
+/// ```
+/// use musika_rs::*;
+///
+/// let time = TimeSignature::new(6, 8);
+/// assert!(time.is_compound());
+/// assert_eq!(time.beats(), [QUARTER.dotted(), QUARTER.dotted()]);
+///
+/// let time = TimeSignature::additive(&[3, 3, 2], 8);
+/// assert_eq!(time.to_string(), "3+3+2/8");
+/// assert_eq!(time.duration(), WHOLE);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TimeSignature {
+    groups: Vec<u8>,
+    denominator: u8,
+    additive: bool,
+}
+
+impl TimeSignature {
+    /// Creates the time signature numerator / denominator, with the usual grouping of its
+    /// units into beats.
+    ///
+    /// # Panics
+    /// Panics if the numerator is zero or if the denominator is not a power of two.
+    pub fn new(numerator: u8, denominator: u8) -> Self {
+        assert!(numerator > 0, "TimeSignature: the numerator is zero");
+        assert!(
+            denominator.is_power_of_two(),
+            "TimeSignature: the denominator is not a power of two [{denominator}]"
+        );
+
+        let groups = match numerator {
+            n if n > 3 && n % 3 == 0 && denominator >= 8 => vec![3; (n / 3) as usize],
+            n if n > 3 && n % 2 == 1 && denominator >= 8 => {
+                let mut groups = vec![2; (n as usize - 3) / 2];
+                groups.push(3);
+                groups
+            }
+            n => vec![1; n as usize],
+        };
+
+        Self {
+            groups,
+            denominator,
+            additive: false,
+        }
+    }
+
+    /// Creates an additive time signature, with the given groups of units (eg. 2+2+3/8).
+    ///
+    /// # Panics
+    /// Panics if there is no group, if a group is empty or if the denominator is not a
+    /// power of two.
+    pub fn additive(groups: &[u8], denominator: u8) -> Self {
+        assert!(
+            !groups.is_empty() && groups.iter().all(|g| *g > 0),
+            "TimeSignature: the groups are empty"
+        );
+        assert!(
+            denominator.is_power_of_two(),
+            "TimeSignature: the denominator is not a power of two [{denominator}]"
+        );
+
+        Self {
+            groups: groups.to_vec(),
+            denominator,
+            additive: true,
+        }
+    }
+
+    /// The common time: 4/4.
+    pub fn common() -> Self {
+        Self::new(4, 4)
+    }
+
+    /// The cut time: 2/2.
+    pub fn cut() -> Self {
+        Self::new(2, 2)
+    }
+
+    /// The number of units in a bar.
+    pub fn numerator(&self) -> u8 {
+        self.groups.iter().sum()
+    }
+
+    pub fn denominator(&self) -> u8 {
+        self.denominator
+    }
+
+    /// The number of units of each beat.
+    pub fn groups(&self) -> impl Iterator<Item = &u8> {
+        self.groups.iter()
+    }
+
+    /// The duration of a unit (eg. an eighth for 6/8).
+    pub fn unit(&self) -> Duration {
+        Duration::from(self.denominator)
+    }
+
+    /// The duration of a bar.
+    pub fn duration(&self) -> Duration {
+        self.unit() * self.numerator() as u32
+    }
+
+    /// The duration of each beat of a bar.
+    pub fn beats(&self) -> Vec<Duration> {
+        self.groups
+            .iter()
+            .map(|g| self.unit() * *g as u32)
+            .collect()
+    }
+
+    /// Returns true if all the beats are made of three units (eg. 6/8, 12/8).
+    pub fn is_compound(&self) -> bool {
+        self.groups.iter().all(|g| *g == 3) && self.groups.len() > 1
+    }
+
+    /// Returns true if the beats have one unit each (eg. 3/4, 5/4).
+    pub fn is_simple(&self) -> bool {
+        self.groups.iter().all(|g| *g == 1)
+    }
+
+    /// Returns true if the beats do not have all the same length (eg. 7/8, 3+3+2/8).
+    pub fn is_odd(&self) -> bool {
+        self.groups.iter().any(|g| *g != self.groups[0])
+    }
+
+    /// Returns true if the grouping of the units is written in the signature (eg. 2+2+3/8).
+    pub fn is_additive(&self) -> bool {
+        self.additive
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::common()
+    }
+}
+
+impl Display for TimeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.additive {
+            true => {
+                let groups = self
+                    .groups
+                    .iter()
+                    .map(|g| g.to_string())
+                    .collect::<Vec<_>>()
+                    .join("+");
+                write!(f, "{groups}/{}", self.denominator)
+            }
+            false => write!(f, "{}/{}", self.numerator(), self.denominator),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EIGHTH, HALF, QUARTER, WHOLE};
+
+    #[test]
+    fn simple() {
+        let time = TimeSignature::default();
+        assert_eq!(time.to_string(), "4/4");
+        assert!(time.is_simple());
+        assert_eq!(time.beats(), [QUARTER; 4]);
+        assert_eq!(time.duration(), WHOLE);
+        assert_eq!(TimeSignature::cut().beats(), [HALF, HALF]);
+        assert!(TimeSignature::new(5, 4).is_simple());
+    }
+
+    #[test]
+    fn compound() {
+        let time = TimeSignature::new(12, 8);
+        assert!(time.is_compound());
+        assert_eq!(time.beats().len(), 4);
+        assert_eq!(time.duration(), WHOLE.dotted());
+        assert!(!TimeSignature::new(3, 8).is_compound());
+    }
+
+    #[test]
+    fn odd() {
+        let time = TimeSignature::new(7, 8);
+        assert!(time.is_odd());
+        assert_eq!(time.groups().copied().collect::<Vec<_>>(), [2, 2, 3]);
+        assert_eq!(time.beats(), [QUARTER, QUARTER, QUARTER.dotted()]);
+        assert_eq!(time.to_string(), "7/8");
+    }
+
+    #[test]
+    fn additive() {
+        let time = TimeSignature::additive(&[3, 2, 2], 8);
+        assert!(time.is_additive());
+        assert_eq!(time.numerator(), 7);
+        assert_eq!(time.beats()[0], EIGHTH * 3);
+        assert_eq!(time.to_string(), "3+2+2/8");
+        assert_ne!(time, TimeSignature::new(7, 8));
+    }
+}
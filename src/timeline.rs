@@ -0,0 +1,513 @@
+//! The absolute time of the elements of a sequence of bars, in beats and in seconds.
+//!
+//! A [`TempoMap`] gives the tempo along the piece: sudden changes and gradual ones
+//! (accelerando, ritardando). A [`Timeline`] places every element of the bars, following the
+//! time signature of each bar, and gives its onset and its duration.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{timeline::*, *};
+//!
+//! let bars = [
+//!     Bar::new().with_note(C, 2).with_note(E, 2),
+//!     Bar::new().with_note(G, 1),
+//! ];
+//! let tempo = TempoMap::new(120.0);
+//! let timeline = Timeline::new(&bars, &tempo);
+//!
+//! let last = timeline.events().last().unwrap();
+//! assert_eq!(last.start_beats(), 4.0);
+//! assert_eq!(last.start_seconds(), 2.0);
+//! assert_eq!(timeline.seconds(), 4.0);
+//! ```
+
+use crate::{Bar, BarElement, BeatPosition, Duration, QUARTER};
+
+/// A point of a tempo map: the tempo reached at a position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoPoint {
+    at: Duration,
+    bpm: f64,
+    ramp: bool,
+}
+
+/// The tempo along a piece, in beats per minute of a unit (a quarter note by default).
+///
+/// The tempo changes suddenly at a [`TempoMap::with_tempo`] point, and moves linearly from
+/// the previous point to a [`TempoMap::with_ramp`] point.
+///
+/// # Example
+/// ```
+/// use musika_rs::{timeline::TempoMap, *};
+///
+/// // 4 bars at 120, then a ritardando down to 60 during 2 bars.
+/// let tempo = TempoMap::new(120.0)
+///     .with_tempo(WHOLE * 4, 120.0)
+///     .with_ramp(WHOLE * 6, 60.0);
+/// assert_eq!(tempo.bpm_at(WHOLE * 5), 90.0);
+/// assert_eq!(tempo.seconds(WHOLE * 4), 8.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TempoMap {
+    unit: Duration,
+    points: Vec<TempoPoint>,
+}
+
+/// An element of a bar, placed in time.
+#[derive(Clone, Copy)]
+pub struct TimedEvent<'a> {
+    bar: usize,
+    voice: usize,
+    element: &'a BarElement,
+    position: Option<BeatPosition>,
+    start: Duration,
+    duration: Duration,
+    start_beats: f64,
+    duration_beats: f64,
+    start_seconds: f64,
+    duration_seconds: f64,
+}
+
+/// A beat of the metronome.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Click {
+    bar: usize,
+    beat: usize,
+    start: Duration,
+    seconds: f64,
+}
+
+/// The elements of a sequence of bars, placed in time.
+pub struct Timeline<'a> {
+    tempo: TempoMap,
+    bars: Vec<(Duration, &'a Bar)>,
+    end: Duration,
+    events: Vec<TimedEvent<'a>>,
+}
+
+impl TempoPoint {
+    /// The position of the point, from the start of the piece.
+    pub fn at(&self) -> Duration {
+        self.at
+    }
+
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Returns true if the tempo moves gradually from the previous point to this one.
+    pub fn is_ramp(&self) -> bool {
+        self.ramp
+    }
+}
+
+impl TempoMap {
+    /// A constant tempo, in quarter notes per minute.
+    pub fn new(bpm: f64) -> Self {
+        Self {
+            unit: QUARTER,
+            points: vec![TempoPoint {
+                at: Duration::new(0, 1),
+                bpm,
+                ramp: false,
+            }],
+        }
+    }
+
+    /// Sets the unit of the beats (eg. a dotted quarter for a 6/8).
+    pub fn with_unit(self, unit: Duration) -> Self {
+        Self { unit, ..self }
+    }
+
+    /// Changes the tempo suddenly at the given position.
+    pub fn with_tempo(self, at: Duration, bpm: f64) -> Self {
+        self.with_point(TempoPoint {
+            at,
+            bpm,
+            ramp: false,
+        })
+    }
+
+    /// Changes the tempo gradually from the previous point, to reach the given tempo at the
+    /// given position (an accelerando or a ritardando).
+    pub fn with_ramp(self, at: Duration, bpm: f64) -> Self {
+        self.with_point(TempoPoint {
+            at,
+            bpm,
+            ramp: true,
+        })
+    }
+
+    fn with_point(self, point: TempoPoint) -> Self {
+        let mut points = self.points;
+        points.retain(|p| p.at != point.at);
+        points.push(point);
+        points.sort_by_key(|p| p.at);
+        Self { points, ..self }
+    }
+
+    pub fn unit(&self) -> Duration {
+        self.unit
+    }
+
+    /// The points of the map, in order.
+    pub fn points(&self) -> impl Iterator<Item = &TempoPoint> {
+        self.points.iter()
+    }
+
+    /// The number of beats from the start of the piece to the position.
+    pub fn beats(&self, position: Duration) -> f64 {
+        position.as_f64() / self.unit.as_f64()
+    }
+
+    /// The tempo at the position.
+    pub fn bpm_at(&self, position: Duration) -> f64 {
+        let beats = self.beats(position);
+        self.segments()
+            .find(|(start, end, _, _)| beats < *end && beats >= *start)
+            .map(|(start, end, from, to)| from + (to - from) * (beats - start) / (end - start))
+            .unwrap_or_else(|| self.points.last().map_or(0.0, |p| p.bpm))
+    }
+
+    /// The number of seconds from the start of the piece to the position.
+    pub fn seconds(&self, position: Duration) -> f64 {
+        self.seconds_at(self.beats(position))
+    }
+
+    /// The number of seconds from the start of the piece to the given number of beats.
+    pub fn seconds_at(&self, beats: f64) -> f64 {
+        let mut seconds = 0.0;
+        for (start, end, from, to) in self.segments() {
+            if beats <= start {
+                return seconds;
+            }
+            let until = beats.min(end);
+            let bpm = from + (to - from) * (until - start) / (end - start);
+            seconds += segment_seconds(until - start, from, bpm);
+        }
+
+        let last = self.points.last().expect("TempoMap: no tempo");
+        let start = self.beats(last.at);
+        if beats > start {
+            seconds += segment_seconds(beats - start, last.bpm, last.bpm);
+        }
+        seconds
+    }
+
+    /// The number of beats from the start of the piece to the given number of seconds.
+    pub fn beats_at(&self, seconds: f64) -> f64 {
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+
+        let mut high = 1.0;
+        while self.seconds_at(high) < seconds {
+            high *= 2.0;
+        }
+        let mut low = 0.0;
+        for _ in 0..64 {
+            let middle = (low + high) / 2.0;
+            match self.seconds_at(middle) < seconds {
+                true => low = middle,
+                false => high = middle,
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// The segments between two points (start and end in beats, tempo at the start and at
+    /// the end).
+    fn segments(&self) -> impl Iterator<Item = (f64, f64, f64, f64)> + '_ {
+        self.points.windows(2).map(|points| {
+            let (from, to) = (points[0], points[1]);
+            let end_bpm = if to.ramp { to.bpm } else { from.bpm };
+            (self.beats(from.at), self.beats(to.at), from.bpm, end_bpm)
+        })
+    }
+}
+
+/// The number of seconds of the beats, with a tempo moving linearly from a value to another.
+fn segment_seconds(beats: f64, from: f64, to: f64) -> f64 {
+    if beats <= 0.0 {
+        return 0.0;
+    }
+    match (to - from).abs() < f64::EPSILON {
+        true => 60.0 * beats / from,
+        false => 60.0 * beats / (to - from) * (to / from).ln(),
+    }
+}
+
+impl Default for TempoMap {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+impl<'a> TimedEvent<'a> {
+    /// The index of the bar of the element.
+    pub fn bar(&self) -> usize {
+        self.bar
+    }
+
+    /// The index of the voice of the element, 0 for the main voice.
+    pub fn voice(&self) -> usize {
+        self.voice
+    }
+
+    pub fn element(&self) -> &'a BarElement {
+        self.element
+    }
+
+    /// The position of the element in its bar, or `None` if it starts after the end of the
+    /// bar.
+    pub fn position(&self) -> Option<BeatPosition> {
+        self.position
+    }
+
+    /// The position of the element, from the start of the piece.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn start_beats(&self) -> f64 {
+        self.start_beats
+    }
+
+    pub fn duration_beats(&self) -> f64 {
+        self.duration_beats
+    }
+
+    pub fn start_seconds(&self) -> f64 {
+        self.start_seconds
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.duration_seconds
+    }
+}
+
+impl Click {
+    /// The index of the bar of the beat.
+    pub fn bar(&self) -> usize {
+        self.bar
+    }
+
+    /// The beat in its bar, starting from 1.
+    pub fn beat(&self) -> usize {
+        self.beat
+    }
+
+    /// Returns true if the beat is the first one of its bar.
+    pub fn is_downbeat(&self) -> bool {
+        self.beat == 1
+    }
+
+    /// The position of the beat, from the start of the piece.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.seconds
+    }
+}
+
+impl<'a> Timeline<'a> {
+    /// Places the elements of the bars in time. Each bar lasts the duration of its time
+    /// signature, whatever its content.
+    pub fn new(bars: impl IntoIterator<Item = &'a Bar>, tempo: &TempoMap) -> Self {
+        let mut placed = vec![];
+        let mut start = Duration::new(0, 1);
+        for bar in bars {
+            placed.push((start, bar));
+            start += bar.time_signature().duration();
+        }
+
+        let events = placed
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (bar_start, bar))| {
+                bar.events().into_iter().map(move |event| {
+                    let start = *bar_start + event.onset();
+                    let duration = event.element().duration();
+                    let start_seconds = tempo.seconds(start);
+                    TimedEvent {
+                        bar: index,
+                        voice: event.voice(),
+                        element: event.element(),
+                        position: bar.position_at(event.onset()),
+                        start,
+                        duration,
+                        start_beats: tempo.beats(start),
+                        duration_beats: tempo.beats(duration),
+                        start_seconds,
+                        duration_seconds: tempo.seconds(start + duration) - start_seconds,
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            tempo: tempo.clone(),
+            bars: placed,
+            end: start,
+            events,
+        }
+    }
+
+    pub fn tempo(&self) -> &TempoMap {
+        &self.tempo
+    }
+
+    /// The elements of all the bars, ordered by the time they start.
+    pub fn events(&self) -> impl Iterator<Item = &TimedEvent<'a>> {
+        self.events.iter()
+    }
+
+    /// The position of the start of the bar, from the start of the piece.
+    pub fn bar_start(&self, bar: usize) -> Option<Duration> {
+        self.bars.get(bar).map(|(start, _)| *start)
+    }
+
+    /// The total duration of the bars.
+    pub fn duration(&self) -> Duration {
+        self.end
+    }
+
+    /// The total number of beats of the bars.
+    pub fn beats(&self) -> f64 {
+        self.tempo.beats(self.end)
+    }
+
+    /// The total number of seconds of the bars.
+    pub fn seconds(&self) -> f64 {
+        self.tempo.seconds(self.end)
+    }
+
+    /// The beats of the metronome, following the time signature of each bar.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{timeline::*, *};
+    ///
+    /// let bars = [Bar::new().with_time_signature(TimeSignature::new(6, 8))];
+    /// let tempo = TempoMap::new(60.0).with_unit(QUARTER.dotted());
+    /// let clicks = Timeline::new(&bars, &tempo).clicks();
+    /// assert_eq!(clicks.len(), 2);
+    /// assert_eq!(clicks[1].seconds(), 1.0);
+    /// ```
+    pub fn clicks(&self) -> Vec<Click> {
+        self.bars
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (bar_start, bar))| {
+                let beats = bar.time_signature().beats();
+                beats
+                    .into_iter()
+                    .scan(*bar_start, |start, beat| {
+                        let current = *start;
+                        *start += beat;
+                        Some(current)
+                    })
+                    .enumerate()
+                    .map(move |(beat, start)| (index, beat + 1, start))
+                    .collect::<Vec<_>>()
+            })
+            .map(|(bar, beat, start)| Click {
+                bar,
+                beat,
+                start,
+                seconds: self.tempo.seconds(start),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TimeSignature, Voice, C, E, EIGHTH, G, HALF, WHOLE};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn constant_tempo() {
+        let tempo = TempoMap::new(60.0);
+        assert_eq!(tempo.beats(WHOLE), 4.0);
+        assert_eq!(tempo.seconds(HALF), 2.0);
+        assert_eq!(tempo.bpm_at(WHOLE * 10), 60.0);
+    }
+
+    #[test]
+    fn tempo_changes() {
+        let tempo = TempoMap::new(60.0).with_tempo(WHOLE, 120.0);
+        assert_eq!(tempo.seconds(WHOLE), 4.0);
+        assert_eq!(tempo.seconds(WHOLE * 2), 6.0);
+        assert_eq!(tempo.bpm_at(WHOLE), 120.0);
+        assert_eq!(tempo.points().count(), 2);
+
+        let tempo = tempo.with_tempo(WHOLE, 240.0);
+        assert_eq!(tempo.points().count(), 2);
+        assert_eq!(tempo.seconds(WHOLE * 2), 5.0);
+    }
+
+    #[test]
+    fn ramps() {
+        // An accelerando from 60 to 120 during a bar: 60 * 4 / 60 * ln(2) seconds.
+        let tempo = TempoMap::new(60.0).with_ramp(WHOLE, 120.0);
+        assert_eq!(tempo.bpm_at(HALF), 90.0);
+        assert!(close(tempo.seconds(WHOLE), 4.0 * 2f64.ln()));
+        assert!(close(tempo.seconds(WHOLE * 2), 4.0 * 2f64.ln() + 2.0));
+        assert!(tempo.seconds(HALF) > 2.0 * 2f64.ln());
+        assert!(close(tempo.beats_at(4.0 * 2f64.ln() + 2.0), 8.0));
+    }
+
+    #[test]
+    fn timeline() {
+        let bars = [
+            Bar::new()
+                .with_note(C, 2)
+                .with_note(E, 2)
+                .with_voice(Voice::new().with_note(C, 1)),
+            Bar::new()
+                .with_time_signature(TimeSignature::new(3, 8))
+                .with_note(G, EIGHTH),
+            Bar::new().with_note(C, 1),
+        ];
+        let timeline = Timeline::new(&bars, &TempoMap::new(120.0));
+        let events = timeline.events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[1].voice(), 1);
+        assert_eq!(events[2].start_beats(), 2.0);
+        assert_eq!(events[3].bar(), 1);
+        assert_eq!(events[3].duration_beats(), 0.5);
+        assert_eq!(events[3].duration_seconds(), 0.25);
+        assert_eq!(events[2].position().map(|p| p.beat()), Some(3));
+        assert_eq!(events[4].start(), WHOLE + EIGHTH * 3);
+        assert_eq!(timeline.bar_start(2), Some(WHOLE + EIGHTH * 3));
+        assert_eq!(timeline.beats(), 9.5);
+        assert_eq!(timeline.seconds(), 4.75);
+    }
+
+    #[test]
+    fn clicks() {
+        let bars = [
+            Bar::new().with_time_signature(TimeSignature::new(3, 4)),
+            Bar::new().with_time_signature(TimeSignature::new(7, 8)),
+        ];
+        let clicks = Timeline::new(&bars, &TempoMap::new(60.0)).clicks();
+        let beats = clicks
+            .iter()
+            .map(|c| (c.bar(), c.beat()))
+            .collect::<Vec<_>>();
+        assert_eq!(beats, [(0, 1), (0, 2), (0, 3), (1, 1), (1, 2), (1, 3)]);
+        assert!(clicks[3].is_downbeat());
+        assert_eq!(clicks[5].seconds(), 5.0);
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+};
+
+use super::read::{self, ScalaError};
+use crate::{Note, C};
+
+/// A note of a [`Microtuning`]: a number of steps from the middle note of its keyboard
+/// mapping. It is the counterpart of a [`Note`] for the tunings which do not have twelve
+/// semitones per octave.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MicroNote(i32);
+
+/// How the keys (the MIDI notes) are mapped to the notes of a tuning, as in a Scala `.kbm`
+/// file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyboardMapping {
+    /// The first and the last keys retuned, the other ones having no note.
+    first: u8,
+    last: u8,
+    /// The key of the step 0.
+    middle: u8,
+    /// The key tuned to the reference frequency.
+    reference: u8,
+    frequency: f64,
+    /// The step of the formal octave, the pattern being repeated at this step.
+    octave: i32,
+    /// The step of each key of the pattern repeated from the middle key, if the key is
+    /// mapped; no pattern maps each key to the next step.
+    pattern: Vec<Option<i32>>,
+}
+
+/// A tuning with any number of steps per period: an equal division of the octave, or the
+/// scale of a Scala `.scl` file, with a keyboard mapping.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Microtuning {
+    description: String,
+    /// The cents of each step above the first one, the last step being the period (usually
+    /// the octave).
+    steps: Vec<f64>,
+    mapping: KeyboardMapping,
+}
+
+impl MicroNote {
+    pub fn new(steps: i32) -> Self {
+        Self(steps)
+    }
+
+    /// Returns the steps from the middle note of the mapping.
+    pub fn steps(&self) -> i32 {
+        self.0
+    }
+}
+
+impl Add<i32> for MicroNote {
+    type Output = MicroNote;
+
+    fn add(self, steps: i32) -> Self::Output {
+        MicroNote(self.0 + steps)
+    }
+}
+
+impl Sub<i32> for MicroNote {
+    type Output = MicroNote;
+
+    fn sub(self, steps: i32) -> Self::Output {
+        MicroNote(self.0 - steps)
+    }
+}
+
+impl Sub for MicroNote {
+    type Output = i32;
+
+    /// The steps from the other note to this one.
+    fn sub(self, other: MicroNote) -> Self::Output {
+        self.0 - other.0
+    }
+}
+
+impl Display for MicroNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+}", self.0)
+    }
+}
+
+impl KeyboardMapping {
+    /// Maps each key to the next step, from the middle note (the step 0), the reference note
+    /// being tuned to the frequency.
+    pub fn linear(middle: Note, reference: Note, frequency: f64) -> Self {
+        assert!(
+            frequency > 0.0,
+            "KeyboardMapping: the frequency {frequency} is not a frequency"
+        );
+        Self {
+            first: 0,
+            last: 127,
+            middle: middle.midi(),
+            reference: reference.midi(),
+            frequency,
+            octave: 0,
+            pattern: vec![],
+        }
+    }
+
+    /// Reads the text of a Scala `.kbm` file: the size of the pattern, the first and last
+    /// keys retuned, the middle key, the reference key and its frequency, the step of the
+    /// formal octave, then the step of each key of the pattern (`x` for a key which is not
+    /// mapped).
+    pub fn from_kbm(text: &str) -> Result<Self, ScalaError> {
+        read::kbm(text)
+    }
+
+    /// Reads a `.kbm` file.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, ScalaError> {
+        Self::from_kbm(&std::fs::read_to_string(path)?)
+    }
+
+    pub(super) fn new(
+        (first, last): (u8, u8),
+        middle: u8,
+        (reference, frequency): (u8, f64),
+        octave: i32,
+        pattern: Vec<Option<i32>>,
+    ) -> Self {
+        Self {
+            first,
+            last,
+            middle,
+            reference,
+            frequency,
+            octave,
+            pattern,
+        }
+    }
+
+    pub fn middle(&self) -> Note {
+        Note::from_midi(self.middle)
+    }
+
+    pub fn reference(&self) -> Note {
+        Note::from_midi(self.reference)
+    }
+
+    /// Returns the frequency of the reference note, in Hz.
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Returns the step of a key, if it is mapped.
+    pub(super) fn step(&self, key: u8) -> Option<i32> {
+        if key < self.first || key > self.last {
+            return None;
+        }
+        let offset = key as i32 - self.middle as i32;
+        if self.pattern.is_empty() {
+            return Some(offset);
+        }
+        let size = self.pattern.len() as i32;
+        let step = self.pattern[offset.rem_euclid(size) as usize]?;
+        Some(offset.div_euclid(size) * self.octave + step)
+    }
+}
+
+impl Default for KeyboardMapping {
+    /// The step 0 on C4, tuned as in the equal temperament.
+    fn default() -> Self {
+        Self::linear(C, C, C.frequency())
+    }
+}
+
+impl Microtuning {
+    /// The equal division of the octave in the given steps, eg. 19, 24 (the quarter tones),
+    /// 31 or 53, with the default keyboard mapping.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{tuning::*, *};
+    ///
+    /// // The major third of 31-EDO (10 steps) is close to the pure one (386.3 cents).
+    /// let edo = Microtuning::edo(31);
+    /// let c = edo.note(C).unwrap();
+    /// assert_eq!((edo.interval(c, c + 10) * 10.0).round(), 3871.0);
+    /// assert_eq!(edo.frequency(c + 31), 2.0 * C.frequency());
+    /// ```
+    pub fn edo(divisions: usize) -> Self {
+        assert!(divisions > 0, "Microtuning: the octave needs a division");
+        Self {
+            description: format!("{divisions} equal divisions of the octave"),
+            steps: (1..=divisions)
+                .map(|step| 1200.0 * step as f64 / divisions as f64)
+                .collect(),
+            mapping: KeyboardMapping::default(),
+        }
+    }
+
+    /// Reads the text of a Scala `.scl` file: a description, the number of steps, then the
+    /// pitch of each step above the first one, in cents (with a dot, eg. `386.31`) or as a
+    /// ratio (eg. `5/4` or `2`), the last one being the period. The lines starting with `!`
+    /// are comments.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{tuning::*, *};
+    ///
+    /// let scl = "! pentatonic.scl\nA just pentatonic scale\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n";
+    /// let tuning = Microtuning::from_scl(scl).unwrap();
+    /// assert_eq!(tuning.description(), "A just pentatonic scale");
+    /// assert_eq!(tuning.size(), 5);
+    /// let c = tuning.note(C).unwrap();
+    /// let sixth = tuning.frequency(c + 4) / tuning.frequency(c);
+    /// assert_eq!((sixth * 3000.0).round(), 5000.0);
+    /// ```
+    pub fn from_scl(text: &str) -> Result<Self, ScalaError> {
+        let (description, steps) = read::scl(text)?;
+        Ok(Self {
+            description,
+            steps,
+            mapping: KeyboardMapping::default(),
+        })
+    }
+
+    /// Reads a `.scl` file.
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, ScalaError> {
+        Self::from_scl(&std::fs::read_to_string(path)?)
+    }
+
+    /// Sets the keyboard mapping giving the note of each key and the reference frequency.
+    pub fn with_mapping(self, mapping: KeyboardMapping) -> Self {
+        assert!(
+            mapping.step(mapping.reference).is_some(),
+            "Microtuning: the reference key {} is not mapped",
+            mapping.reference
+        );
+        Self { mapping, ..self }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the number of steps per period.
+    pub fn size(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the period of the tuning (the interval its steps repeat at), in cents.
+    pub fn period(&self) -> f64 {
+        self.steps[self.steps.len() - 1]
+    }
+
+    pub fn mapping(&self) -> &KeyboardMapping {
+        &self.mapping
+    }
+
+    /// Returns the note of the key of a note, if it is mapped.
+    pub fn note(&self, note: Note) -> Option<MicroNote> {
+        self.mapping.step(note.midi()).map(MicroNote)
+    }
+
+    /// Returns the step of the note inside its period, from 0.
+    pub fn degree(&self, note: MicroNote) -> usize {
+        note.0.rem_euclid(self.size() as i32) as usize
+    }
+
+    /// Returns the cents of the note above the middle note of the mapping.
+    pub fn cents(&self, note: MicroNote) -> f64 {
+        let periods = note.0.div_euclid(self.size() as i32);
+        let degree = match self.degree(note) {
+            0 => 0.0,
+            degree => self.steps[degree - 1],
+        };
+        periods as f64 * self.period() + degree
+    }
+
+    /// Returns the frequency of the note in Hz.
+    pub fn frequency(&self, note: MicroNote) -> f64 {
+        let reference = self
+            .mapping
+            .step(self.mapping.reference)
+            .expect("Microtuning: the reference key is mapped");
+        let cents = self.cents(note) - self.cents(MicroNote(reference));
+        self.mapping.frequency * 2f64.powf(cents / 1200.0)
+    }
+
+    /// Returns the size of the interval between the notes, in cents.
+    pub fn interval(&self, from: MicroNote, to: MicroNote) -> f64 {
+        self.cents(to) - self.cents(from)
+    }
+
+    /// Returns the note with the nearest frequency.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{tuning::*, *};
+    ///
+    /// // The quarter tone between C and C#.
+    /// let edo = Microtuning::edo(24);
+    /// let c = edo.note(C).unwrap();
+    /// assert_eq!(edo.nearest(C.frequency() * 2f64.powf(0.5 / 12.0)), c + 1);
+    /// ```
+    pub fn nearest(&self, frequency: f64) -> MicroNote {
+        let middle = MicroNote(0);
+        let cents = 1200.0 * (frequency / self.frequency(middle)).log2();
+        let periods = (cents / self.period()).floor();
+        let rest = cents - periods * self.period();
+        let degree = std::iter::once(0.0)
+            .chain(self.steps.iter().copied())
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a - rest).abs().total_cmp(&(b - rest).abs()))
+            .map_or(0, |(degree, _)| degree);
+        MicroNote(periods as i32 * self.size() as i32 + degree as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, D, E, OCTAVE};
+
+    #[test]
+    fn micro_notes() {
+        let note = MicroNote::new(3);
+        assert_eq!(note + 2, MicroNote::new(5));
+        assert_eq!(note - 5, MicroNote::new(-2));
+        assert_eq!(MicroNote::new(10) - note, 7);
+        assert_eq!(note.to_string(), "+3");
+    }
+
+    #[test]
+    fn edos() {
+        let twelve = Microtuning::edo(12);
+        for note in [A, C, D, E + OCTAVE] {
+            let micro = twelve.note(note).unwrap();
+            assert!((twelve.frequency(micro) - note.frequency()).abs() < 1e-9);
+        }
+
+        let edo = Microtuning::edo(53);
+        assert_eq!(edo.size(), 53);
+        assert_eq!(edo.period(), 1200.0);
+        let c = edo.note(C).unwrap();
+        assert_eq!(c, MicroNote::new(0));
+        assert_eq!(edo.degree(c - 1), 52);
+        assert!((edo.interval(c, c + 31) - 701.887).abs() < 0.001);
+        assert!((edo.cents(c - 53) + 1200.0).abs() < 1e-9);
+        assert_eq!(edo.nearest(edo.frequency(c - 60)), c - 60);
+    }
+
+    #[test]
+    fn mappings() {
+        // 19-EDO with its A4 (the step 14 above C4, on the key D5) at 440 Hz.
+        let mapping = KeyboardMapping::linear(C, Note::from(14), 440.0);
+        let edo = Microtuning::edo(19).with_mapping(mapping);
+        let c = edo.note(C).unwrap();
+        assert!((edo.frequency(c + 14) - 440.0).abs() < 1e-9);
+        assert_eq!(edo.mapping().middle(), C);
+
+        // The white keys only, the black keys being unmapped.
+        let pattern = [Some(0), None, Some(1), None, Some(2), Some(3), None];
+        let pattern = pattern
+            .into_iter()
+            .chain([Some(4), None, Some(5), None, Some(6)])
+            .collect();
+        let mapping = KeyboardMapping::new((0, 127), 60, (69, 440.0), 7, pattern);
+        let tuning = Microtuning::edo(7).with_mapping(mapping);
+        assert_eq!(tuning.note(C), Some(MicroNote::new(0)));
+        assert_eq!(tuning.note(crate::C_SHARP), None);
+        assert_eq!(tuning.note(A), Some(MicroNote::new(-2)));
+        assert_eq!(tuning.note(E + OCTAVE), Some(MicroNote::new(9)));
+        assert!((tuning.frequency(MicroNote::new(5)) - 440.0).abs() < 1e-9);
+    }
+}
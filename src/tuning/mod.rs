@@ -0,0 +1,259 @@
+//! The frequencies of the notes in the historical tunings.
+//!
+//! A [`Tuning`] gives the frequency of each note in a [`Temperament`]: the equal
+//! temperament, the just intonation, the Pythagorean tuning, the quarter-comma meantone, or
+//! the well temperaments of Werckmeister (III) and Vallotti. The tunings other than the equal
+//! temperament are built from a tonic, which keeps the frequency it has in the equal
+//! temperament, the other notes deviating from it by some cents.
+//!
+//! A [`Microtuning`] has any number of steps per period: an equal division of the octave
+//! ([`Microtuning::edo`]), or the scale of a Scala `.scl` file ([`Microtuning::from_scl`]),
+//! with a keyboard mapping of a `.kbm` file ([`KeyboardMapping::from_kbm`]). Its notes are
+//! [`MicroNote`]s, counted in steps.
+//!
+//! # Example
+//! ```
+//! use musika_rs::{tuning::*, *};
+//!
+//! // The major third of a C major triad is pure in the just intonation, wide in the
+//! // Pythagorean tuning.
+//! let just = Tuning::new(Temperament::Just, C);
+//! let pythagorean = Tuning::new(Temperament::Pythagorean, C);
+//! assert_eq!(Tuning::equal().interval(C, E).round(), 400.0);
+//! assert_eq!(just.interval(C, E).round(), 386.0);
+//! assert_eq!(pythagorean.interval(C, E).round(), 408.0);
+//! assert_eq!(just.cents(E).round(), -14.0);
+//! ```
+
+mod micro;
+mod read;
+
+use std::fmt::Display;
+
+use crate::{Note, A, OCTAVE};
+
+pub use micro::*;
+pub use read::ScalaError;
+
+/// A way of dividing the octave into the twelve notes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Temperament {
+    /// Twelve equal semitones of 100 cents.
+    #[default]
+    Equal,
+    /// The 5-limit just intonation: the ratios of small whole numbers from the tonic (5/4
+    /// for the major third, 3/2 for the fifth...).
+    Just,
+    /// Pure fifths (3/2), from the minor third to the augmented fifth above the tonic.
+    Pythagorean,
+    /// Fifths narrowed by a quarter of the syntonic comma, for pure major thirds, from the
+    /// minor third to the augmented fifth above the tonic.
+    Meantone,
+    /// The Werckmeister III well temperament: the fifths from the tonic to the sixth and
+    /// the one from the seventh narrowed by a quarter of the Pythagorean comma.
+    Werckmeister,
+    /// The Vallotti well temperament: the six fifths from the fourth to the seventh
+    /// narrowed by a sixth of the Pythagorean comma.
+    Vallotti,
+}
+
+/// The frequencies of the notes in a temperament, from a tonic and a reference pitch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tuning {
+    temperament: Temperament,
+    tonic: Note,
+    reference: f64,
+}
+
+/// The ratios of the 5-limit just intonation, for each semitone above the tonic.
+const JUST: [(u32, u32); 12] = [
+    (1, 1),
+    (16, 15),
+    (9, 8),
+    (6, 5),
+    (5, 4),
+    (4, 3),
+    (45, 32),
+    (3, 2),
+    (8, 5),
+    (5, 3),
+    (9, 5),
+    (15, 8),
+];
+
+/// The fifths from the tonic to each semitone above it, from the minor third (3 fifths
+/// below) to the augmented fifth (8 fifths above).
+const FIFTHS: [i32; 12] = [0, 7, 2, -3, 4, -1, 6, 1, 8, 3, -2, 5];
+
+/// The cents above the tonic of the Werckmeister III temperament.
+const WERCKMEISTER: [f64; 12] = [
+    0.0, 90.225, 192.18, 294.135, 390.225, 498.045, 588.27, 696.09, 792.18, 888.27, 996.09, 1092.18,
+];
+
+/// The cents above the tonic of the Vallotti temperament.
+const VALLOTTI: [f64; 12] = [
+    0.0, 94.135, 196.09, 298.045, 392.18, 501.955, 592.18, 698.045, 796.09, 894.135, 1000.0,
+    1090.225,
+];
+
+impl Temperament {
+    /// The cents above the tonic of each semitone.
+    fn cents(&self) -> [f64; 12] {
+        let ratio = |(n, d): (u32, u32)| 1200.0 * (n as f64 / d as f64).log2();
+        let fifths = |fifth: f64| FIFTHS.map(|n| (n as f64 * fifth).rem_euclid(1200.0));
+        match self {
+            Temperament::Equal => std::array::from_fn(|i| i as f64 * 100.0),
+            Temperament::Just => JUST.map(ratio),
+            Temperament::Pythagorean => fifths(ratio((3, 2))),
+            Temperament::Meantone => fifths(1200.0 * 5f64.log2() / 4.0),
+            Temperament::Werckmeister => WERCKMEISTER,
+            Temperament::Vallotti => VALLOTTI,
+        }
+    }
+}
+
+impl Display for Temperament {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Temperament::Equal => "equal temperament",
+            Temperament::Just => "just intonation",
+            Temperament::Pythagorean => "Pythagorean",
+            Temperament::Meantone => "quarter-comma meantone",
+            Temperament::Werckmeister => "Werckmeister III",
+            Temperament::Vallotti => "Vallotti",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Tuning {
+    /// The temperament built from the tonic, with A4 at 440 Hz in the equal temperament.
+    pub fn new(temperament: Temperament, tonic: Note) -> Self {
+        Self {
+            temperament,
+            tonic,
+            reference: 440.0,
+        }
+    }
+
+    /// The equal temperament, with A4 at 440 Hz.
+    pub fn equal() -> Self {
+        Self::new(Temperament::Equal, A)
+    }
+
+    /// Sets the frequency of A4 in the equal temperament, eg. 415 Hz for the baroque pitch.
+    pub fn with_reference(self, reference: f64) -> Self {
+        assert!(
+            reference > 0.0,
+            "Tuning: the reference {reference} is not a frequency"
+        );
+        Self { reference, ..self }
+    }
+
+    pub fn temperament(&self) -> Temperament {
+        self.temperament
+    }
+
+    pub fn tonic(&self) -> Note {
+        self.tonic
+    }
+
+    pub fn reference(&self) -> f64 {
+        self.reference
+    }
+
+    /// Returns the frequency of the note in Hz.
+    ///
+    /// # Example
+    /// ```
+    /// use musika_rs::{tuning::*, *};
+    ///
+    /// let meantone = Tuning::new(Temperament::Meantone, C);
+    /// assert_eq!(meantone.frequency(C), C.frequency());
+    /// assert_eq!((meantone.frequency(E) / meantone.frequency(C) * 1000.0).round(), 1250.0);
+    /// ```
+    pub fn frequency(&self, note: Note) -> f64 {
+        let equal = self.reference / 440.0 * note.frequency();
+        equal * 2f64.powf(self.cents(note) / 1200.0)
+    }
+
+    /// Returns how far the note is from its pitch in the equal temperament, in cents.
+    pub fn cents(&self, note: Note) -> f64 {
+        let degree = self.degree(note);
+        self.temperament.cents()[degree] - degree as f64 * 100.0
+    }
+
+    /// Returns the size of the interval between the notes, in cents.
+    pub fn interval(&self, from: Note, to: Note) -> f64 {
+        1200.0 * (self.frequency(to) / self.frequency(from)).log2()
+    }
+
+    /// The semitones from the tonic to the note, inside an octave.
+    fn degree(&self, note: Note) -> usize {
+        let semitones = note.pitch_class() as i16 - self.tonic.pitch_class() as i16;
+        semitones.rem_euclid(OCTAVE.inner() as i16) as usize
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::equal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C, D, E, F_SHARP, G, G_SHARP};
+
+    const TEMPERAMENTS: [Temperament; 6] = [
+        Temperament::Equal,
+        Temperament::Just,
+        Temperament::Pythagorean,
+        Temperament::Meantone,
+        Temperament::Werckmeister,
+        Temperament::Vallotti,
+    ];
+
+    #[test]
+    fn tonic_and_octaves() {
+        for temperament in TEMPERAMENTS {
+            let tuning = Tuning::new(temperament, D);
+            assert_eq!(tuning.cents(D), 0.0, "{temperament}");
+            assert_eq!(tuning.frequency(D), D.frequency());
+            let octave = tuning.frequency(G + OCTAVE) / tuning.frequency(G);
+            assert!((octave - 2.0).abs() < 1e-9, "{temperament}");
+        }
+    }
+
+    #[test]
+    fn equal() {
+        let tuning = Tuning::default();
+        assert_eq!(tuning.frequency(A + OCTAVE), 440.0);
+        assert_eq!(tuning.cents(F_SHARP), 0.0);
+        let baroque = tuning.with_reference(415.0);
+        assert!((baroque.frequency(A + OCTAVE) - 415.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fifths_and_thirds() {
+        let cents = |temperament, from, to| Tuning::new(temperament, C).interval(from, to);
+        assert!((cents(Temperament::Just, C, G) - 701.955).abs() < 0.001);
+        assert!((cents(Temperament::Pythagorean, D, A + OCTAVE) - 701.955).abs() < 0.001);
+        assert!((cents(Temperament::Meantone, D, A + OCTAVE) - 696.578).abs() < 0.001);
+        assert!((cents(Temperament::Meantone, E, G_SHARP) - 386.314).abs() < 0.001);
+        // The wolf fifth of the meantone, from G# to Eb.
+        let wolf = cents(Temperament::Meantone, G_SHARP, crate::D_SHARP + OCTAVE);
+        assert!((wolf - 737.637).abs() < 0.001);
+    }
+
+    #[test]
+    fn well_temperaments() {
+        let werckmeister = Tuning::new(Temperament::Werckmeister, C);
+        assert!((werckmeister.cents(A) + 11.73).abs() < 0.001);
+        let vallotti = Tuning::new(Temperament::Vallotti, C);
+        assert!((vallotti.interval(C, E) - 392.18).abs() < 0.001);
+        assert!((vallotti.interval(E, G_SHARP) - 403.91).abs() < 0.001);
+        assert_eq!(Temperament::Werckmeister.to_string(), "Werckmeister III");
+    }
+}
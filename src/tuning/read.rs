@@ -0,0 +1,294 @@
+use std::fmt::Display;
+
+use super::KeyboardMapping;
+
+/// The errors of reading a Scala file.
+#[derive(Debug)]
+pub enum ScalaError {
+    Io(std::io::Error),
+    /// A line is missing or cannot be read.
+    Syntax(usize, String),
+}
+
+/// The lines of a file which are not comments, with their number.
+struct Lines<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+    /// The number of the last line read, or the one after the end of the file.
+    line: usize,
+    count: usize,
+}
+
+/// Reads a `.scl` file: its description and the cents of its steps.
+pub(super) fn scl(text: &str) -> Result<(String, Vec<f64>), ScalaError> {
+    let mut lines = Lines::new(text);
+    let description = lines.next("a description")?.trim().to_string();
+    let count: usize = lines.value("the number of notes")?;
+    if count == 0 {
+        return Err(lines.error("a scale needs a note"));
+    }
+    let steps = (0..count)
+        .map(|_| {
+            let line = lines.next("a pitch")?;
+            let token = line.split_whitespace().next().unwrap_or_default();
+            pitch(token).ok_or_else(|| lines.error(&format!("invalid pitch {token:?}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((description, steps))
+}
+
+/// Reads a `.kbm` file.
+pub(super) fn kbm(text: &str) -> Result<KeyboardMapping, ScalaError> {
+    let mut lines = Lines::new(text);
+    let size: usize = lines.value("the size of the map")?;
+    if size > 128 {
+        return Err(lines.error(&format!("the size of the map {size} is more than 128")));
+    }
+    let first: u8 = lines.key("the first key")?;
+    let last: u8 = lines.key("the last key")?;
+    let middle: u8 = lines.key("the middle key")?;
+    let reference: u8 = lines.key("the reference key")?;
+    let reference_line = lines.line;
+    let frequency: f64 = lines.value("the reference frequency")?;
+    if frequency <= 0.0 {
+        return Err(lines.error("the reference frequency is not a frequency"));
+    }
+    let octave: i32 = lines.value("the formal octave")?;
+
+    // The keys of the pattern which are not written are not mapped.
+    let mut pattern = vec![None; size];
+    for step in pattern.iter_mut() {
+        let Some(line) = lines.optional() else {
+            break;
+        };
+        *step = match line.split_whitespace().next() {
+            Some("x") | None => None,
+            Some(token) => Some(
+                token
+                    .parse()
+                    .map_err(|_| lines.error(&format!("invalid step {token:?}")))?,
+            ),
+        };
+    }
+
+    let mapping = KeyboardMapping::new(
+        (first, last),
+        middle,
+        (reference, frequency),
+        octave,
+        pattern,
+    );
+    match mapping.step(reference) {
+        Some(_) => Ok(mapping),
+        None => Err(ScalaError::Syntax(
+            reference_line,
+            "the reference key is not mapped".to_string(),
+        )),
+    }
+}
+
+/// The cents of a pitch: cents with a dot (eg. `386.31`), or a ratio (eg. `5/4` or `2`).
+fn pitch(token: &str) -> Option<f64> {
+    if token.contains('.') {
+        return token.parse().ok();
+    }
+    let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+    let numerator: u64 = numerator.parse().ok()?;
+    let denominator: u64 = denominator.parse().ok()?;
+    if numerator == 0 || denominator == 0 {
+        return None;
+    }
+    Some(1200.0 * (numerator as f64 / denominator as f64).log2())
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            lines: text.lines().enumerate(),
+            line: 0,
+            count: text.lines().count(),
+        }
+    }
+
+    /// The next line which is not a comment, if any.
+    fn optional(&mut self) -> Option<&'a str> {
+        match self.lines.find(|(_, line)| !line.starts_with('!')) {
+            Some((index, line)) => {
+                self.line = index + 1;
+                Some(line)
+            }
+            None => {
+                self.line = self.count + 1;
+                None
+            }
+        }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&'a str, ScalaError> {
+        match self.optional() {
+            Some(line) => Ok(line),
+            None => Err(self.error(&format!("expected {expected}"))),
+        }
+    }
+
+    /// The value written first on the next line.
+    fn value<T: std::str::FromStr>(&mut self, expected: &str) -> Result<T, ScalaError> {
+        let line = self.next(expected)?;
+        let token = line.split_whitespace().next().unwrap_or_default();
+        token
+            .parse()
+            .map_err(|_| self.error(&format!("expected {expected}, found {token:?}")))
+    }
+
+    /// A MIDI key, from 0 to 127.
+    fn key(&mut self, expected: &str) -> Result<u8, ScalaError> {
+        let key: u8 = self.value(expected)?;
+        match key < 128 {
+            true => Ok(key),
+            false => Err(self.error(&format!("{expected} {key} is not in 0..128"))),
+        }
+    }
+
+    fn error(&self, message: &str) -> ScalaError {
+        ScalaError::Syntax(self.line, message.to_string())
+    }
+}
+
+impl Display for ScalaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalaError::Io(e) => write!(f, "{e}"),
+            ScalaError::Syntax(line, message) => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ScalaError {}
+
+impl From<std::io::Error> for ScalaError {
+    fn from(e: std::io::Error) -> Self {
+        ScalaError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tuning::{MicroNote, Microtuning},
+        C, C_SHARP, D,
+    };
+
+    const MEANTONE: &str = "\
+! meanquar.scl
+!
+1/4-comma meantone scale. Pietro Aaron's temperament (1523)
+ 12
+!
+ 76.04900
+ 193.15686
+ 310.26471
+ 5/4
+ 503.42157
+ 579.47057
+ 696.57843
+ 25/16
+ 889.73529
+ 1006.84314
+ 1082.89214
+ 2/1
+";
+
+    #[test]
+    fn scales() {
+        let tuning = Microtuning::from_scl(MEANTONE).unwrap();
+        assert_eq!(
+            tuning.description(),
+            "1/4-comma meantone scale. Pietro Aaron's temperament (1523)"
+        );
+        assert_eq!(tuning.size(), 12);
+        assert_eq!(tuning.period(), 1200.0);
+        let c = tuning.note(C).unwrap();
+        assert!((tuning.cents(c + 4) - 386.314).abs() < 0.001);
+        assert!((tuning.cents(c + 8) - 772.627).abs() < 0.001);
+        assert!((tuning.cents(c + 7) - 696.57843).abs() < 1e-9);
+
+        assert_eq!(pitch("3"), Some(1200.0 * 3f64.log2()));
+        assert_eq!(pitch("-5.0"), Some(-5.0));
+        assert_eq!(pitch("0/1"), None);
+    }
+
+    #[test]
+    fn scale_errors() {
+        let error = Microtuning::from_scl("Two\n2\n9/8\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 4: expected a pitch");
+        let error = Microtuning::from_scl("Bad\n1\nfoo\n").err().unwrap();
+        assert_eq!(error.to_string(), r#"line 3: invalid pitch "foo""#);
+        let error = Microtuning::from_scl("Empty\n0\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: a scale needs a note");
+        assert!(matches!(
+            Microtuning::from_scl("! only a comment\n"),
+            Err(ScalaError::Syntax(2, _))
+        ));
+    }
+
+    #[test]
+    fn mappings() {
+        // The black keys are not mapped, A4 (the step 5) is at 440 Hz.
+        let kbm = "\
+! white.kbm
+12
+0
+127
+60
+69
+440.0
+7
+! the pattern
+0
+x
+1
+x
+2
+3
+x
+4
+x
+5
+x
+6
+";
+        let mapping = KeyboardMapping::from_kbm(kbm).unwrap();
+        assert_eq!(mapping.middle(), C);
+        assert_eq!(mapping.frequency(), 440.0);
+        let tuning = Microtuning::edo(7).with_mapping(mapping);
+        assert_eq!(tuning.note(C_SHARP), None);
+        assert_eq!(tuning.note(D), Some(MicroNote::new(1)));
+        assert!((tuning.frequency(MicroNote::new(5)) - 440.0).abs() < 1e-9);
+
+        // A linear mapping, with the pattern left out.
+        let mapping = KeyboardMapping::from_kbm("0\n0\n127\n60\n60\n261.6\n12\n").unwrap();
+        assert_eq!(mapping.step(61), Some(1));
+    }
+
+    #[test]
+    fn mapping_errors() {
+        let error = KeyboardMapping::from_kbm("0\n0\n127\n60\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 5: expected the reference key");
+        let error = KeyboardMapping::from_kbm("0\n0\n200\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 3: the last key 200 is not in 0..128"
+        );
+        let error = KeyboardMapping::from_kbm("1\n0\n127\n60\n60\n440\n12\nx\n")
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "line 5: the reference key is not mapped");
+        let error = KeyboardMapping::from_kbm("1000000000000\n0\n127\n60\n60\n440\n12\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 1: the size of the map 1000000000000 is more than 128"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use super::{distance_to_center, inversions, placements, Register, Voicing, VoicingKind};
+use crate::{chords::Chords, Note};
+use std::{
+    cmp::Reverse,
+    fmt::{Display, UpperHex},
+};
+
+/// The move of one voice from a chord to the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Motion {
+    from: Note,
+    to: Note,
+}
+
+/// A chord of a progression, voiced to move smoothly from the previous one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoicedChord {
+    voicing: Voicing,
+    motions: Vec<Motion>,
+}
+
+impl Motion {
+    pub fn new(from: Note, to: Note) -> Self {
+        Self { from, to }
+    }
+
+    pub fn from(&self) -> Note {
+        self.from
+    }
+
+    pub fn to(&self) -> Note {
+        self.to
+    }
+
+    /// The number of semitones the voice moves, negative when it moves down.
+    pub fn semitones(&self) -> i8 {
+        i8::from(self.to) - i8::from(self.from)
+    }
+
+    /// Returns true if the voice keeps its note.
+    pub fn is_common_tone(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+impl VoicedChord {
+    pub fn voicing(&self) -> &Voicing {
+        &self.voicing
+    }
+
+    /// The motion of each voice from the previous chord, from the lowest voice to the
+    /// highest one. Empty for the first chord of the progression.
+    pub fn motions(&self) -> impl Iterator<Item = &Motion> {
+        self.motions.iter()
+    }
+
+    /// The total number of semitones moved by all the voices.
+    pub fn total_motion(&self) -> u8 {
+        self.motions
+            .iter()
+            .map(|m| m.semitones().unsigned_abs())
+            .sum()
+    }
+
+    /// The number of voices which keep their note.
+    pub fn common_tones(&self) -> usize {
+        self.motions.iter().filter(|m| m.is_common_tone()).count()
+    }
+}
+
+/// Voices a progression of chords so that each chord moves as little as possible from the
+/// previous one, keeping the common tones.
+///
+/// Every chord is voiced in four voices, using the closed and the drop-2 voicings in all
+/// their inversions, inside the register. The first chord is the one closest to the center
+/// of the register. Returns `None` if a chord cannot fit in the register.
+///
+/// # Example
+/// ```
+/// use musika_rs::{voicings::{self, Register}, *};
+///
+/// let progression = [D.min7(), G.dom7(), C.maj7()];
+/// let voiced = voicings::voice_lead(&progression, &Register::left_hand()).unwrap();
+/// for chord in voiced {
+///     println!("{chord:X}");
+/// }
+/// ```
+pub fn voice_lead(chords: &[Chords], register: &Register) -> Option<Vec<VoicedChord>> {
+    let mut voiced: Vec<VoicedChord> = Vec::with_capacity(chords.len());
+
+    for chord in chords {
+        let candidates = candidates(chord, register);
+        let next = match voiced.last() {
+            None => candidates
+                .into_iter()
+                .min_by_key(|v| distance_to_center(&v.notes, register))
+                .map(|voicing| VoicedChord {
+                    voicing,
+                    motions: vec![],
+                })?,
+            Some(previous) => candidates
+                .into_iter()
+                .map(|voicing| VoicedChord {
+                    motions: motions(&previous.voicing, &voicing),
+                    voicing,
+                })
+                .min_by_key(|v| {
+                    (
+                        v.total_motion(),
+                        Reverse(v.common_tones()),
+                        distance_to_center(&v.voicing.notes, register),
+                    )
+                })?,
+        };
+
+        voiced.push(next);
+    }
+
+    Some(voiced)
+}
+
+/// All the four voices voicings of the chord which fit in the register.
+fn candidates(chord: &Chords, register: &Register) -> Vec<Voicing> {
+    [VoicingKind::Closed, VoicingKind::Drop2]
+        .into_iter()
+        .flat_map(|kind| {
+            inversions(chord, kind)
+                .into_iter()
+                .flat_map(|notes| placements(&notes, register))
+                .map(move |notes| Voicing::new(kind, chord.to_string(), notes))
+        })
+        .collect()
+}
+
+fn motions(from: &Voicing, to: &Voicing) -> Vec<Motion> {
+    from.notes()
+        .zip(to.notes())
+        .map(|(from, to)| Motion::new(*from, *to))
+        .collect()
+}
+
+impl Display for Motion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:X}{} -> {:X}{} ({:+})",
+            self.from,
+            self.from.scientific_octave(),
+            self.to,
+            self.to.scientific_octave(),
+            self.semitones()
+        )
+    }
+}
+
+impl Display for VoicedChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.voicing)
+    }
+}
+
+impl UpperHex for VoicedChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:X}", self.voicing)?;
+        for motion in self.motions() {
+            write!(f, "\n  {motion}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C, D, G};
+
+    #[test]
+    fn two_five_one() {
+        let progression = [D.min7(), G.dom7(), C.maj7()];
+        let voiced = voice_lead(&progression, &Register::left_hand()).unwrap();
+        assert_eq!(voiced.len(), 3);
+        assert_eq!(voiced[0].motions().count(), 0);
+
+        // Dm7 -> G7: two common tones, the others move down by step.
+        assert_eq!(voiced[1].common_tones(), 2);
+        assert_eq!(voiced[1].total_motion(), 3);
+        assert!(voiced[1].motions().any(|m| m.semitones() == -1));
+
+        // G7 -> Cmaj7: two common tones, the others move down by step.
+        assert_eq!(voiced[2].common_tones(), 2);
+        assert_eq!(voiced[2].total_motion(), 3);
+    }
+
+    #[test]
+    fn register() {
+        let register = Register::left_hand();
+        let progression = [C.maj7(), G.dom7(), C.maj7(), G.dom7()];
+        let voiced = voice_lead(&progression, &register).unwrap();
+        assert!(voiced
+            .iter()
+            .all(|v| v.voicing().notes().all(|n| register.contains(*n))));
+
+        let narrow = Register::new(C, D);
+        assert!(voice_lead(&progression, &narrow).is_none());
+    }
+
+    #[test]
+    fn motion() {
+        let motion = Motion::new(C, Note::from(-1));
+        assert_eq!(motion.semitones(), -1);
+        assert_eq!(motion.to_string(), "C4 -> B3 (-1)");
+    }
+}
@@ -0,0 +1,580 @@
+//! Octave-aware voicings of chords.
+//!
+//! The chords keep their notes in one octave (see [`Note::base`]), which is fine for naming
+//! them but not for playing them. A voicing places every note of a chord in a given octave,
+//! inside a [`Register`].
+//!
+//! # Example
+//! ```
+//! use musika_rs::{voicings::{self, Register}, *};
+//!
+//! let chord = D.min7();
+//! let voicing = voicings::drop2(&chord, &Register::left_hand()).unwrap();
+//! println!("{voicing:X}");
+//! ```
+
+use crate::{
+    chords::{Alteration, Chord, Chords, Degree, Quality},
+    Note,
+};
+use std::fmt::{Display, LowerHex, UpperHex};
+
+mod leading;
+mod quartal;
+mod upper_structure;
+
+pub use leading::*;
+pub use quartal::*;
+pub use upper_structure::*;
+
+/// The range of notes a voicing must fit in, both ends included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Register {
+    low: Note,
+    high: Note,
+}
+
+impl Register {
+    pub fn new(low: Note, high: Note) -> Self {
+        debug_assert!(low <= high, "The register is empty: {low:?} > {high:?}");
+        Self { low, high }
+    }
+
+    /// The usual comping range of the left hand, from C3 to C5.
+    pub fn left_hand() -> Self {
+        Self::new(Note::from(-12), Note::from(12))
+    }
+
+    /// The usual range of the right hand, from C4 to C6.
+    pub fn right_hand() -> Self {
+        Self::new(Note::from(0), Note::from(24))
+    }
+
+    /// The full range of a piano keyboard.
+    pub fn piano() -> Self {
+        Self::new(Note::from(-39), Note::from(48))
+    }
+
+    pub fn low(&self) -> Note {
+        self.low
+    }
+
+    pub fn high(&self) -> Note {
+        self.high
+    }
+
+    pub fn contains(&self, note: Note) -> bool {
+        self.low <= note && note <= self.high
+    }
+
+    fn center(&self) -> i16 {
+        (i8::from(self.low) as i16 + i8::from(self.high) as i16) / 2
+    }
+}
+
+impl Default for Register {
+    fn default() -> Self {
+        Self::piano()
+    }
+}
+
+/// The different ways of voicing a chord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoicingKind {
+    /// Four voices as close as possible, inside one octave.
+    Closed,
+    /// The root and the fifth at the bottom, the rest of the chord stacked above.
+    Open,
+    /// Root, third and seventh.
+    Shell,
+    /// Bill Evans style rootless voicing, with the third at the bottom (3-5-7-9).
+    RootlessA,
+    /// Bill Evans style rootless voicing, with the seventh at the bottom (7-9-3-5).
+    RootlessB,
+    /// The closed voicing with the second voice from the top dropped an octave.
+    Drop2,
+    /// The closed voicing with the third voice from the top dropped an octave.
+    Drop3,
+    /// The closed voicing with the second and the fourth voices from the top dropped an octave.
+    Drop24,
+    /// Two hands voicing, the root and the seventh in the left hand, the rest in the right hand.
+    Spread,
+    /// Notes stacked in perfect fourths.
+    Quartal,
+    /// Three perfect fourths and a major third, as in Bill Evans' chords on "So What".
+    SoWhat,
+    /// A major triad on top of the root, the third and the seventh of a dominant chord.
+    UpperStructure(UpperStructure),
+}
+
+/// A chord with all its notes placed in a given octave, from the lowest to the highest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Voicing {
+    kind: VoicingKind,
+    symbol: String,
+    notes: Vec<Note>,
+}
+
+impl Voicing {
+    pub(crate) fn new(kind: VoicingKind, symbol: String, notes: Vec<Note>) -> Self {
+        Self {
+            kind,
+            symbol,
+            notes,
+        }
+    }
+
+    pub fn kind(&self) -> VoicingKind {
+        self.kind
+    }
+
+    /// The chord symbol the voicing stands for.
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = &Note> {
+        self.notes.iter()
+    }
+
+    pub fn lowest(&self) -> Note {
+        self.notes[0]
+    }
+
+    pub fn highest(&self) -> Note {
+        self.notes[self.notes.len() - 1]
+    }
+
+    /// The distance in semitones between the lowest and the highest note.
+    pub fn span(&self) -> u8 {
+        u8::from(self.highest() - self.lowest())
+    }
+
+    const SEPARATOR: &'static str = ", ";
+
+    fn notes_upper_hex(&self) -> String {
+        self.notes
+            .iter()
+            .map(|n| format!("{n:X}{}", n.scientific_octave()))
+            .collect::<Vec<_>>()
+            .join(Self::SEPARATOR)
+    }
+
+    fn notes_lower_hex(&self) -> String {
+        self.notes
+            .iter()
+            .map(|n| format!("{n:x}{}", n.scientific_octave()))
+            .collect::<Vec<_>>()
+            .join(Self::SEPARATOR)
+    }
+}
+
+/// Builds a closed voicing: the four voices of the chord inside one octave.
+///
+/// The ninth, when present, takes the place of the root, and the thirteenth the place of
+/// the fifth. Triads double their root.
+pub fn closed(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let notes = stack(chord.root(), &four_voices(chord));
+    build(VoicingKind::Closed, chord, notes, register)
+}
+
+/// Builds an open voicing: the root and the fifth at the bottom, then the rest of the chord
+/// stacked in thirds above them (eg. C-G-E-B for a Cmaj7).
+pub fn open(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let mut offsets = chord
+        .degrees()
+        .iter()
+        .map(|d| d.semitones())
+        .collect::<Vec<_>>();
+    if let Some(fifth) = chord.degree(Degree::Fifth) {
+        let fifth = offsets.remove(offsets.iter().position(|s| *s == fifth.semitones())?);
+        offsets.insert(1.min(offsets.len()), fifth);
+    }
+
+    let notes = stack(chord.root(), &offsets);
+    build(VoicingKind::Open, chord, notes, register)
+}
+
+/// Builds a shell voicing: root, third and seventh (1-3-7).
+pub fn shell(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let offsets = [0, third(chord), seventh(chord)];
+    let notes = stack(chord.root(), &offsets);
+    build(VoicingKind::Shell, chord, notes, register)
+}
+
+/// Builds a rootless voicing with the third at the bottom: 3-5-7-9, or 3-13-7-9 when the
+/// chord has a thirteenth.
+pub fn rootless_a(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let offsets = [third(chord), fifth(chord), seventh(chord), ninth(chord)];
+    let notes = stack(chord.root(), &offsets);
+    build(VoicingKind::RootlessA, chord, notes, register)
+}
+
+/// Builds a rootless voicing with the seventh at the bottom: 7-9-3-5, or 7-9-3-13 when the
+/// chord has a thirteenth.
+pub fn rootless_b(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let offsets = [seventh(chord), ninth(chord), third(chord), fifth(chord)];
+    let notes = stack(chord.root(), &offsets);
+    build(VoicingKind::RootlessB, chord, notes, register)
+}
+
+/// Builds a drop-2 voicing: the closed voicing with the second voice from the top dropped
+/// an octave.
+pub fn drop2(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let notes = drop(stack(chord.root(), &four_voices(chord)), &[1]);
+    build(VoicingKind::Drop2, chord, notes, register)
+}
+
+/// Builds a drop-3 voicing: the closed voicing with the third voice from the top dropped
+/// an octave.
+pub fn drop3(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let notes = drop(stack(chord.root(), &four_voices(chord)), &[2]);
+    build(VoicingKind::Drop3, chord, notes, register)
+}
+
+/// Builds a drop-2&4 voicing: the closed voicing with the second and the fourth voices from
+/// the top dropped an octave.
+pub fn drop24(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let notes = drop(stack(chord.root(), &four_voices(chord)), &[1, 3]);
+    build(VoicingKind::Drop24, chord, notes, register)
+}
+
+/// Builds a two hands voicing: the root and the seventh (or the fifth for triads) in the
+/// left hand, the rest of the chord stacked in the right hand above them.
+pub fn spread(chord: &Chords, register: &Register) -> Option<Voicing> {
+    let quality = chord.quality();
+    let has_thirteenth = chord.degree(Degree::Thirteenth).is_some();
+    let left = match chord.degree(Degree::Seventh) {
+        Some(seventh) => seventh.semitones(),
+        None => fifth(chord),
+    };
+
+    let mut offsets = vec![0, left];
+    offsets.extend(
+        chord
+            .degrees()
+            .iter()
+            .filter(|d| d.degree() != Degree::Root && d.semitones() != left)
+            .filter(|d| !(d.degree() == Degree::Fifth && has_thirteenth))
+            .filter(|d| {
+                !(d.degree() == Degree::Eleventh
+                    && d.alteration() == Alteration::Natural
+                    && matches!(quality, Quality::Major | Quality::Dominant))
+            })
+            .map(|d| d.semitones()),
+    );
+
+    let notes = stack(chord.root(), &offsets);
+    build(VoicingKind::Spread, chord, notes, register)
+}
+
+/// Builds the voicing of the given kind.
+///
+/// The quartal and the "So What" voicings are built from the root of the minor and the
+/// suspended chords, the upper structures only for the dominant chords.
+pub fn voice(chord: &Chords, kind: VoicingKind, register: &Register) -> Option<Voicing> {
+    match kind {
+        VoicingKind::Closed => closed(chord, register),
+        VoicingKind::Open => open(chord, register),
+        VoicingKind::Shell => shell(chord, register),
+        VoicingKind::RootlessA => rootless_a(chord, register),
+        VoicingKind::RootlessB => rootless_b(chord, register),
+        VoicingKind::Drop2 => drop2(chord, register),
+        VoicingKind::Drop3 => drop3(chord, register),
+        VoicingKind::Drop24 => drop24(chord, register),
+        VoicingKind::Spread => spread(chord, register),
+        VoicingKind::Quartal => match chord.quality() {
+            Quality::Minor | Quality::Suspended => quartal(chord.root(), 4, register),
+            _ => None,
+        },
+        VoicingKind::SoWhat => match chord.quality() {
+            Quality::Minor | Quality::Suspended => so_what(chord.root(), register),
+            _ => None,
+        },
+        VoicingKind::UpperStructure(structure) => match chord.quality() {
+            Quality::Dominant => upper_structure(chord.root(), structure, register),
+            _ => None,
+        },
+    }
+}
+
+/// The closed, drop-2, drop-3 or drop-2&4 voicings of the chord in all their inversions,
+/// before being placed in a register. Returns nothing for the other kinds of voicings.
+pub(crate) fn inversions(chord: &Chords, kind: VoicingKind) -> Vec<Vec<Note>> {
+    let positions: &[usize] = match kind {
+        VoicingKind::Closed => &[],
+        VoicingKind::Drop2 => &[1],
+        VoicingKind::Drop3 => &[2],
+        VoicingKind::Drop24 => &[1, 3],
+        _ => return vec![],
+    };
+
+    let voices = four_voices(chord);
+    (0..voices.len())
+        .map(|inversion| {
+            let mut offsets = voices.clone();
+            offsets.rotate_left(inversion);
+            drop(stack(chord.root(), &offsets), positions)
+        })
+        .collect()
+}
+
+//
+// The degrees of the chord used by the voicings
+//
+
+fn third(chord: &Chords) -> u8 {
+    [Degree::Third, Degree::Fourth, Degree::Second]
+        .into_iter()
+        .find_map(|d| chord.degree(d))
+        .map_or(4, |d| d.semitones())
+}
+
+fn fifth(chord: &Chords) -> u8 {
+    chord
+        .degree(Degree::Thirteenth)
+        .or_else(|| chord.degree(Degree::Fifth))
+        .map_or(7, |d| d.semitones())
+}
+
+/// The seventh of the chord, or the sixth for the chords without a seventh.
+fn seventh(chord: &Chords) -> u8 {
+    chord.degree(Degree::Seventh).map_or(9, |d| d.semitones())
+}
+
+fn ninth(chord: &Chords) -> u8 {
+    chord.degree(Degree::Ninth).map_or(2, |d| d.semitones())
+}
+
+/// The four voices of the chord, from the root up: the ninth replaces the root, the
+/// thirteenth (or the eleventh of a minor chord) replaces the fifth, triads double the root.
+fn four_voices(chord: &Chords) -> Vec<u8> {
+    let bottom = chord.degree(Degree::Ninth).map_or(0, |d| d.semitones());
+    let middle = chord
+        .degree(Degree::Thirteenth)
+        .or_else(|| {
+            chord
+                .degree(Degree::Eleventh)
+                .filter(|_| chord.quality() == Quality::Minor)
+        })
+        .or_else(|| chord.degree(Degree::Fifth))
+        .map_or(7, |d| d.semitones());
+    let top = chord.degree(Degree::Seventh).map_or(0, |d| d.semitones());
+
+    vec![bottom, third(chord), middle, top]
+}
+
+//
+// Placing the notes
+//
+
+/// Stacks the notes, given as distances from the root, each one above the previous one.
+fn stack(root: Note, offsets: &[u8]) -> Vec<Note> {
+    let root = i8::from(root.base()) as i16;
+    let mut notes: Vec<i16> = Vec::with_capacity(offsets.len());
+
+    for offset in offsets {
+        let mut note = root + *offset as i16;
+        if let Some(previous) = notes.last() {
+            while note <= *previous {
+                note += 12;
+            }
+            while note - 12 > *previous {
+                note -= 12;
+            }
+        }
+        notes.push(note);
+    }
+
+    notes.into_iter().map(|n| Note::from(n as i8)).collect()
+}
+
+/// Drops an octave the voices at the given positions, counted from the top.
+fn drop(notes: Vec<Note>, positions: &[usize]) -> Vec<Note> {
+    let len = notes.len();
+    let mut notes = notes
+        .into_iter()
+        .enumerate()
+        .map(|(i, n)| match positions.contains(&(len - 1 - i)) {
+            true => Note::from(i8::from(n) - 12),
+            false => n,
+        })
+        .collect::<Vec<_>>();
+    notes.sort();
+    notes
+}
+
+/// Returns all the ways of moving the notes by octaves so they fit in the register.
+fn placements(notes: &[Note], register: &Register) -> Vec<Vec<Note>> {
+    let (Some(low), Some(high)) = (notes.iter().min(), notes.iter().max()) else {
+        return vec![];
+    };
+    let low = i8::from(*low) as i16;
+    let high = i8::from(*high) as i16;
+
+    (-10i16..=10)
+        .map(|octaves| octaves * 12)
+        .filter(|shift| {
+            low + shift >= i8::from(register.low) as i16
+                && high + shift <= i8::from(register.high) as i16
+        })
+        .map(|shift| {
+            notes
+                .iter()
+                .map(|n| Note::from((i8::from(*n) as i16 + shift) as i8))
+                .collect()
+        })
+        .collect()
+}
+
+/// The distance between the center of the notes and the center of the register.
+fn distance_to_center(notes: &[Note], register: &Register) -> i16 {
+    let low = notes.iter().min().map_or(0, |n| i8::from(*n) as i16);
+    let high = notes.iter().max().map_or(0, |n| i8::from(*n) as i16);
+    ((low + high) / 2 - register.center()).abs()
+}
+
+/// Moves the notes by octaves so they fit in the register, as close as possible to its center.
+fn fit(notes: &[Note], register: &Register) -> Option<Vec<Note>> {
+    placements(notes, register)
+        .into_iter()
+        .min_by_key(|notes| distance_to_center(notes, register))
+}
+
+fn build(
+    kind: VoicingKind,
+    chord: &Chords,
+    mut notes: Vec<Note>,
+    register: &Register,
+) -> Option<Voicing> {
+    notes.sort();
+    let notes = fit(&notes, register)?;
+    Some(Voicing::new(kind, chord.to_string(), notes))
+}
+
+impl Display for VoicingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoicingKind::Closed => write!(f, "closed"),
+            VoicingKind::Open => write!(f, "open"),
+            VoicingKind::Shell => write!(f, "shell"),
+            VoicingKind::RootlessA => write!(f, "rootless A"),
+            VoicingKind::RootlessB => write!(f, "rootless B"),
+            VoicingKind::Drop2 => write!(f, "drop-2"),
+            VoicingKind::Drop3 => write!(f, "drop-3"),
+            VoicingKind::Drop24 => write!(f, "drop-2&4"),
+            VoicingKind::Spread => write!(f, "spread"),
+            VoicingKind::Quartal => write!(f, "quartal"),
+            VoicingKind::SoWhat => write!(f, "So What"),
+            VoicingKind::UpperStructure(structure) => write!(f, "upper structure {structure}"),
+        }
+    }
+}
+
+impl Display for Voicing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.symbol, self.kind)
+    }
+}
+
+impl UpperHex for Voicing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let notes = self.notes_upper_hex();
+        write!(f, "{self} [{notes}]")
+    }
+}
+
+impl LowerHex for Voicing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let notes = self.notes_lower_hex();
+        write!(f, "{self} [{notes}]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{A, C, D, G};
+
+    fn numbers(voicing: &Voicing) -> Vec<i8> {
+        voicing.notes().map(|n| i8::from(*n)).collect()
+    }
+
+    #[test]
+    fn closed_voicing() {
+        let voicing = closed(&C.maj7(), &Register::right_hand()).unwrap();
+        assert_eq!(format!("{voicing:X}"), "Cmaj7 closed [C5, E5, G5, B5]");
+
+        let voicing = closed(&C.maj(), &Register::right_hand()).unwrap();
+        assert_eq!(numbers(&voicing), [0, 4, 7, 12]);
+    }
+
+    #[test]
+    fn open_voicing() {
+        let voicing = open(&C.maj7(), &Register::piano()).unwrap();
+        let notes = numbers(&voicing);
+        assert_eq!(notes[1] - notes[0], 7);
+        assert_eq!(notes[2] - notes[0], 16);
+        assert_eq!(notes[3] - notes[0], 23);
+    }
+
+    #[test]
+    fn shell_voicing() {
+        let voicing = shell(&G.dom7(), &Register::left_hand()).unwrap();
+        assert_eq!(format!("{voicing:X}"), "G7 shell [G3, B3, F4]");
+    }
+
+    #[test]
+    fn rootless_voicings() {
+        let a = rootless_a(&D.min7(), &Register::left_hand()).unwrap();
+        assert_eq!(format!("{a:x}"), "Dm7 rootless A [F3, A3, C4, E4]");
+
+        let b = rootless_b(&G.dom13(), &Register::left_hand()).unwrap();
+        assert_eq!(format!("{b:X}"), "G13 rootless B [F3, A3, B3, E4]");
+    }
+
+    #[test]
+    fn drop_voicings() {
+        let register = Register::piano();
+        let voicing = drop2(&C.maj7(), &register).unwrap();
+        let notes = numbers(&voicing);
+        assert_eq!(
+            notes.iter().map(|n| n - notes[0]).collect::<Vec<_>>(),
+            [0, 5, 9, 16]
+        );
+
+        let voicing = drop3(&C.maj7(), &register).unwrap();
+        let notes = numbers(&voicing);
+        assert_eq!(
+            notes.iter().map(|n| n - notes[0]).collect::<Vec<_>>(),
+            [0, 8, 15, 19]
+        );
+
+        let voicing = drop24(&C.maj7(), &register).unwrap();
+        let notes = numbers(&voicing);
+        assert_eq!(
+            notes.iter().map(|n| n - notes[0]).collect::<Vec<_>>(),
+            [0, 7, 16, 23]
+        );
+    }
+
+    #[test]
+    fn spread_voicing() {
+        let voicing = spread(&C.maj9(), &Register::piano()).unwrap();
+        let notes = numbers(&voicing);
+        assert_eq!(
+            notes.iter().map(|n| n - notes[0]).collect::<Vec<_>>(),
+            [0, 11, 16, 19, 26]
+        );
+    }
+
+    #[test]
+    fn register() {
+        let register = Register::new(Note::from(-24), Note::from(0));
+        let voicing = closed(&A.min7(), &register).unwrap();
+        assert!(voicing.notes().all(|n| register.contains(*n)));
+
+        let narrow = Register::new(Note::from(0), Note::from(4));
+        assert!(closed(&A.min7(), &narrow).is_none());
+    }
+}
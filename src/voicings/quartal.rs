@@ -0,0 +1,78 @@
+use super::{fit, Register, Voicing, VoicingKind};
+use crate::{Note, PERFECT_4TH};
+
+/// Builds a quartal voicing: three to five notes stacked in perfect fourths from the given
+/// bottom note, annotated with the chord it implies over that bottom note
+/// (7sus4, m11 or m11b13).
+///
+/// # Example
+/// ```
+/// use musika_rs::{voicings::{self, Register}, *};
+///
+/// let voicing = voicings::quartal(D, 4, &Register::left_hand()).unwrap();
+/// assert_eq!(voicing.symbol(), "Dm11");
+/// ```
+pub fn quartal(bottom: Note, voices: usize, register: &Register) -> Option<Voicing> {
+    let suffix = match voices {
+        3 => "7sus4",
+        4 => "m11",
+        5 => "m11b13",
+        _ => return None,
+    };
+
+    let notes = std::iter::successors(Some(bottom), |n| Some(n + PERFECT_4TH))
+        .take(voices)
+        .collect::<Vec<_>>();
+    let notes = fit(&notes, register)?;
+    Some(Voicing::new(
+        VoicingKind::Quartal,
+        format!("{}{suffix}", bottom.base()),
+        notes,
+    ))
+}
+
+/// Builds the "So What" voicing: three perfect fourths and a major third stacked from the
+/// given bottom note (eg. E-A-D-G-B), which implies a minor eleventh chord over that note.
+pub fn so_what(bottom: Note, register: &Register) -> Option<Voicing> {
+    let steps = [5, 5, 5, 4];
+    let notes = std::iter::once(bottom)
+        .chain(steps.iter().scan(i8::from(bottom), |note, step| {
+            *note += step;
+            Some(Note::from(*note))
+        }))
+        .collect::<Vec<_>>();
+    let notes = fit(&notes, register)?;
+    Some(Voicing::new(
+        VoicingKind::SoWhat,
+        format!("{}m11", bottom.base()),
+        notes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{D, E};
+
+    #[test]
+    fn quartal_voicings() {
+        let register = Register::piano();
+
+        let voicing = quartal(D, 3, &register).unwrap();
+        assert_eq!(voicing.symbol(), "D7sus4");
+        assert_eq!(voicing.notes().count(), 3);
+
+        let voicing = quartal(D, 5, &register).unwrap();
+        assert_eq!(voicing.symbol(), "Dm11b13");
+        let notes = voicing.notes().map(|n| i8::from(*n)).collect::<Vec<_>>();
+        assert!(notes.windows(2).all(|w| w[1] - w[0] == 5));
+
+        assert!(quartal(D, 6, &register).is_none());
+    }
+
+    #[test]
+    fn so_what_voicing() {
+        let voicing = so_what(E, &Register::piano()).unwrap();
+        assert_eq!(format!("{voicing:X}"), "Em11 So What [E3, A3, D4, G4, B4]");
+    }
+}
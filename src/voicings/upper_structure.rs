@@ -0,0 +1,122 @@
+use super::{fit, Register, Voicing, VoicingKind};
+use crate::{chords, Interval, Note};
+use std::fmt::Display;
+
+/// The upper-structure triads of a dominant chord, named after the degree of their root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpperStructure {
+    /// The major triad on the second degree (D/C7), which implies a 13#11.
+    II,
+    /// The major triad on the minor third (Eb/C7), which implies a 7#9.
+    FlatIII,
+    /// The major triad on the diminished fifth (Gb/C7), which implies a 7b9#11.
+    FlatV,
+    /// The major triad on the minor sixth (Ab/C7), which implies a 7#5#9.
+    FlatVI,
+    /// The major triad on the major sixth (A/C7), which implies a 13b9.
+    VI,
+}
+
+impl UpperStructure {
+    fn semitones(&self) -> u8 {
+        match self {
+            UpperStructure::II => 2,
+            UpperStructure::FlatIII => 3,
+            UpperStructure::FlatV => 6,
+            UpperStructure::FlatVI => 8,
+            UpperStructure::VI => 9,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            UpperStructure::II => "13#11",
+            UpperStructure::FlatIII => "7#9",
+            UpperStructure::FlatV => "7b9#11",
+            UpperStructure::FlatVI => "7#5#9",
+            UpperStructure::VI => "13b9",
+        }
+    }
+
+    /// The root of the triad over the dominant chord with the given root.
+    pub fn triad_root(&self, root: Note) -> Note {
+        (root + Interval::from(self.semitones())).base()
+    }
+
+    /// The slash notation of the voicing over the dominant chord with the given root
+    /// (eg. D/C7).
+    pub fn slash(&self, root: Note) -> String {
+        format!("{}/{}7", self.triad_root(root), root.base())
+    }
+}
+
+/// Builds an upper-structure voicing over the dominant chord with the given root: the root,
+/// the third and the seventh in the left hand, and the major triad of the upper structure on
+/// top of them. The voicing is annotated with the chord it implies (eg. C13#11 for D/C7).
+///
+/// # Example
+/// ```
+/// use musika_rs::{voicings::{self, Register, UpperStructure}, *};
+///
+/// let voicing = voicings::upper_structure(C, UpperStructure::II, &Register::piano()).unwrap();
+/// assert_eq!(voicing.symbol(), "C13#11");
+/// ```
+pub fn upper_structure(
+    root: Note,
+    structure: UpperStructure,
+    register: &Register,
+) -> Option<Voicing> {
+    let root = root.base();
+    let seventh = i8::from(root) + 10;
+    let mut notes = vec![root, Note::from(i8::from(root) + 4), Note::from(seventh)];
+
+    let triad = chords::maj(structure.triad_root(root));
+    let mut previous = seventh;
+    for note in chords::Chord::notes(&triad) {
+        let mut note = i8::from(*note);
+        while note <= previous {
+            note += 12;
+        }
+        notes.push(Note::from(note));
+        previous = note;
+    }
+
+    let notes = fit(&notes, register)?;
+    Some(Voicing::new(
+        VoicingKind::UpperStructure(structure),
+        format!("{root}{}", structure.suffix()),
+        notes,
+    ))
+}
+
+impl Display for UpperStructure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpperStructure::II => write!(f, "II"),
+            UpperStructure::FlatIII => write!(f, "bIII"),
+            UpperStructure::FlatV => write!(f, "bV"),
+            UpperStructure::FlatVI => write!(f, "bVI"),
+            UpperStructure::VI => write!(f, "VI"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C, D_SHARP, G};
+
+    #[test]
+    fn upper_structures() {
+        let voicing = upper_structure(C, UpperStructure::II, &Register::piano()).unwrap();
+        assert_eq!(
+            format!("{voicing:X}"),
+            "C13#11 upper structure II [C3, E3, A#3, D4, F#4, A4]"
+        );
+        assert_eq!(UpperStructure::II.slash(C), "D/C7");
+
+        let voicing = upper_structure(G, UpperStructure::FlatVI, &Register::piano()).unwrap();
+        assert_eq!(voicing.symbol(), "G7#5#9");
+        assert_eq!(UpperStructure::FlatVI.triad_root(G), D_SHARP);
+    }
+}